@@ -0,0 +1,413 @@
+//! Provides `#[derive(ApproxEq)]` for the `general::fwk::approx_eq::ApproxEq` trait.
+//!
+//! The derived implementation compares fields recursively with the tolerance it is given. Attributes:
+//! - `#[approx_eq(scalar = f32)]` on the type sets the float type of the tolerances (default `f64`);
+//! - `#[approx_eq(skip)]` on a field excludes it from the comparison;
+//! - `#[approx_eq(exact)]` on a field compares it with `PartialEq`, e.g., for integer or string fields;
+//! - `#[approx_eq(abs = ..., rel = ..., ulps = ...)]` on a field overrides the tolerance bounds for it, keeping the
+//!   `NaN` and infinity options of the tolerance given. Omitted bounds are zero.
+
+use proc_macro2::TokenStream;
+use quote::{ToTokens, format_ident, quote};
+use syn::{
+    Attribute, Data, DeriveInput, Error, Expr, Fields, Ident, Result, Type, parse_macro_input,
+    parse_quote,
+};
+
+#[proc_macro_derive(ApproxEq, attributes(approx_eq))]
+pub fn derive_approx_eq(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// How a field is compared.
+enum FieldMode {
+    Skip,
+    Exact,
+    /// With the tokens of the overridden bounds, if any.
+    Approx {
+        abs: Option<TokenStream>,
+        rel: Option<TokenStream>,
+        ulps: Option<TokenStream>,
+    },
+}
+
+fn container_scalar(attrs: &[Attribute]) -> Result<Type> {
+    let mut scalar = parse_quote!(f64);
+    for attr in attrs.iter().filter(|a| a.path().is_ident("approx_eq")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("scalar") {
+                scalar = meta.value()?.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("expected `scalar`"))
+            }
+        })?;
+    }
+    Ok(scalar)
+}
+
+const CONFLICT: &str = "`skip`, `exact` and tolerance overrides are mutually exclusive";
+
+/// Kinds of field attribute keys, which are mutually exclusive.
+#[derive(Clone, Copy, PartialEq)]
+enum ModeKey {
+    Skip,
+    Exact,
+    Override,
+}
+
+fn field_mode(attrs: &[Attribute]) -> Result<FieldMode> {
+    let mut mode = None;
+    let (mut abs, mut rel, mut ulps) = (None, None, None);
+    for attr in attrs.iter().filter(|a| a.path().is_ident("approx_eq")) {
+        attr.parse_nested_meta(|meta| {
+            let path = &meta.path;
+            let key = if path.is_ident("skip") {
+                ModeKey::Skip
+            } else if path.is_ident("exact") {
+                ModeKey::Exact
+            } else if path.is_ident("abs") {
+                abs = Some(meta.value()?.parse::<Expr>()?.into_token_stream());
+                ModeKey::Override
+            } else if path.is_ident("rel") {
+                rel = Some(meta.value()?.parse::<Expr>()?.into_token_stream());
+                ModeKey::Override
+            } else if path.is_ident("ulps") {
+                ulps = Some(meta.value()?.parse::<Expr>()?.into_token_stream());
+                ModeKey::Override
+            } else {
+                return Err(meta.error("expected `skip`, `exact`, `abs`, `rel` or `ulps`"));
+            };
+            match mode.replace(key) {
+                Some(prev) if prev != key => Err(meta.error(CONFLICT)),
+                _ => Ok(()),
+            }
+        })?;
+    }
+
+    Ok(match mode {
+        Some(ModeKey::Skip) => FieldMode::Skip,
+        Some(ModeKey::Exact) => FieldMode::Exact,
+        Some(ModeKey::Override) | None => FieldMode::Approx { abs, rel, ulps },
+    })
+}
+
+/// Comparisons and difference updates generated for some fields.
+struct FieldCode {
+    eq: Vec<TokenStream>,
+    diff: Vec<TokenStream>,
+}
+
+/// Generates the code for `fields`, with `left` and `right` giving the expressions that refer to field `i` or `ident`
+/// of each value.
+fn field_code(
+    fields: &Fields,
+    left: impl Fn(usize, &Option<Ident>) -> TokenStream,
+    right: impl Fn(usize, &Option<Ident>) -> TokenStream,
+) -> Result<FieldCode> {
+    let trait_path = quote!(::general::fwk::approx_eq::ApproxEq);
+    let mut code = FieldCode {
+        eq: Vec::new(),
+        diff: Vec::new(),
+    };
+
+    for (i, field) in fields.iter().enumerate() {
+        let (l, r) = (left(i, &field.ident), right(i, &field.ident));
+        match field_mode(&field.attrs)? {
+            FieldMode::Skip => (),
+            FieldMode::Exact => {
+                code.eq.push(quote!(#l == #r));
+                code.diff.push(quote! {
+                    if #l != #r {
+                        return ::core::option::Option::None;
+                    }
+                });
+            }
+            FieldMode::Approx { abs, rel, ulps } => {
+                let eq = if abs.is_none() && rel.is_none() && ulps.is_none() {
+                    quote!(#trait_path::approx_eq_with(#l, #r, tol))
+                } else {
+                    let zero = quote!(::core::default::Default::default());
+                    let abs = abs.unwrap_or(zero.clone());
+                    let rel = rel.unwrap_or(zero);
+                    let ulps = ulps.map_or(
+                        quote!(::core::option::Option::None),
+                        |e| quote!(::core::option::Option::Some(#e)),
+                    );
+                    quote! {
+                        #trait_path::approx_eq_with(#l, #r, &::general::fwk::approx_eq::Tolerance {
+                            abs: #abs,
+                            rel: #rel,
+                            ulps: #ulps,
+                            ..*tol
+                        })
+                    }
+                };
+                code.eq.push(eq);
+                code.diff.push(quote! {
+                    max = max.max(#trait_path::abs_diff(#l, #r)?);
+                });
+            }
+        }
+    }
+    Ok(code)
+}
+
+/// Identifier that binds field `i` or `ident` of a variant in a pattern, e.g., `l_x` or `l_0` for prefix `l`.
+fn binding(prefix: &str, i: usize, ident: &Option<Ident>) -> Ident {
+    match ident {
+        Some(ident) => format_ident!("{prefix}_{ident}"),
+        None => format_ident!("{prefix}_{i}"),
+    }
+}
+
+/// Pattern binding the fields of a variant to identifiers with `prefix`.
+fn fields_pattern(fields: &Fields, prefix: &str) -> TokenStream {
+    match fields {
+        Fields::Named(named) => {
+            let bindings = named.named.iter().enumerate().map(|(i, f)| {
+                let ident = &f.ident;
+                let b = binding(prefix, i, ident);
+                quote!(#ident: #b)
+            });
+            quote!({ #(#bindings),* })
+        }
+        Fields::Unnamed(unnamed) => {
+            let bindings = (0..unnamed.unnamed.len()).map(|i| binding(prefix, i, &None));
+            quote!(( #(#bindings),* ))
+        }
+        Fields::Unit => quote!(),
+    }
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let scalar = container_scalar(&input.attrs)?;
+
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(parse_quote!(::general::fwk::approx_eq::ApproxEq<Scalar = #scalar>));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let (eq_body, diff_body) = match &input.data {
+        Data::Struct(data) => {
+            let member = |i: usize, ident: &Option<Ident>| match ident {
+                Some(ident) => quote!(#ident),
+                None => {
+                    let index = syn::Index::from(i);
+                    quote!(#index)
+                }
+            };
+            let code = field_code(
+                &data.fields,
+                |i, ident| {
+                    let m = member(i, ident);
+                    quote!(&self.#m)
+                },
+                |i, ident| {
+                    let m = member(i, ident);
+                    quote!(&other.#m)
+                },
+            )?;
+            let (eq, diff) = (code.eq, code.diff);
+            (
+                quote!(true #(&& #eq)*),
+                quote! {
+                    let mut max = 0_f64;
+                    #(#diff)*
+                    ::core::option::Option::Some(max)
+                },
+            )
+        }
+        Data::Enum(data) => {
+            let mut eq_arms = Vec::new();
+            let mut diff_arms = Vec::new();
+            for variant in &data.variants {
+                let v = &variant.ident;
+                let left_pat = fields_pattern(&variant.fields, "l");
+                let right_pat = fields_pattern(&variant.fields, "r");
+                let code = field_code(
+                    &variant.fields,
+                    |i, ident| binding("l", i, ident).into_token_stream(),
+                    |i, ident| binding("r", i, ident).into_token_stream(),
+                )?;
+                let (eq, diff) = (code.eq, code.diff);
+                eq_arms.push(quote! {
+                    (Self::#v #left_pat, Self::#v #right_pat) => true #(&& #eq)*,
+                });
+                diff_arms.push(quote! {
+                    (Self::#v #left_pat, Self::#v #right_pat) => {
+                        let mut max = 0_f64;
+                        #(#diff)*
+                        ::core::option::Option::Some(max)
+                    }
+                });
+            }
+            (
+                quote! {
+                    #[allow(unused_variables)]
+                    match (self, other) {
+                        #(#eq_arms)*
+                        _ => false,
+                    }
+                },
+                quote! {
+                    #[allow(unused_variables, unused_mut)]
+                    match (self, other) {
+                        #(#diff_arms)*
+                        _ => ::core::option::Option::None,
+                    }
+                },
+            )
+        }
+        Data::Union(data) => {
+            return Err(Error::new_spanned(
+                data.union_token,
+                "`ApproxEq` cannot be derived for unions",
+            ));
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::general::fwk::approx_eq::ApproxEq for #name #ty_generics #where_clause {
+            type Scalar = #scalar;
+
+            #[allow(unused_variables)]
+            fn approx_eq_with(
+                &self,
+                other: &Self,
+                tol: &::general::fwk::approx_eq::Tolerance<#scalar>,
+            ) -> bool {
+                #eq_body
+            }
+
+            #[allow(unused_mut)]
+            fn abs_diff(&self, other: &Self) -> ::core::option::Option<f64> {
+                #diff_body
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Mode of field `x` with attributes `attrs`.
+    fn mode_of(attrs: TokenStream) -> Result<FieldMode> {
+        let input: DeriveInput = parse_quote!(struct S { #attrs x: f64 });
+        match &input.data {
+            Data::Struct(data) => field_mode(&data.fields.iter().next().unwrap().attrs),
+            _ => unreachable!(),
+        }
+    }
+
+    fn scalar_of(input: DeriveInput) -> String {
+        container_scalar(&input.attrs)
+            .unwrap()
+            .into_token_stream()
+            .to_string()
+    }
+
+    fn error_of<T>(res: Result<T>) -> String {
+        res.err().expect("expected an error").to_string()
+    }
+
+    #[test]
+    fn test_container_scalar() {
+        assert_eq!(
+            scalar_of(parse_quote!(
+                struct S;
+            )),
+            "f64"
+        );
+        assert_eq!(
+            scalar_of(parse_quote!(
+                #[approx_eq(scalar = f32)]
+                struct S;
+            )),
+            "f32"
+        );
+
+        let input: DeriveInput = parse_quote!(
+            #[approx_eq(skip)]
+            struct S;
+        );
+        assert_eq!(
+            error_of(container_scalar(&input.attrs)),
+            "expected `scalar`"
+        );
+    }
+
+    #[test]
+    fn test_field_mode() {
+        assert!(matches!(
+            mode_of(quote!()),
+            Ok(FieldMode::Approx {
+                abs: None,
+                rel: None,
+                ulps: None
+            })
+        ));
+        assert!(matches!(
+            mode_of(quote!(#[approx_eq(skip)])),
+            Ok(FieldMode::Skip)
+        ));
+        assert!(matches!(
+            mode_of(quote!(#[approx_eq(exact)] #[approx_eq(exact)])),
+            Ok(FieldMode::Exact)
+        ));
+        match mode_of(quote!(#[approx_eq(abs = 1e-3, ulps = 4)])).unwrap() {
+            FieldMode::Approx { abs, rel, ulps } => {
+                assert_eq!(abs.unwrap().to_string(), "1e-3");
+                assert!(rel.is_none());
+                assert_eq!(ulps.unwrap().to_string(), "4");
+            }
+            _ => panic!("expected tolerance overrides"),
+        }
+        // Attributes of other derives are ignored.
+        assert!(matches!(
+            mode_of(quote!(#[serde(skip)])),
+            Ok(FieldMode::Approx { .. })
+        ));
+    }
+
+    #[test]
+    fn test_field_mode_errors() {
+        assert_eq!(
+            error_of(mode_of(quote!(#[approx_eq(tol = 1.)]))),
+            "expected `skip`, `exact`, `abs`, `rel` or `ulps`"
+        );
+        for attrs in [
+            quote!(#[approx_eq(skip, exact)]),
+            quote!(#[approx_eq(skip)] #[approx_eq(exact)]),
+            quote!(#[approx_eq(exact, rel = 0.1)]),
+            quote!(#[approx_eq(abs = 0.1, skip)]),
+        ] {
+            assert_eq!(error_of(mode_of(attrs.clone())), CONFLICT, "{attrs}");
+        }
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        let input: DeriveInput = parse_quote!(union U { a: f64, b: u64 });
+        assert_eq!(
+            error_of(expand(input)),
+            "`ApproxEq` cannot be derived for unions"
+        );
+
+        // Field errors are reported for enum variants too.
+        let input: DeriveInput = parse_quote! {
+            enum E {
+                A(#[approx_eq(skip, exact)] f64),
+            }
+        };
+        assert_eq!(error_of(expand(input)), CONFLICT);
+    }
+}
@@ -0,0 +1,254 @@
+//! Functions that do a significant amount of computation, and their calibration to a target latency.
+
+use super::latency_m;
+use sha2::{Digest, Sha256};
+use std::{hint::black_box, time::Duration};
+
+/// Function that does a significant amount of computation to support validation of benchmarking frameworks.
+/// `effort` is the number of iterations that determines the amount of work performed.
+pub fn busy_work_sha(effort: u32) {
+    let extent = black_box(effort);
+    let seed = black_box(0_u64);
+    let buf = seed.to_be_bytes();
+    let mut hasher = Sha256::new();
+    for _ in 0..extent {
+        hasher.update(buf);
+    }
+    let hash = hasher.finalize();
+    black_box(hash);
+}
+
+/// Function that does a significant amount of computation to support validation of benchmarking frameworks.
+/// `effort` is the number of iterations that determines the amount of work performed.
+pub fn busy_work_umul(effort: u32) {
+    let extent = black_box(effort);
+    let mut v: u64;
+    for _ in 0..extent {
+        v = black_box(u64::MAX).wrapping_mul(black_box(black_box(u64::MAX)));
+        black_box(v);
+    }
+}
+
+/// Function that does a significant amount of computation to support validation of benchmarking frameworks.
+/// `effort` is the number of iterations that determines the amount of work performed.
+pub fn busy_work_fmul(effort: u32) {
+    const F: f64 = 0.5;
+    let extent = black_box(effort);
+    let mut vf = F;
+    for _ in 0..extent {
+        vf = black_box(((1. + vf) * (1. + vf)).fract());
+    }
+    black_box(vf);
+}
+
+/// Function that does a significant amount of computation to support validation of benchmarking frameworks.
+/// `effort` is the number of iterations that determines the amount of work performed.
+pub fn busy_work_exp(effort: u32) {
+    const M: u64 = 7;
+    let extent = black_box(effort);
+    let mut v = M as f64;
+    for _ in 0..extent {
+        let ve = v.exp();
+        let vei = ve.floor();
+        let vef = ve - vei;
+        let vem = vei as u64 % M + 1;
+        v = vem as f64 + vef;
+    }
+    black_box(v);
+}
+
+/// Effort that scales `effort`, which has latency `latency`, to `target_latency`, assuming proportionality.
+fn scaled_effort(effort: u32, latency: Duration, target_latency: Duration) -> u32 {
+    let nanos = latency.as_nanos().max(1);
+    let scaled = target_latency.as_nanos() * effort as u128 / nanos;
+    scaled.clamp(1, u32::MAX as u128) as u32
+}
+
+/// Returns an estimate of the number of iterations required for `busy_work` to have latency `target_latency`.
+///
+/// Calls [`calibrate_busy_work_x`] with predefined default `calibration_effort` and `reps` values.
+pub fn calibrate_busy_work(busy_work: fn(u32), target_latency: Duration) -> u32 {
+    const CALIBRATION_EFFORT: u32 = 100_000;
+    const REPS: usize = 0;
+    calibrate_busy_work_x(busy_work, target_latency, CALIBRATION_EFFORT, REPS)
+}
+
+/// Returns an estimate of the number of iterations required for `busy_work` to have latency `target_latency`,
+/// based on a single proportional extrapolation. See [`CalibratedWork::calibrate`] for an iterative calibration.
+///
+/// # Arguments
+/// - `busy_work`: function to be calibrated.
+/// - `target_latency`: target latency.
+/// - `calibration_effort`: the number of iterations executed during calibration.
+/// - `reps`: the number of times the calibration is run. The median calibration is returned.
+pub fn calibrate_busy_work_x(
+    busy_work: fn(u32),
+    target_latency: Duration,
+    calibration_effort: u32,
+    reps: usize,
+) -> u32 {
+    let latency = latency_m(|| busy_work(calibration_effort), reps);
+    scaled_effort(calibration_effort, latency, target_latency)
+}
+
+/// Parameters of [`CalibratedWork::calibrate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationParams {
+    /// Effort used for the warm-up runs and as the starting point of the calibration.
+    pub initial_effort: u32,
+    /// Number of runs executed, and discarded, before calibration starts.
+    pub warm_up_runs: u32,
+    /// Number of runs whose median latency is taken at each calibration step.
+    pub reps: usize,
+    /// Maximum relative deviation of the measured latency from the target latency for the calibration to converge.
+    pub tolerance: f64,
+    /// Maximum number of calibration steps.
+    pub max_steps: u32,
+}
+
+impl Default for CalibrationParams {
+    fn default() -> Self {
+        Self {
+            initial_effort: 100_000,
+            warm_up_runs: 3,
+            reps: 11,
+            tolerance: 0.02,
+            max_steps: 10,
+        }
+    }
+}
+
+/// Busy work function calibrated to a target latency.
+#[derive(Debug, Clone, Copy)]
+pub struct CalibratedWork {
+    pub busy_work: fn(u32),
+    /// Effort that yields the [`Self::achieved_latency`].
+    pub effort: u32,
+    pub target_latency: Duration,
+    /// Median latency measured with [`Self::effort`] in the last calibration step.
+    pub achieved_latency: Duration,
+    /// Number of calibration steps executed.
+    pub steps: u32,
+    /// Whether the achieved latency is within the requested tolerance of the target latency.
+    pub converged: bool,
+}
+
+impl CalibratedWork {
+    /// Calibrates `busy_work` to `target_latency` iteratively: after warm-up runs, the effort is repeatedly rescaled
+    /// by the ratio of the target latency to the median measured latency until the latter is within
+    /// `params.tolerance` of the target or `params.max_steps` is reached.
+    pub fn calibrate(
+        busy_work: fn(u32),
+        target_latency: Duration,
+        params: &CalibrationParams,
+    ) -> CalibratedWork {
+        for _ in 0..params.warm_up_runs {
+            busy_work(params.initial_effort);
+        }
+        Self::calibrate_with(busy_work, target_latency, params, |effort| {
+            latency_m(|| busy_work(effort), params.reps)
+        })
+    }
+
+    /// Iterative rescaling of [`Self::calibrate`], with `measure` returning the latency of `busy_work` for an effort.
+    fn calibrate_with(
+        busy_work: fn(u32),
+        target_latency: Duration,
+        params: &CalibrationParams,
+        mut measure: impl FnMut(u32) -> Duration,
+    ) -> CalibratedWork {
+        let mut effort = params.initial_effort.max(1);
+        let mut steps = 0;
+        loop {
+            let achieved_latency = measure(effort);
+            steps += 1;
+            let mut work = CalibratedWork {
+                busy_work,
+                effort,
+                target_latency,
+                achieved_latency,
+                steps,
+                converged: false,
+            };
+            work.converged = work.rel_error().abs() <= params.tolerance;
+            if work.converged || steps >= params.max_steps {
+                return work;
+            }
+            effort = scaled_effort(effort, achieved_latency, target_latency);
+        }
+    }
+
+    /// Relative deviation of the achieved latency from the target latency.
+    pub fn rel_error(&self) -> f64 {
+        let target = self.target_latency.as_nanos() as f64;
+        (self.achieved_latency.as_nanos() as f64 - target) / target
+    }
+
+    /// Runs the busy work with the calibrated effort.
+    pub fn run(&self) {
+        (self.busy_work)(self.effort)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scaled_effort() {
+        let ms = Duration::from_millis;
+        assert_eq!(scaled_effort(1000, ms(2), ms(1)), 500);
+        assert_eq!(scaled_effort(1000, ms(1), ms(3)), 3000);
+        assert_eq!(scaled_effort(1, ms(10), ms(1)), 1);
+        assert_eq!(scaled_effort(u32::MAX, Duration::ZERO, ms(1)), u32::MAX);
+    }
+
+    /// Deterministic latency model with a fixed overhead, so that proportional rescaling needs several steps.
+    fn model_latency(effort: u32) -> Duration {
+        Duration::from_nanos(5_000 + 3 * effort as u64)
+    }
+
+    #[test]
+    fn test_calibrate_with() {
+        let params = CalibrationParams::default();
+        let target = Duration::from_micros(500);
+        let work = CalibratedWork::calibrate_with(busy_work_umul, target, &params, model_latency);
+        assert!(work.converged, "{work:?}");
+        assert!(work.steps > 1 && work.steps <= params.max_steps, "{work:?}");
+        assert_eq!(work.achieved_latency, model_latency(work.effort));
+        assert!(work.rel_error().abs() <= params.tolerance, "{work:?}");
+
+        // Without enough steps, the calibration stops at the last effort without converging.
+        let params = CalibrationParams {
+            max_steps: 1,
+            ..Default::default()
+        };
+        let work = CalibratedWork::calibrate_with(busy_work_umul, target, &params, model_latency);
+        assert!(!work.converged, "{work:?}");
+        assert_eq!((work.steps, work.effort), (1, params.initial_effort));
+        assert_eq!(work.achieved_latency, model_latency(params.initial_effort));
+    }
+
+    #[test]
+    #[ignore = "depends on wall-clock timing, run the busy_work binary instead"]
+    fn test_calibrate() {
+        let params = CalibrationParams {
+            initial_effort: 1000,
+            tolerance: 0.25,
+            ..Default::default()
+        };
+        let target = Duration::from_micros(500);
+        let work = CalibratedWork::calibrate(busy_work_umul, target, &params);
+        assert!(work.converged, "{work:?}");
+        assert!(work.steps >= 1 && work.steps <= params.max_steps);
+        assert!(work.rel_error().abs() <= params.tolerance, "{work:?}");
+
+        // The calibrated effort reproduces the target latency when measured independently of the calibration.
+        let measured = latency_m(|| work.run(), params.reps).as_nanos() as f64;
+        let rel_error = (measured - target.as_nanos() as f64) / target.as_nanos() as f64;
+        assert!(
+            rel_error.abs() <= params.tolerance,
+            "measured={measured}ns, {work:?}"
+        );
+    }
+}
@@ -0,0 +1,12 @@
+//! Support for the validation of benchmarking frameworks: latency measurement, calibrated busy work, synthetic
+//! workloads, and a benchmark runner.
+
+mod busy_work;
+mod runner;
+mod timing;
+mod workload;
+
+pub use busy_work::*;
+pub use runner::*;
+pub use timing::*;
+pub use workload::*;
@@ -0,0 +1,252 @@
+//! Minimal benchmark runner with warm-up, outlier detection, and statistical reporting.
+
+use super::{latency, latency_m};
+use crate::{
+    fwk::{select::select_nth_many_by, sort::introsort_by},
+    stats::{StatsError, Summary, SummaryParams, sorted_quantiles},
+};
+use rand::{SeedableRng, rngs::StdRng};
+use std::{fmt::Write, time::Duration};
+
+/// Rule for the detection of outlier samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutlierRule {
+    /// Keep all samples.
+    Keep,
+    /// Tukey's fences: samples more than `k` interquartile ranges below the first or above the third quartile are
+    /// outliers. `k` is usually `1.5`.
+    Iqr(f64),
+    /// Samples whose modified z-score `0.6745 * (x - median) / MAD` exceeds the threshold in absolute value are
+    /// outliers, where MAD is the median absolute deviation. The threshold is usually `3.5`.
+    Mad(f64),
+}
+
+/// Parameters of a [`Runner`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunnerParams {
+    /// Number of discarded runs before sampling.
+    pub warm_up: usize,
+    /// Number of samples collected.
+    pub samples: usize,
+    /// Number of runs per sample. Each sample is the median latency of its runs.
+    pub reps: usize,
+    pub outliers: OutlierRule,
+    pub summary: SummaryParams,
+    /// Seed of the random number generator used for bootstrapping.
+    pub seed: u64,
+}
+
+impl Default for RunnerParams {
+    fn default() -> Self {
+        Self {
+            warm_up: 10,
+            samples: 100,
+            reps: 1,
+            outliers: OutlierRule::Iqr(1.5),
+            summary: SummaryParams::default(),
+            seed: 42,
+        }
+    }
+}
+
+/// Result of benchmarking one variant. Latencies are in nanoseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub name: String,
+    /// Samples retained after outlier removal, in ascending order.
+    pub samples: Vec<f64>,
+    /// Number of samples removed as outliers.
+    pub outliers: usize,
+    pub summary: Summary,
+}
+
+/// Benchmarks closures and collects their results for side-by-side comparison.
+#[derive(Debug, Clone)]
+pub struct Runner {
+    params: RunnerParams,
+    results: Vec<BenchResult>,
+}
+
+/// Splits ascending `sorted` samples into retained samples and the number of outliers according to `rule`.
+pub fn remove_outliers(sorted: &[f64], rule: OutlierRule) -> Result<(Vec<f64>, usize), StatsError> {
+    let (lower, upper) = match rule {
+        OutlierRule::Keep => return Ok((sorted.to_vec(), 0)),
+        OutlierRule::Iqr(k) => {
+            let qs = sorted_quantiles(sorted, &[0.25, 0.75])?;
+            let iqr = qs[1] - qs[0];
+            (qs[0] - k * iqr, qs[1] + k * iqr)
+        }
+        OutlierRule::Mad(threshold) => {
+            let median = sorted_quantiles(sorted, &[0.5])?[0];
+            let mut abs_devs = sorted
+                .iter()
+                .map(|x| (x - median).abs())
+                .collect::<Vec<_>>();
+            // The absolute deviations are not sorted, so select their two middle values instead of sorting them.
+            let n = abs_devs.len();
+            let middle = select_nth_many_by(&mut abs_devs, &[(n - 1) / 2, n / 2], f64::total_cmp)
+                .expect("ranks are less than the number of samples");
+            let mad = (middle[0] + middle[1]) / 2.;
+            if mad == 0. {
+                return Ok((sorted.to_vec(), 0));
+            }
+            let half_width = threshold * mad / 0.6745;
+            (median - half_width, median + half_width)
+        }
+    };
+
+    let retained = sorted
+        .iter()
+        .copied()
+        .filter(|x| (lower..=upper).contains(x))
+        .collect::<Vec<_>>();
+    let outliers = sorted.len() - retained.len();
+    Ok((retained, outliers))
+}
+
+fn fmt_nanos(nanos: f64) -> String {
+    format!("{:.2?}", Duration::from_nanos(nanos.max(0.) as u64))
+}
+
+impl Runner {
+    pub fn new(params: RunnerParams) -> Runner {
+        Runner {
+            params,
+            results: Vec::new(),
+        }
+    }
+
+    pub fn results(&self) -> &[BenchResult] {
+        &self.results
+    }
+
+    /// Benchmarks `f` under `name` and returns its result, which is also kept for [`Self::table`].
+    pub fn bench(&mut self, name: &str, f: impl Fn()) -> Result<&BenchResult, StatsError> {
+        for _ in 0..self.params.warm_up {
+            latency(&f);
+        }
+
+        let mut samples = (0..self.params.samples)
+            .map(|_| latency_m(&f, self.params.reps).as_nanos() as f64)
+            .collect::<Vec<_>>();
+        introsort_by(&mut samples, f64::total_cmp);
+
+        let (samples, outliers) = remove_outliers(&samples, self.params.outliers)?;
+        let mut rng = StdRng::seed_from_u64(self.params.seed);
+        let summary = Summary::from_sorted_sample(&samples, &self.params.summary, &mut rng)?;
+
+        self.results.push(BenchResult {
+            name: name.to_owned(),
+            samples,
+            outliers,
+            summary,
+        });
+        Ok(self.results.last().expect("result was just pushed"))
+    }
+
+    /// Returns a Markdown table comparing all benchmarked variants.
+    pub fn table(&self) -> String {
+        let level = self.params.summary.level * 100.;
+        let mut header = vec![
+            "variant".to_owned(),
+            "n".to_owned(),
+            "outliers".to_owned(),
+            "mean".to_owned(),
+            format!("mean {level}% CI"),
+            "stdev".to_owned(),
+            "median".to_owned(),
+            format!("median {level}% CI"),
+        ];
+        header.extend(
+            self.params
+                .summary
+                .quantiles
+                .iter()
+                .map(|q| format!("p{}", q * 100.)),
+        );
+
+        let mut table = String::new();
+        writeln!(table, "| {} |", header.join(" | ")).unwrap();
+        writeln!(table, "|---|{}", "---:|".repeat(header.len() - 1)).unwrap();
+        for r in &self.results {
+            let s = &r.summary;
+            let mut row = vec![
+                r.name.clone(),
+                s.moments.n.to_string(),
+                r.outliers.to_string(),
+                fmt_nanos(s.moments.mean),
+                format!(
+                    "[{}, {}]",
+                    fmt_nanos(s.mean_ci.lower),
+                    fmt_nanos(s.mean_ci.upper)
+                ),
+                fmt_nanos(s.moments.stdev),
+                fmt_nanos(s.median),
+                format!(
+                    "[{}, {}]",
+                    fmt_nanos(s.median_ci.lower),
+                    fmt_nanos(s.median_ci.upper)
+                ),
+            ];
+            row.extend(s.quantiles.iter().map(|(_, v)| fmt_nanos(*v)));
+            writeln!(table, "| {} |", row.join(" | ")).unwrap();
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_remove_outliers() {
+        let mut sample = (1..=20).map(|x| x as f64).collect::<Vec<_>>();
+        sample.push(1000.);
+
+        let (kept, outliers) = remove_outliers(&sample, OutlierRule::Keep).unwrap();
+        assert_eq!((kept.len(), outliers), (21, 0));
+
+        let (kept, outliers) = remove_outliers(&sample, OutlierRule::Iqr(1.5)).unwrap();
+        assert_eq!(outliers, 1);
+        assert_eq!(kept.last(), Some(&20.));
+
+        let (kept, outliers) = remove_outliers(&sample, OutlierRule::Mad(3.5)).unwrap();
+        assert_eq!(outliers, 1);
+        assert_eq!(kept.last(), Some(&20.));
+
+        let constant = [5.; 10];
+        let (kept, outliers) = remove_outliers(&constant, OutlierRule::Mad(3.5)).unwrap();
+        assert_eq!((kept.len(), outliers), (10, 0));
+    }
+
+    #[test]
+    fn test_runner() {
+        let params = RunnerParams {
+            warm_up: 2,
+            samples: 20,
+            summary: SummaryParams {
+                resamples: 100,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut runner = Runner::new(params);
+        runner.bench("noop", || ()).unwrap();
+        runner
+            .bench("sleep", || std::thread::sleep(Duration::from_micros(100)))
+            .unwrap();
+
+        let results = runner.results();
+        assert_eq!(results.len(), 2);
+        assert!(results[1].summary.median >= 100_000.);
+        assert!(results[0].summary.median < results[1].summary.median);
+        for r in results {
+            assert_eq!(r.samples.len() + r.outliers, 20);
+        }
+
+        let table = runner.table();
+        assert_eq!(table.lines().count(), 4);
+        assert!(table.contains("| sleep |"));
+    }
+}
@@ -0,0 +1,30 @@
+use std::time::{Duration, Instant};
+
+/// Invokes `f` once and returns its latency.
+#[inline(always)]
+pub fn latency(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    Instant::now().duration_since(start)
+}
+
+/// Invokes `f` `reps` times and returns the median latency. Invokes `f` once if `reps` is 0.
+///
+/// The latencies are collected on the heap, so `reps` can be arbitrarily large.
+pub fn latency_m(f: impl Fn(), reps: usize) -> Duration {
+    if reps <= 1 {
+        return latency(&f);
+    }
+
+    let mut lats = (0..reps).map(|_| latency(&f)).collect::<Vec<_>>();
+    lats.sort_unstable();
+
+    if reps % 2 == 1 {
+        lats[reps / 2]
+    } else {
+        let m1 = lats[reps / 2 - 1].as_nanos();
+        let m2 = lats[reps / 2].as_nanos();
+        let m = (m1 + m2) / 2;
+        Duration::from_nanos(m as u64)
+    }
+}
@@ -0,0 +1,504 @@
+//! Synthetic workloads whose latencies follow known distributions, to validate latency measurement frameworks.
+//!
+//! A [`Workload`] injects latencies sampled from a [`LatencyDist`] by running calibrated busy work (CPU-bound) or
+//! by sleeping (suspend-bound), optionally inside nested `tracing` spans. The injected latencies are returned as a
+//! histogram that [`validate`] compares with the histogram measured by the framework under test, e.g., the span
+//! histograms recorded by the [`Latencies`] layer in [`Workload::run_traced`].
+
+use super::CalibratedWork;
+use crate::latency::{Latencies, LatencyReport, measure_latencies_local};
+use hdrhistogram::Histogram;
+use rand::Rng;
+use rand_distr::{Distribution, Exp, LogNormal, Normal};
+use std::{thread, time::Duration};
+use thiserror::Error;
+use tracing::{Instrument, trace_span};
+
+/// Name of the span around each invocation of a [`Workload`].
+pub const WORKLOAD_SPAN: &str = "synthetic_workload";
+
+/// Name of the span nested within [`WORKLOAD_SPAN`] when [`Workload::nested`] is set.
+pub const INNER_SPAN: &str = "synthetic_inner";
+
+/// Distribution of injected latencies. Sampled values below zero are clamped to zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LatencyDist {
+    Constant(Duration),
+    Uniform {
+        low: Duration,
+        high: Duration,
+    },
+    Normal {
+        mean: Duration,
+        stdev: Duration,
+    },
+    /// Log-normal distribution with the given median, where `sigma` is the standard deviation of the logarithm.
+    LogNormal {
+        median: Duration,
+        sigma: f64,
+    },
+    Exponential {
+        mean: Duration,
+    },
+    /// Mixture of two normal distributions with the same standard deviation, where the mode at `high` is chosen
+    /// with probability `p_high`.
+    Bimodal {
+        low: Duration,
+        high: Duration,
+        stdev: Duration,
+        p_high: f64,
+    },
+}
+
+impl LatencyDist {
+    /// Draws a latency from the distribution.
+    ///
+    /// # Panics
+    /// - If the distribution parameters are invalid, e.g., a negative `sigma` or a `p_high` outside `[0, 1]`.
+    pub fn sample(&self, rng: &mut impl Rng) -> Duration {
+        let nanos = |d: &Duration| d.as_nanos() as f64;
+        let normal = |mean: &Duration, stdev: &Duration| {
+            Normal::new(nanos(mean), nanos(stdev)).expect("invalid normal distribution parameters")
+        };
+
+        let sampled_nanos = match self {
+            LatencyDist::Constant(d) => nanos(d),
+            LatencyDist::Uniform { low, high } => rng.gen_range(nanos(low)..=nanos(high)),
+            LatencyDist::Normal { mean, stdev } => normal(mean, stdev).sample(rng),
+            LatencyDist::LogNormal { median, sigma } => LogNormal::new(nanos(median).ln(), *sigma)
+                .expect("invalid log-normal distribution parameters")
+                .sample(rng),
+            LatencyDist::Exponential { mean } => Exp::new(1. / nanos(mean))
+                .expect("invalid exponential distribution parameters")
+                .sample(rng),
+            LatencyDist::Bimodal {
+                low,
+                high,
+                stdev,
+                p_high,
+            } => {
+                let mean = if rng.gen_bool(*p_high) { high } else { low };
+                normal(mean, stdev).sample(rng)
+            }
+        };
+
+        Duration::from_nanos(sampled_nanos.max(0.) as u64)
+    }
+}
+
+/// How a [`Workload`] spends the injected latency.
+#[derive(Debug, Clone, Copy)]
+pub enum WorkKind {
+    /// Busy work with effort scaled linearly from the calibration.
+    Cpu(CalibratedWork),
+    /// Sleep, i.e., [`thread::sleep`] in sync code and [`tokio::time::sleep`] in async code.
+    Sleep,
+}
+
+/// Workload with latencies following `dist`.
+#[derive(Debug, Clone, Copy)]
+pub struct Workload {
+    pub dist: LatencyDist,
+    pub kind: WorkKind,
+    /// Whether each invocation runs in a `synthetic_inner` span nested within the `synthetic_workload` span.
+    /// Otherwise, only the `synthetic_workload` span is created.
+    pub nested: bool,
+}
+
+/// Result of [`Workload::run_traced`], with all histograms in microseconds.
+#[derive(Debug, Clone)]
+pub struct TracedRun {
+    /// Injected latencies.
+    pub injected: Histogram<u64>,
+    /// Total times recorded for the `synthetic_workload` span.
+    pub workload: Histogram<u64>,
+    /// Total times recorded for the `synthetic_inner` span, if the workload is nested.
+    pub inner: Option<Histogram<u64>>,
+}
+
+/// Creates the histogram for injected latencies, in microseconds.
+fn new_histogram() -> Histogram<u64> {
+    let mut hist = Histogram::new_with_bounds(1, 60 * 1000 * 1000, 3).expect("bounds are valid");
+    hist.auto(true);
+    hist
+}
+
+impl Workload {
+    /// Spends `latency` according to the [`WorkKind`], without creating spans.
+    fn spend(&self, latency: Duration) {
+        match self.kind {
+            WorkKind::Cpu(work) => {
+                let scale = latency.as_nanos() as f64 / work.target_latency.as_nanos() as f64;
+                (work.busy_work)((work.effort as f64 * scale).round() as u32);
+            }
+            WorkKind::Sleep => thread::sleep(latency),
+        }
+    }
+
+    /// Runs one invocation with the given `latency`, within the workload's spans.
+    pub fn run_once(&self, latency: Duration) {
+        trace_span!(WORKLOAD_SPAN).in_scope(|| {
+            if self.nested {
+                trace_span!(INNER_SPAN).in_scope(|| self.spend(latency));
+            } else {
+                self.spend(latency);
+            }
+        });
+    }
+
+    /// Async version of [`Self::run_once`].
+    pub async fn run_once_async(&self, latency: Duration) {
+        let spend = async {
+            match self.kind {
+                WorkKind::Cpu(_) => self.spend(latency),
+                WorkKind::Sleep => tokio::time::sleep(latency).await,
+            }
+        };
+
+        let inner = async {
+            if self.nested {
+                spend.instrument(trace_span!(INNER_SPAN)).await;
+            } else {
+                spend.await;
+            }
+        };
+
+        inner.instrument(trace_span!(WORKLOAD_SPAN)).await;
+    }
+
+    /// Runs `n` invocations with latencies sampled with `rng` and returns the histogram of the injected latencies,
+    /// in microseconds.
+    pub fn run(&self, n: usize, rng: &mut impl Rng) -> Histogram<u64> {
+        let mut hist = new_histogram();
+        for _ in 0..n {
+            let latency = self.dist.sample(rng);
+            self.run_once(latency);
+            hist.record(latency.as_micros() as u64)
+                .expect("histogram auto-resizes");
+        }
+        hist
+    }
+
+    /// Async version of [`Self::run`].
+    pub async fn run_async(&self, n: usize, rng: &mut (impl Rng + Send)) -> Histogram<u64> {
+        let mut hist = new_histogram();
+        for _ in 0..n {
+            let latency = self.dist.sample(rng);
+            self.run_once_async(latency).await;
+            hist.record(latency.as_micros() as u64)
+                .expect("histogram auto-resizes");
+        }
+        hist
+    }
+
+    /// Runs `n` invocations as [`Self::run`] does, but under a [`Latencies`] layer whose histograms have `sigfig`
+    /// significant figures, and returns the injected latencies together with the span latencies it recorded.
+    pub fn run_traced(&self, n: usize, rng: &mut (impl Rng + Send), sigfig: u8) -> TracedRun {
+        self.traced(sigfig, || self.run(n, rng))
+    }
+
+    /// Async version of [`Self::run_traced`], which drives [`Self::run_async`] on a current-thread [tokio]
+    /// runtime, so that all the spans are created on the thread where the layer is installed.
+    pub fn run_traced_async(&self, n: usize, rng: &mut (impl Rng + Send), sigfig: u8) -> TracedRun {
+        self.traced(sigfig, || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(self.run_async(n, rng))
+        })
+    }
+
+    /// Runs `f`, which returns the injected latencies, under a new [`Latencies`] layer with `sigfig` significant
+    /// figures.
+    fn traced(&self, sigfig: u8, f: impl FnOnce() -> Histogram<u64> + Send) -> TracedRun {
+        let latencies = Latencies::with_significant_figures(sigfig);
+        let injected = measure_latencies_local(&latencies, f);
+        let report = latencies.report();
+        let inner = self.nested.then(|| span_total_time(&report, INNER_SPAN));
+        TracedRun {
+            injected,
+            workload: span_total_time(&report, WORKLOAD_SPAN),
+            inner,
+        }
+    }
+}
+
+/// Combined total times of the callsites of this module's spans named `name` in `report`.
+fn span_total_time(report: &LatencyReport, name: &str) -> Histogram<u64> {
+    let mut hist = new_histogram();
+    for (key, span) in &report.spans {
+        if key.target == module_path!() && key.name == name {
+            hist.add(&span.total_time).expect("histogram auto-resizes");
+        }
+    }
+    hist
+}
+
+//=================
+// Validation
+
+/// Comparison of a measured statistic with the injected one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deviation {
+    pub expected: f64,
+    pub measured: f64,
+    /// `(measured - expected) / expected`.
+    pub rel_error: f64,
+}
+
+impl Deviation {
+    fn new(expected: f64, measured: f64) -> Deviation {
+        Deviation {
+            expected,
+            measured,
+            rel_error: (measured - expected) / expected,
+        }
+    }
+}
+
+/// Error returned by [`validate`].
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ValidationError {
+    #[error("expected {statistic} is zero, so the relative error of the measured one is undefined")]
+    ZeroExpected { statistic: String },
+}
+
+/// Result of [`validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validation {
+    pub mean: Deviation,
+    /// Deviations by quantile.
+    pub quantiles: Vec<(f64, Deviation)>,
+    pub tolerance: f64,
+    /// Whether all relative errors are within the tolerance.
+    pub passed: bool,
+}
+
+/// Checks whether `measured` recovers the injected distribution in `expected`: the means and the quantiles `qs`
+/// must agree within relative `tolerance`. Both histograms must use the same unit.
+///
+/// Measurement overhead biases the measured latencies upwards, so the tolerance should allow for it, especially for
+/// short latencies.
+///
+/// Fails if the expected mean or one of the expected quantiles is zero, e.g., for latencies shorter than the unit.
+pub fn validate(
+    expected: &Histogram<u64>,
+    measured: &Histogram<u64>,
+    qs: &[f64],
+    tolerance: f64,
+) -> Result<Validation, ValidationError> {
+    let deviation = |statistic: &dyn Fn() -> String, expected: f64, measured: f64| {
+        if expected == 0. {
+            return Err(ValidationError::ZeroExpected {
+                statistic: statistic(),
+            });
+        }
+        Ok(Deviation::new(expected, measured))
+    };
+
+    let mean = deviation(&|| "mean".to_owned(), expected.mean(), measured.mean())?;
+    let quantiles = qs
+        .iter()
+        .map(|q| {
+            let dev = deviation(
+                &|| format!("quantile {q}"),
+                expected.value_at_quantile(*q) as f64,
+                measured.value_at_quantile(*q) as f64,
+            )?;
+            Ok((*q, dev))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let passed = std::iter::once(&mean)
+        .chain(quantiles.iter().map(|(_, dev)| dev))
+        .all(|dev| dev.rel_error.abs() <= tolerance);
+
+    Ok(Validation {
+        mean,
+        quantiles,
+        tolerance,
+        passed,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bench::latency;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    fn sample_mean_micros(dist: LatencyDist, n: usize) -> f64 {
+        let mut rng = StdRng::seed_from_u64(7);
+        let sum: f64 = (0..n)
+            .map(|_| dist.sample(&mut rng).as_micros() as f64)
+            .sum();
+        sum / n as f64
+    }
+
+    #[test]
+    fn test_sample_means() {
+        let us = Duration::from_micros;
+        let cases = [
+            (LatencyDist::Constant(us(500)), 500.),
+            (
+                LatencyDist::Uniform {
+                    low: us(100),
+                    high: us(300),
+                },
+                200.,
+            ),
+            (
+                LatencyDist::Normal {
+                    mean: us(1000),
+                    stdev: us(100),
+                },
+                1000.,
+            ),
+            // Mean of log-normal is median * exp(sigma^2 / 2).
+            (
+                LatencyDist::LogNormal {
+                    median: us(1000),
+                    sigma: 0.5,
+                },
+                1000. * (0.125_f64).exp(),
+            ),
+            (LatencyDist::Exponential { mean: us(400) }, 400.),
+            (
+                LatencyDist::Bimodal {
+                    low: us(100),
+                    high: us(1000),
+                    stdev: us(10),
+                    p_high: 0.25,
+                },
+                325.,
+            ),
+        ];
+        for (dist, expected) in cases {
+            let mean = sample_mean_micros(dist, 20_000);
+            assert!(
+                (mean - expected).abs() / expected < 0.03,
+                "{dist:?}: mean={mean}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut expected = new_histogram();
+        let mut close = new_histogram();
+        let mut far = new_histogram();
+        for v in 1..=1000 {
+            expected.record(v).unwrap();
+            close.record(v + v / 50).unwrap();
+            far.record(2 * v).unwrap();
+        }
+
+        let qs = [0.5, 0.9, 0.99];
+        let res = validate(&expected, &close, &qs, 0.05).unwrap();
+        assert!(res.passed, "{res:?}");
+        assert_eq!(res.quantiles.len(), 3);
+
+        let res = validate(&expected, &far, &qs, 0.05).unwrap();
+        assert!(!res.passed, "{res:?}");
+        assert!((res.mean.rel_error - 1.).abs() < 0.01, "{res:?}");
+
+        // Sub-microsecond latencies are recorded as zero, for which relative errors are undefined.
+        let mut zeros = new_histogram();
+        zeros.record_n(0, 10).unwrap();
+        assert_eq!(
+            validate(&zeros, &close, &qs, 0.05),
+            Err(ValidationError::ZeroExpected {
+                statistic: "mean".to_owned()
+            })
+        );
+        zeros.record_n(100, 10).unwrap();
+        assert_eq!(
+            validate(&zeros, &close, &qs, 0.05),
+            Err(ValidationError::ZeroExpected {
+                statistic: "quantile 0.5".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn test_run() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let workload = Workload {
+            dist: LatencyDist::Uniform {
+                low: Duration::from_micros(100),
+                high: Duration::from_micros(200),
+            },
+            kind: WorkKind::Sleep,
+            nested: true,
+        };
+
+        let mut measured = new_histogram();
+        let injected = (0..5)
+            .map(|_| {
+                let d = workload.dist.sample(&mut rng);
+                measured
+                    .record(latency(|| workload.run_once(d)).as_micros() as u64)
+                    .unwrap();
+                d
+            })
+            .collect::<Vec<_>>();
+        // Sleeps never undershoot.
+        assert!(measured.min() >= injected.iter().min().unwrap().as_micros() as u64);
+
+        let hist = workload.run(5, &mut rng);
+        assert_eq!(hist.len(), 5);
+    }
+
+    #[test]
+    fn test_run_traced() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let workload = Workload {
+            dist: LatencyDist::Uniform {
+                low: Duration::from_micros(1000),
+                high: Duration::from_micros(2000),
+            },
+            kind: WorkKind::Sleep,
+            nested: true,
+        };
+
+        let run = workload.run_traced(10, &mut rng, 3);
+        assert_eq!(run.injected.len(), 10);
+        assert_eq!(run.workload.len(), 10);
+        let inner = run.inner.unwrap();
+        assert_eq!(inner.len(), 10);
+        // Sleeps never undershoot, and the outer span contains the inner one.
+        assert!(inner.min() >= run.injected.min());
+        assert!(run.workload.max() >= inner.max());
+
+        // Each run uses its own layer.
+        let flat = Workload {
+            nested: false,
+            ..workload
+        };
+        let run = flat.run_traced(5, &mut rng, 3);
+        assert_eq!(run.workload.len(), 5);
+        assert!(run.inner.is_none());
+    }
+
+    #[test]
+    fn test_run_traced_async() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let workload = Workload {
+            dist: LatencyDist::Uniform {
+                low: Duration::from_micros(1000),
+                high: Duration::from_micros(2000),
+            },
+            kind: WorkKind::Sleep,
+            nested: true,
+        };
+
+        let run = workload.run_traced_async(10, &mut rng, 3);
+        assert_eq!(run.injected.len(), 10);
+        assert_eq!(run.workload.len(), 10);
+        let inner = run.inner.unwrap();
+        assert_eq!(inner.len(), 10);
+        // Sleeps never undershoot, and the outer span contains the inner one.
+        assert!(inner.min() >= run.injected.min());
+        assert!(run.workload.max() >= inner.max());
+    }
+}
@@ -1,16 +1,4 @@
-macro_rules! compose0 {
-    ($h:ident, $s:ty, $u:ty, $f:ident, $g:ident) => {
-        fn $h(x: $s) -> $u {
-            $g($f(x))
-        }
-    };
-}
-
-macro_rules! compose {
-    ($f:ident, $g:ident) => {
-        |x| $g($f(x))
-    };
-}
+use general::{compose, fwk::compose::AndThen, pipe};
 
 fn add_one(x: i32) -> i32 {
     x + 1
@@ -20,13 +8,23 @@ fn to_string(x: i32) -> String {
     x.to_string()
 }
 
-compose0!(comp, i32, String, add_one, to_string);
+fn comp(x: i32) -> String {
+    compose!(to_string, add_one)(x)
+}
 
 fn main() {
     assert_eq!(comp(5), "6");
     println!("comp(5)={}", comp(5));
 
-    let c: fn(i32) -> String = compose!(add_one, to_string);
+    let c: fn(i32) -> String = |x| pipe!(x => add_one, to_string);
     assert_eq!(c(5), "6");
     println!("c(5)={}", c(5));
+
+    let d = pipe!(add_one, |x| x * 10, to_string);
+    assert_eq!(d(5), "60");
+    println!("d(5)={}", d(5));
+
+    let e = add_one.and_then(add_one).and_then(to_string);
+    assert_eq!(e(5), "7");
+    println!("e(5)={}", e(5));
 }
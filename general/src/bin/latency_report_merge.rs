@@ -0,0 +1,50 @@
+//! Merges latency reports exported by the `latency_trace` binary, e.g., from benchmarks sharded across processes.
+//!
+//! Usage: `cargo run --bin latency_report_merge -- <output file> <input file>...`
+
+use anyhow::{Context, Result, bail};
+use general::latency::LatencyReport;
+use std::{fs::File, io::BufReader};
+
+fn read_report(path: &str) -> Result<LatencyReport> {
+    let file = File::open(path).with_context(|| format!("unable to open {path}"))?;
+    let report = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("unable to parse latency report {path}"))?;
+    Ok(report)
+}
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let Some(out_path) = args.next() else {
+        bail!("usage: latency_report_merge <output file> <input file>...");
+    };
+    let in_paths = args.collect::<Vec<_>>();
+    if in_paths.is_empty() {
+        bail!("at least one input file is required");
+    }
+
+    let mut merged = LatencyReport::new();
+    for path in &in_paths {
+        let report = read_report(path)?;
+        merged
+            .merge(&report)
+            .with_context(|| format!("unable to merge {path}"))?;
+    }
+
+    let file = File::create(&out_path).with_context(|| format!("unable to create {out_path}"))?;
+    serde_json::to_writer_pretty(file, &merged)?;
+
+    println!("Merged {} reports into {out_path}:", in_paths.len());
+    for (key, v) in &merged.spans {
+        println!(
+            "  span={}, total_time_count={}, median_total_time={}μs, active_time_count={}, median_active_time={}μs",
+            key,
+            v.total_time.len(),
+            v.total_time.value_at_quantile(0.5),
+            v.active_time.len(),
+            v.active_time.value_at_quantile(0.5)
+        );
+    }
+
+    Ok(())
+}
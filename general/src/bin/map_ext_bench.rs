@@ -0,0 +1,154 @@
+//! Compares the borrowing, consuming and in-place variants of the `general::fwk::map_ext::MapExt` operations on a
+//! `HashMap<String, Vec<u64>>` of per-key latencies, by time and by heap allocations.
+//!
+//! Execute it by running:
+//! ```
+//! cargo run -r --bin map_ext_bench -- [keys] [samples]
+//! ```
+//! with `keys` the number of map entries (default 10,000, with 100 latencies each) and `samples` the number of
+//! timed runs per variant (default 20). Each timed run includes cloning the input map, which is reported as the
+//! `clone` baseline. The allocations are counted for the operation alone, excluding the clone.
+
+use general::{
+    bench::{OutlierRule, Runner, RunnerParams},
+    fwk::map_ext::MapExt,
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    collections::HashMap,
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// System allocator that counts allocations and allocated bytes.
+struct CountingAlloc;
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+type Data = HashMap<String, Vec<u64>>;
+type Op = fn(Data) -> Data;
+
+/// Number of allocations and allocated bytes during `f`.
+fn count_allocs(f: impl FnOnce()) -> (usize, usize) {
+    let (allocs, bytes) = (
+        ALLOCS.load(Ordering::Relaxed),
+        BYTES.load(Ordering::Relaxed),
+    );
+    f();
+    (
+        ALLOCS.load(Ordering::Relaxed) - allocs,
+        BYTES.load(Ordering::Relaxed) - bytes,
+    )
+}
+
+fn double(v: &mut [u64]) {
+    v.iter_mut().for_each(|x| *x *= 2);
+}
+
+fn is_slow(v: &[u64]) -> bool {
+    v.iter().sum::<u64>() > 50 * 1_000_000
+}
+
+fn cmd_line_args() -> (usize, usize) {
+    let mut args = std::env::args().skip(1);
+    let keys = args
+        .next()
+        .map(|s| s.parse().expect("keys must be a non-negative integer"))
+        .unwrap_or(10_000);
+    let samples = args
+        .next()
+        .map(|s| s.parse().expect("samples must be a positive integer"))
+        .unwrap_or(20);
+    (keys, samples)
+}
+
+fn main() {
+    let (keys, samples) = cmd_line_args();
+    println!("keys={keys}, samples={samples}");
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let data = (0..keys)
+        .map(|i| {
+            let latencies = (0..100).map(|_| rng.gen_range(0..1_000_000)).collect();
+            (format!("span-{i}"), latencies)
+        })
+        .collect::<Data>();
+
+    // Each operation takes ownership of a clone of `data`, so the borrowing variants drop their input afterwards.
+    let variants: [(&str, Op); 7] = [
+        ("clone", |m| m),
+        ("map_values", |m| {
+            m.map_values(|v| {
+                let mut v = v.clone();
+                double(&mut v);
+                v
+            })
+        }),
+        ("into_map_values", |m| {
+            m.into_map_values(|mut v| {
+                double(&mut v);
+                v
+            })
+        }),
+        ("map_values_in_place", |mut m| {
+            m.map_values_in_place(|_, v| double(v));
+            m
+        }),
+        ("filter", |m| m.filter(|_, v| is_slow(v))),
+        ("into_filter", |m| m.into_filter(|_, v| is_slow(v))),
+        ("retain_map", |mut m| {
+            m.retain_map(|_, v| is_slow(v));
+            m
+        }),
+    ];
+
+    println!("\n|variant|allocations|allocated bytes|");
+    println!("|-|-:|-:|");
+    for (name, op) in variants {
+        let input = data.clone();
+        let mut output = None;
+        let (allocs, bytes) = count_allocs(|| output = Some(op(input)));
+        black_box(output);
+        println!("|{name}|{allocs}|{bytes}|");
+    }
+
+    let params = RunnerParams {
+        warm_up: 1,
+        samples,
+        outliers: OutlierRule::Keep,
+        ..Default::default()
+    };
+    let mut runner = Runner::new(params);
+    for (name, op) in variants {
+        runner
+            .bench(name, || {
+                black_box(op(data.clone()));
+            })
+            .expect("enough samples");
+    }
+
+    println!("\n{}", runner.table());
+}
@@ -0,0 +1,73 @@
+//! Compares the parallel sorts in `general::fwk::par_sort` with their sequential versions and the standard library
+//! sorts, on random `u64` latencies.
+//!
+//! Execute it by running:
+//! ```
+//! cargo run -r --bin par_sort_bench -- [len] [samples]
+//! ```
+//! with `len` the number of items sorted (default 1,000,000) and `samples` the number of timed sorts per variant
+//! (default 20). Each timed run includes cloning the unsorted input, which is reported as the `clone` baseline.
+
+use general::{
+    bench::{OutlierRule, Runner, RunnerParams},
+    fwk::{
+        merge_sort::merge_sort,
+        par_sort::{par_introsort, par_merge_sort},
+        sort::introsort,
+    },
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+type Sort = fn(&mut [u64]);
+
+fn cmd_line_args() -> (usize, usize) {
+    let mut args = std::env::args().skip(1);
+    let len = args
+        .next()
+        .map(|s| s.parse().expect("len must be a non-negative integer"))
+        .unwrap_or(1_000_000);
+    let samples = args
+        .next()
+        .map(|s| s.parse().expect("samples must be a positive integer"))
+        .unwrap_or(20);
+    (len, samples)
+}
+
+fn main() {
+    let (len, samples) = cmd_line_args();
+    println!("len={len}, samples={samples}");
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let data = (0..len)
+        .map(|_| rng.gen_range(0..1_000_000_000))
+        .collect::<Vec<u64>>();
+
+    let variants: [(&str, Sort); 7] = [
+        ("clone", |_| ()),
+        ("slice::sort_unstable", |a| a.sort_unstable()),
+        ("slice::sort", |a| a.sort()),
+        ("introsort", introsort),
+        ("par_introsort", par_introsort),
+        ("merge_sort", merge_sort),
+        ("par_merge_sort", par_merge_sort),
+    ];
+
+    let params = RunnerParams {
+        warm_up: 1,
+        samples,
+        outliers: OutlierRule::Keep,
+        ..Default::default()
+    };
+    let mut runner = Runner::new(params);
+    for (name, sort) in variants {
+        runner
+            .bench(name, || {
+                let mut a = data.clone();
+                sort(&mut a);
+                debug_assert!(a.is_sorted());
+            })
+            .expect("enough samples");
+    }
+
+    println!("\n{}", runner.table());
+}
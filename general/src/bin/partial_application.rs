@@ -1,6 +1,12 @@
 //! Examples of partial application. See `async-borrow.rs` for a more complex scenario involving closures
 //! with higher-rank trait bounds (https://doc.rust-lang.org/nomicon/hrtb.html).
+//!
+//! The hand-written functions below are generalized by `general::fwk::partial`, whose use is shown at the end.
 
+use general::{
+    fwk::partial::{PartialApply2, PartialApply3},
+    partial, partial_async,
+};
 use std::{future::Future, time::Duration};
 
 /// This works for both regular and async functions.
@@ -72,4 +78,24 @@ async fn main() {
     let f_part = partial_application_async(f_a_r2, 60);
     let res = f_part(&2).await;
     println!("{res}");
+
+    let f_part = f.bind(20);
+    let res = f_part(2);
+    println!("{res}");
+
+    let f_part = f_a.bind_async(40);
+    let res = tokio::spawn(f_part(2)).await.unwrap();
+    println!("{res}");
+
+    let f_part = partial_async!(f_a_r1, ref 60, _);
+    let res = tokio::spawn(f_part(2)).await.unwrap();
+    println!("{res}");
+
+    let f_part = partial_async!(f_a_r2, 60, ref 2);
+    let res = f_part().await;
+    println!("{res}");
+
+    let g = |x: u64, y: u64, z: u64| x * y + z;
+    let res = g.bind(3).bind(4)(5) + partial!(g, _, 4, _)(3, 5);
+    println!("{res}");
 }
@@ -1,12 +1,7 @@
+use general::pin_async_fn;
 use std::time::Duration;
 use tokio::time::sleep;
 
-macro_rules! pin_async_fn {
-    ($f:ident) => {
-        |s| Box::pin($f(s))
-    };
-}
-
 async fn bar_a_bf(sleep_millis: u64) -> String {
     sleep(Duration::from_millis(sleep_millis)).await;
 
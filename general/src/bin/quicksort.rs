@@ -1,3 +1,5 @@
+//! Demonstrates [`quicksort`]. Run with `--features sort-trace` to print the sorting steps.
+
 use general::fwk::quicksort::quicksort;
 
 fn main() {
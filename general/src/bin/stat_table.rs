@@ -0,0 +1,278 @@
+//! Generates critical-value tables for the Student's t, chi-squared, F and normal distributions.
+//! Generalizes `students_t_table_statrs` and `students_t_table_distrs`, reporting values with a positive sign
+//! (upper tail), see [`SamplingDist::critical_value`].
+//!
+//! Usage: `cargo run --bin stat_table -- [options]`, with options:
+//! - `--dist t|chi2|f|normal` (default `t`);
+//! - `--dfs <list>` degrees of freedom, e.g., `1-30` or `1,2,5-10,20` (default `1-30`);
+//! - `--dfs2 <list>` denominator degrees of freedom for the F distribution (default `1-30`);
+//! - `--alphas <list>` significance levels, e.g., `0.05,0.01` (default `0.1,0.05,0.025,0.01,0.005,0.001,0.0005`);
+//! - `--tails one|two` (default `one`);
+//! - `--format md|csv|json` (default `md`);
+//! - `--help` prints the usage.
+
+use anyhow::{Context, Result, bail};
+use general::stats::{SamplingDist, Tails};
+use serde::Serialize;
+
+const USAGE: &str = "\
+Usage: stat_table [options]
+
+Options:
+  --dist t|chi2|f|normal  distribution (default t)
+  --dfs <list>            degrees of freedom, e.g., 1-30 or 1,2,5-10,20 (default 1-30)
+  --dfs2 <list>           denominator degrees of freedom for the F distribution (default 1-30)
+  --alphas <list>         significance levels, e.g., 0.05,0.01 (default 0.1,0.05,0.025,0.01,0.005,0.001,0.0005)
+  --tails one|two         one- or two-tailed critical values (default one)
+  --format md|csv|json    output format (default md)
+  --help                  prints this message";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DistKind {
+    StudentsT,
+    ChiSquared,
+    F,
+    Normal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Markdown,
+    Csv,
+    Json,
+}
+
+#[derive(Debug)]
+struct Args {
+    dist: DistKind,
+    dfs: Vec<u32>,
+    dfs2: Vec<u32>,
+    alphas: Vec<f64>,
+    tails: Tails,
+    format: Format,
+}
+
+#[derive(Debug, Serialize)]
+struct Row {
+    df: Option<u32>,
+    df2: Option<u32>,
+    values: Vec<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Table {
+    distribution: &'static str,
+    tails: Tails,
+    alphas: Vec<f64>,
+    rows: Vec<Row>,
+}
+
+/// Parses a comma-separated list of values and inclusive ranges such as `1,2,5-10`. Ranges must not be reversed.
+fn parse_dfs(s: &str) -> Result<Vec<u32>> {
+    let mut dfs = Vec::new();
+    for item in s.split(',') {
+        match item.split_once('-') {
+            Some((lo, hi)) => {
+                let lo: u32 = lo
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("invalid range {item}"))?;
+                let hi: u32 = hi
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("invalid range {item}"))?;
+                if lo > hi {
+                    bail!("invalid range {item}: start is greater than end");
+                }
+                dfs.extend(lo..=hi);
+            }
+            None => dfs.push(
+                item.trim()
+                    .parse()
+                    .with_context(|| format!("invalid df {item}"))?,
+            ),
+        }
+    }
+    Ok(dfs)
+}
+
+fn parse_alphas(s: &str) -> Result<Vec<f64>> {
+    s.split(',')
+        .map(|a| {
+            a.trim()
+                .parse()
+                .with_context(|| format!("invalid alpha {a}"))
+        })
+        .collect()
+}
+
+/// Returns the parsed command line arguments, or [`None`] if the usage was requested.
+fn cmd_line_args() -> Result<Option<Args>> {
+    let mut args = Args {
+        dist: DistKind::StudentsT,
+        dfs: (1..=30).collect(),
+        dfs2: (1..=30).collect(),
+        alphas: vec![0.1, 0.05, 0.025, 0.01, 0.005, 0.001, 0.0005],
+        tails: Tails::One,
+        format: Format::Markdown,
+    };
+
+    let mut iter = std::env::args().skip(1);
+    while let Some(opt) = iter.next() {
+        if opt == "--help" || opt == "-h" {
+            return Ok(None);
+        }
+        let Some(value) = iter.next() else {
+            bail!("missing value for option {opt}");
+        };
+        match opt.as_str() {
+            "--dist" => {
+                args.dist = match value.as_str() {
+                    "t" => DistKind::StudentsT,
+                    "chi2" => DistKind::ChiSquared,
+                    "f" => DistKind::F,
+                    "normal" => DistKind::Normal,
+                    _ => bail!("unknown distribution {value}"),
+                }
+            }
+            "--dfs" => args.dfs = parse_dfs(&value)?,
+            "--dfs2" => args.dfs2 = parse_dfs(&value)?,
+            "--alphas" => args.alphas = parse_alphas(&value)?,
+            "--tails" => {
+                args.tails = match value.as_str() {
+                    "one" => Tails::One,
+                    "two" => Tails::Two,
+                    _ => bail!("unknown tails {value}"),
+                }
+            }
+            "--format" => {
+                args.format = match value.as_str() {
+                    "md" => Format::Markdown,
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    _ => bail!("unknown format {value}"),
+                }
+            }
+            _ => bail!("unknown option {opt}"),
+        }
+    }
+
+    Ok(Some(args))
+}
+
+fn make_table(args: &Args) -> Result<Table> {
+    let params: Vec<(Option<u32>, Option<u32>, SamplingDist)> = match args.dist {
+        DistKind::StudentsT => args
+            .dfs
+            .iter()
+            .map(|df| (Some(*df), None, SamplingDist::StudentsT { df: *df as f64 }))
+            .collect(),
+        DistKind::ChiSquared => args
+            .dfs
+            .iter()
+            .map(|df| (Some(*df), None, SamplingDist::ChiSquared { df: *df as f64 }))
+            .collect(),
+        DistKind::F => args
+            .dfs
+            .iter()
+            .flat_map(|df1| {
+                args.dfs2.iter().map(move |df2| {
+                    let dist = SamplingDist::F {
+                        df1: *df1 as f64,
+                        df2: *df2 as f64,
+                    };
+                    (Some(*df1), Some(*df2), dist)
+                })
+            })
+            .collect(),
+        DistKind::Normal => vec![(None, None, SamplingDist::Normal)],
+    };
+
+    let rows = params
+        .into_iter()
+        .map(|(df, df2, dist)| {
+            let values = args
+                .alphas
+                .iter()
+                .map(|alpha| dist.critical_value(*alpha, args.tails))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Row { df, df2, values })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let distribution = match args.dist {
+        DistKind::StudentsT => "students_t",
+        DistKind::ChiSquared => "chi_squared",
+        DistKind::F => "f",
+        DistKind::Normal => "normal",
+    };
+
+    Ok(Table {
+        distribution,
+        tails: args.tails,
+        alphas: args.alphas.clone(),
+        rows,
+    })
+}
+
+/// Header and row cells, with the df columns that apply to the distribution.
+fn cells(table: &Table) -> (Vec<String>, Vec<Vec<String>>) {
+    let has_df = table.rows.iter().any(|r| r.df.is_some());
+    let has_df2 = table.rows.iter().any(|r| r.df2.is_some());
+
+    let mut header = Vec::new();
+    if has_df {
+        header.push("df".to_owned());
+    }
+    if has_df2 {
+        header.push("df2".to_owned());
+    }
+    header.extend(table.alphas.iter().map(|a| a.to_string()));
+
+    let rows = table
+        .rows
+        .iter()
+        .map(|r| {
+            let mut row = Vec::new();
+            row.extend(r.df.map(|df| df.to_string()));
+            row.extend(r.df2.map(|df| df.to_string()));
+            row.extend(r.values.iter().map(|v| format!("{v:.4}")));
+            row
+        })
+        .collect();
+
+    (header, rows)
+}
+
+fn print_markdown(table: &Table) {
+    let (header, rows) = cells(table);
+    println!("| {} |", header.join(" | "));
+    println!("|{}", "---:|".repeat(header.len()));
+    for row in rows {
+        println!("| {} |", row.join(" | "));
+    }
+}
+
+fn print_csv(table: &Table) {
+    let (header, rows) = cells(table);
+    println!("{}", header.join(","));
+    for row in rows {
+        println!("{}", row.join(","));
+    }
+}
+
+fn main() -> Result<()> {
+    let Some(args) = cmd_line_args()? else {
+        println!("{USAGE}");
+        return Ok(());
+    };
+    let table = make_table(&args)?;
+
+    match args.format {
+        Format::Markdown => print_markdown(&table),
+        Format::Csv => print_csv(&table),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&table)?),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+//! Runs synthetic workloads with known latency distributions under the [`Latencies`](general::latency::Latencies)
+//! layer and checks that the latencies it records for the `synthetic_workload` and `synthetic_inner` spans recover
+//! the injected ones, both in sync code and in async code running on [tokio].
+//!
+//! The tokio timer has a resolution of one millisecond and rounds sleeps up, so async sleeps, and hence the latencies
+//! recorded for them, overshoot the injected latencies by up to a millisecond.
+//!
+//! Execute it by running:
+//! ```
+//! cargo run -r --bin workload_validation
+//! ```
+
+use general::bench::{
+    CalibratedWork, CalibrationParams, LatencyDist, WorkKind, Workload, busy_work_fmul, validate,
+};
+use hdrhistogram::Histogram;
+use rand::{SeedableRng, rngs::StdRng};
+use std::time::Duration;
+
+const N: usize = 200;
+const QS: [f64; 3] = [0.5, 0.9, 0.99];
+const TOLERANCE: f64 = 0.1;
+/// Significant figures of the layer's histograms, as for the injected latencies.
+const SIGFIG: u8 = 3;
+
+fn print_validation(name: &str, expected: &Histogram<u64>, measured: &Histogram<u64>) {
+    match validate(expected, measured, &QS, TOLERANCE) {
+        Ok(res) => println!(
+            "  {name}: passed={}, mean_rel_error={:.4}, quantile_rel_errors={:?}",
+            res.passed,
+            res.mean.rel_error,
+            res.quantiles
+                .iter()
+                .map(|(q, dev)| format!("p{}={:.4}", q * 100., dev.rel_error))
+                .collect::<Vec<_>>()
+        ),
+        Err(e) => println!("  {name}: {e}"),
+    }
+}
+
+fn main() {
+    let us = Duration::from_micros;
+    let dists = [
+        LatencyDist::Constant(us(2000)),
+        LatencyDist::Uniform {
+            low: us(1000),
+            high: us(3000),
+        },
+        LatencyDist::Normal {
+            mean: us(2000),
+            stdev: us(300),
+        },
+        LatencyDist::LogNormal {
+            median: us(2000),
+            sigma: 0.4,
+        },
+        LatencyDist::Exponential { mean: us(2000) },
+        LatencyDist::Bimodal {
+            low: us(1000),
+            high: us(4000),
+            stdev: us(200),
+            p_high: 0.3,
+        },
+    ];
+
+    let work = CalibratedWork::calibrate(busy_work_fmul, us(2000), &CalibrationParams::default());
+    println!(
+        "calibrated busy work: effort={}, rel_error={:.4}, converged={}",
+        work.effort,
+        work.rel_error(),
+        work.converged
+    );
+
+    let mut rng = StdRng::seed_from_u64(42);
+    for (kind_name, kind) in [("cpu", WorkKind::Cpu(work)), ("sleep", WorkKind::Sleep)] {
+        for (mode, is_async) in [("sync", false), ("async", true)] {
+            for dist in dists {
+                let workload = Workload {
+                    dist,
+                    kind,
+                    nested: true,
+                };
+                let run = if is_async {
+                    workload.run_traced_async(N, &mut rng, SIGFIG)
+                } else {
+                    workload.run_traced(N, &mut rng, SIGFIG)
+                };
+                println!("{mode} {kind_name} {dist:?}:");
+                print_validation("synthetic_workload", &run.injected, &run.workload);
+                if let Some(inner) = &run.inner {
+                    print_validation("synthetic_inner", &run.injected, inner);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,424 @@
+//! Composable aggregators for grouping and summarizing the items of iterators and maps.
+//!
+//! An [`Aggregator`] creates an accumulator with a seed factory, adds items to it, and finishes it into an output.
+//! The built-in aggregators are [`count`], [`sum`], [`min`], [`max`], [`mean`], [`histogram`], [`to_vec`] and
+//! [`fold`]. [`map_items`] transforms the items before aggregating them, and [`group_btree`] and [`group_hash`]
+//! aggregate the items of each group separately, producing nested maps when they are nested.
+//!
+//! Iterators aggregate their items with [`AggregateExt::aggregate`], and maps aggregate groups of their values with
+//! [`MapExt::group_aggregate`](super::map_ext::MapExt::group_aggregate).
+
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    marker::PhantomData,
+    ops::AddAssign,
+};
+
+/// Aggregation of items of type `T` into an output of type [`Self::Output`].
+pub trait Aggregator<T> {
+    /// Accumulated state.
+    type Acc;
+    /// Result of the aggregation.
+    type Output;
+
+    /// Accumulator for no items.
+    fn seed(&mut self) -> Self::Acc;
+
+    /// Adds `item` to `acc`.
+    fn add(&mut self, acc: &mut Self::Acc, item: T);
+
+    /// Output for the items added to `acc`.
+    fn finish(&mut self, acc: Self::Acc) -> Self::Output;
+}
+
+//=================
+// Built-in aggregators
+
+/// See [`count`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Count;
+
+/// Number of items.
+pub fn count() -> Count {
+    Count
+}
+
+impl<T> Aggregator<T> for Count {
+    type Acc = usize;
+    type Output = usize;
+
+    fn seed(&mut self) -> usize {
+        0
+    }
+
+    fn add(&mut self, acc: &mut usize, _item: T) {
+        *acc += 1;
+    }
+
+    fn finish(&mut self, acc: usize) -> usize {
+        acc
+    }
+}
+
+/// See [`sum`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sum<N>(PhantomData<N>);
+
+/// Sum of the items as an `N`, e.g., `sum::<u64>()` for items of type `u64` or `&u64`.
+pub fn sum<N>() -> Sum<N> {
+    Sum(PhantomData)
+}
+
+impl<T, N: Default + AddAssign<T>> Aggregator<T> for Sum<N> {
+    type Acc = N;
+    type Output = N;
+
+    fn seed(&mut self) -> N {
+        N::default()
+    }
+
+    fn add(&mut self, acc: &mut N, item: T) {
+        *acc += item;
+    }
+
+    fn finish(&mut self, acc: N) -> N {
+        acc
+    }
+}
+
+/// See [`min`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Min;
+
+/// Smallest item, or [`None`] if there are no items. The first of equal or incomparable items is kept.
+pub fn min() -> Min {
+    Min
+}
+
+impl<T: PartialOrd> Aggregator<T> for Min {
+    type Acc = Option<T>;
+    type Output = Option<T>;
+
+    fn seed(&mut self) -> Option<T> {
+        None
+    }
+
+    fn add(&mut self, acc: &mut Option<T>, item: T) {
+        if acc.as_ref().is_none_or(|m| item < *m) {
+            *acc = Some(item);
+        }
+    }
+
+    fn finish(&mut self, acc: Option<T>) -> Option<T> {
+        acc
+    }
+}
+
+/// See [`max`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Max;
+
+/// Largest item, or [`None`] if there are no items. The first of equal or incomparable items is kept.
+pub fn max() -> Max {
+    Max
+}
+
+impl<T: PartialOrd> Aggregator<T> for Max {
+    type Acc = Option<T>;
+    type Output = Option<T>;
+
+    fn seed(&mut self) -> Option<T> {
+        None
+    }
+
+    fn add(&mut self, acc: &mut Option<T>, item: T) {
+        if acc.as_ref().is_none_or(|m| item > *m) {
+            *acc = Some(item);
+        }
+    }
+
+    fn finish(&mut self, acc: Option<T>) -> Option<T> {
+        acc
+    }
+}
+
+/// See [`mean`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mean;
+
+/// Arithmetic mean of `f64` items (or references to them), or [`None`] if there are no items. Other items can be
+/// converted with [`map_items`].
+pub fn mean() -> Mean {
+    Mean
+}
+
+impl<T: Borrow<f64>> Aggregator<T> for Mean {
+    /// Sum and number of items.
+    type Acc = (f64, usize);
+    type Output = Option<f64>;
+
+    fn seed(&mut self) -> (f64, usize) {
+        (0., 0)
+    }
+
+    fn add(&mut self, acc: &mut (f64, usize), item: T) {
+        acc.0 += item.borrow();
+        acc.1 += 1;
+    }
+
+    fn finish(&mut self, (sum, n): (f64, usize)) -> Option<f64> {
+        (n > 0).then(|| sum / n as f64)
+    }
+}
+
+/// See [`histogram`].
+#[derive(Debug, Clone, Copy)]
+pub struct Histogram<F>(F);
+
+/// Number of items in each bucket `bucket(&item)`, sorted by bucket.
+pub fn histogram<F>(bucket: F) -> Histogram<F> {
+    Histogram(bucket)
+}
+
+impl<T, B: Ord, F: FnMut(&T) -> B> Aggregator<T> for Histogram<F> {
+    type Acc = BTreeMap<B, usize>;
+    type Output = BTreeMap<B, usize>;
+
+    fn seed(&mut self) -> BTreeMap<B, usize> {
+        BTreeMap::new()
+    }
+
+    fn add(&mut self, acc: &mut BTreeMap<B, usize>, item: T) {
+        *acc.entry((self.0)(&item)).or_default() += 1;
+    }
+
+    fn finish(&mut self, acc: BTreeMap<B, usize>) -> BTreeMap<B, usize> {
+        acc
+    }
+}
+
+/// See [`to_vec`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ToVec;
+
+/// Items in the order they are added.
+pub fn to_vec() -> ToVec {
+    ToVec
+}
+
+impl<T> Aggregator<T> for ToVec {
+    type Acc = Vec<T>;
+    type Output = Vec<T>;
+
+    fn seed(&mut self) -> Vec<T> {
+        Vec::new()
+    }
+
+    fn add(&mut self, acc: &mut Vec<T>, item: T) {
+        acc.push(item);
+    }
+
+    fn finish(&mut self, acc: Vec<T>) -> Vec<T> {
+        acc
+    }
+}
+
+/// See [`fold`].
+#[derive(Debug, Clone, Copy)]
+pub struct Fold<S, F>(S, F);
+
+/// Custom aggregation: the accumulator of each group is created by `seed` and updated by `f` for each item.
+pub fn fold<S, F>(seed: S, f: F) -> Fold<S, F> {
+    Fold(seed, f)
+}
+
+impl<T, A, S: FnMut() -> A, F: FnMut(&mut A, T)> Aggregator<T> for Fold<S, F> {
+    type Acc = A;
+    type Output = A;
+
+    fn seed(&mut self) -> A {
+        (self.0)()
+    }
+
+    fn add(&mut self, acc: &mut A, item: T) {
+        (self.1)(acc, item);
+    }
+
+    fn finish(&mut self, acc: A) -> A {
+        acc
+    }
+}
+
+//=================
+// Adaptors
+
+/// See [`map_items`].
+#[derive(Debug, Clone, Copy)]
+pub struct MapItems<F, A>(F, A);
+
+/// Aggregates the results of `f` on the items with `agg`, e.g., `map_items(|x: &u64| *x as f64, mean())`.
+pub fn map_items<F, A>(f: F, agg: A) -> MapItems<F, A> {
+    MapItems(f, agg)
+}
+
+impl<T, U, F: FnMut(T) -> U, A: Aggregator<U>> Aggregator<T> for MapItems<F, A> {
+    type Acc = A::Acc;
+    type Output = A::Output;
+
+    fn seed(&mut self) -> A::Acc {
+        self.1.seed()
+    }
+
+    fn add(&mut self, acc: &mut A::Acc, item: T) {
+        self.1.add(acc, (self.0)(item));
+    }
+
+    fn finish(&mut self, acc: A::Acc) -> A::Output {
+        self.1.finish(acc)
+    }
+}
+
+macro_rules! group_aggregator {
+    ($name:ident, $ctor:ident, $map:ident, $desc:literal, $($key_bound:tt)+) => {
+        #[doc = concat!("See [`", stringify!($ctor), "`].")]
+        #[derive(Debug, Clone, Copy)]
+        pub struct $name<F, A>(F, A);
+
+        #[doc = concat!("Aggregates the items of each group `key(&item)` separately with `agg`, into a [`", stringify!($map), "`]")]
+        #[doc = concat!($desc, ". Nesting it in another grouping aggregator produces nested maps.")]
+        pub fn $ctor<F, A>(key: F, agg: A) -> $name<F, A> {
+            $name(key, agg)
+        }
+
+        impl<T, G: $($key_bound)+, F: FnMut(&T) -> G, A: Aggregator<T>> Aggregator<T> for $name<F, A> {
+            type Acc = $map<G, A::Acc>;
+            type Output = $map<G, A::Output>;
+
+            fn seed(&mut self) -> Self::Acc {
+                $map::new()
+            }
+
+            fn add(&mut self, acc: &mut Self::Acc, item: T) {
+                let group = acc.entry((self.0)(&item)).or_insert_with(|| self.1.seed());
+                self.1.add(group, item);
+            }
+
+            fn finish(&mut self, acc: Self::Acc) -> Self::Output {
+                acc.into_iter().map(|(g, a)| (g, self.1.finish(a))).collect()
+            }
+        }
+    };
+}
+
+group_aggregator!(GroupBTree, group_btree, BTreeMap, " sorted by group", Ord);
+group_aggregator!(GroupHash, group_hash, HashMap, "", Eq + Hash);
+
+//=================
+// Iterators
+
+/// Aggregation of the items of iterators.
+pub trait AggregateExt: Iterator + Sized {
+    /// Aggregates all items with `agg`. For grouped results, use [`group_btree`] or [`group_hash`] as `agg`.
+    fn aggregate<A: Aggregator<Self::Item>>(self, mut agg: A) -> A::Output {
+        let mut acc = agg.seed();
+        for item in self {
+            agg.add(&mut acc, item);
+        }
+        agg.finish(acc)
+    }
+}
+
+impl<I: Iterator> AggregateExt for I {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// `(thread, endpoint, latency)` record.
+    type Record = (u32, &'static str, u64);
+
+    const RECORDS: [Record; 7] = [
+        (1, "get", 10),
+        (1, "get", 30),
+        (1, "put", 50),
+        (2, "get", 20),
+        (2, "put", 40),
+        (2, "put", 60),
+        (2, "put", 80),
+    ];
+
+    fn latency(r: &Record) -> f64 {
+        r.2 as f64
+    }
+
+    #[test]
+    fn test_builtins() {
+        let xs = [3_u64, 1, 4, 1, 5];
+        assert_eq!(xs.iter().aggregate(count()), 5);
+        assert_eq!(xs.iter().aggregate(sum::<u64>()), 14);
+        assert_eq!(xs.into_iter().aggregate(sum::<u64>()), 14);
+        assert_eq!(xs.iter().aggregate(min()), Some(&1));
+        assert_eq!(xs.iter().aggregate(max()), Some(&5));
+        assert_eq!(
+            xs.iter().aggregate(map_items(|x: &u64| *x as f64, mean())),
+            Some(2.8)
+        );
+        assert_eq!([1., 2.].iter().aggregate(mean()), Some(1.5));
+        assert_eq!([0_f64; 0].iter().aggregate(mean()), None);
+        assert_eq!(
+            xs.iter().aggregate(histogram(|x: &&u64| **x / 2)),
+            BTreeMap::from([(0, 2), (1, 1), (2, 2)])
+        );
+        assert_eq!(xs.into_iter().aggregate(to_vec()), xs);
+        assert_eq!(
+            xs.iter()
+                .aggregate(fold(String::new, |s: &mut String, x: &u64| s
+                    .push_str(&x.to_string()))),
+            "31415"
+        );
+    }
+
+    #[test]
+    fn test_min_max_keep_first() {
+        let xs = [2, 1, 3, 1, 3];
+        assert!(std::ptr::eq(xs.iter().aggregate(min()).unwrap(), &xs[1]));
+        assert!(std::ptr::eq(xs.iter().aggregate(max()).unwrap(), &xs[2]));
+
+        // `NaN` is incomparable, so it is skipped unless it comes first.
+        assert_eq!([1., f64::NAN, 0.5].into_iter().aggregate(min()), Some(0.5));
+        assert!(
+            [f64::NAN, 1.]
+                .into_iter()
+                .aggregate(max())
+                .unwrap()
+                .is_nan()
+        );
+    }
+
+    #[test]
+    fn test_grouping() {
+        let by_thread = RECORDS
+            .iter()
+            .aggregate(group_btree(|r: &&Record| r.0, count()));
+        assert_eq!(by_thread, BTreeMap::from([(1, 3), (2, 4)]));
+
+        let means = RECORDS
+            .iter()
+            .aggregate(group_hash(|r: &&Record| r.1, map_items(latency, mean())));
+        assert_eq!(
+            means,
+            HashMap::from([("get", Some(20.)), ("put", Some(57.5))])
+        );
+
+        // Two levels: thread, then endpoint.
+        let nested = RECORDS.iter().aggregate(group_btree(
+            |r: &&Record| r.0,
+            group_btree(|r: &&Record| r.1, map_items(|r: &Record| r.2, to_vec())),
+        ));
+        assert_eq!(nested[&1]["get"], [10, 30]);
+        assert_eq!(nested[&2]["put"], [40, 60, 80]);
+        assert_eq!(nested[&2].len(), 2);
+    }
+}
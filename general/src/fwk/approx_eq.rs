@@ -1,24 +1,395 @@
-//! Provides approximate equality for floating point types
+//! Provides approximate equality for floating point types and for collections, tuples and options of them.
+//!
+//! Values are compared with a [`Tolerance`] that combines an absolute bound, a bound relative to the larger
+//! magnitude, and a bound on the distance in units in the last place (ULPs): two values are approximately equal if
+//! any of the bounds holds. The tolerance also controls whether `NaN`s and infinities can be equal.
+//!
+//! Structs and enums of such values can derive [`ApproxEq`], which compares their fields recursively.
 
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    hash::{BuildHasher, Hash},
+};
+
+pub use approx_eq_derive::ApproxEq;
+
+/// Tolerance for [`ApproxEq`] comparisons. The default tolerance only accepts exactly equal values.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Tolerance<S> {
+    /// Maximum absolute difference.
+    pub abs: S,
+    /// Maximum difference relative to the larger magnitude of the compared values.
+    pub rel: S,
+    /// Maximum distance in units in the last place, if any.
+    pub ulps: Option<u64>,
+    /// Whether `NaN` is approximately equal to `NaN`.
+    pub nan_eq: bool,
+    /// Whether infinities of the same sign are approximately equal. Infinities are never approximately equal to
+    /// finite values.
+    pub inf_eq: bool,
+}
+
+impl<S: Default> Tolerance<S> {
+    /// Exact equality, with infinities of the same sign equal and `NaN`s unequal.
+    pub fn exact() -> Self {
+        Self {
+            inf_eq: true,
+            ..Default::default()
+        }
+    }
+
+    /// Absolute tolerance `abs`.
+    pub fn abs(abs: S) -> Self {
+        Self {
+            abs,
+            ..Self::exact()
+        }
+    }
+
+    /// Relative tolerance `rel`.
+    pub fn rel(rel: S) -> Self {
+        Self {
+            rel,
+            ..Self::exact()
+        }
+    }
+
+    /// Absolute tolerance `abs` or relative tolerance `rel`, which suits values that may be close to zero.
+    pub fn abs_rel(abs: S, rel: S) -> Self {
+        Self {
+            abs,
+            rel,
+            ..Self::exact()
+        }
+    }
+
+    /// Tolerance of `ulps` units in the last place.
+    pub fn ulps(ulps: u64) -> Self {
+        Self {
+            ulps: Some(ulps),
+            ..Self::exact()
+        }
+    }
+
+    /// Sets whether `NaN` is approximately equal to `NaN`.
+    pub fn nan_eq(self, nan_eq: bool) -> Self {
+        Self { nan_eq, ..self }
+    }
+
+    /// Sets whether infinities of the same sign are approximately equal.
+    pub fn inf_eq(self, inf_eq: bool) -> Self {
+        Self { inf_eq, ..self }
+    }
+}
+
+/// Approximate equality. `#[derive(ApproxEq)]` implements it for structs and enums whose fields implement it, see
+/// [`approx_eq_derive`] for the attributes that skip fields or override their tolerance.
 pub trait ApproxEq {
-    fn approx_eq(self, other: Self, epsilon: Self) -> bool;
+    /// Float type of the tolerances.
+    type Scalar: Copy + Default + Debug;
+
+    /// Whether `self` and `other` are equal within tolerance `tol`.
+    fn approx_eq_with(&self, other: &Self, tol: &Tolerance<Self::Scalar>) -> bool;
+
+    /// Maximum absolute difference between the corresponding floats in `self` and `other`, or [`None`] if they
+    /// have different shapes, e.g., slices of different lengths.
+    fn abs_diff(&self, other: &Self) -> Option<f64>;
+
+    /// Whether `self` and `other` differ by at most `epsilon`.
+    fn approx_eq(&self, other: &Self, epsilon: Self::Scalar) -> bool {
+        self.approx_eq_with(other, &Tolerance::abs(epsilon))
+    }
+
+    /// Whether `self` and `other` differ by at most `rel` times the larger magnitude.
+    fn approx_eq_rel(&self, other: &Self, rel: Self::Scalar) -> bool {
+        self.approx_eq_with(other, &Tolerance::rel(rel))
+    }
+
+    /// Whether `self` and `other` differ by at most `abs` or by at most `rel` times the larger magnitude.
+    fn approx_eq_abs_rel(&self, other: &Self, abs: Self::Scalar, rel: Self::Scalar) -> bool {
+        self.approx_eq_with(other, &Tolerance::abs_rel(abs, rel))
+    }
+
+    /// Whether `self` and `other` are at most `ulps` units in the last place apart.
+    fn approx_eq_ulps(&self, other: &Self, ulps: u64) -> bool {
+        self.approx_eq_with(other, &Tolerance::ulps(ulps))
+    }
+}
+
+//=================
+// Floats
+
+macro_rules! impl_approx_eq_float {
+    ($t:ty, $bits:ty) => {
+        impl ApproxEq for $t {
+            type Scalar = $t;
+
+            fn approx_eq_with(&self, other: &Self, tol: &Tolerance<$t>) -> bool {
+                let (x, y) = (*self, *other);
+                if x.is_nan() || y.is_nan() {
+                    return tol.nan_eq && x.is_nan() && y.is_nan();
+                }
+                if x.is_infinite() || y.is_infinite() {
+                    return tol.inf_eq && x == y;
+                }
+                if x == y {
+                    return true;
+                }
+
+                let diff = (x - y).abs();
+                diff <= tol.abs
+                    || diff <= tol.rel * x.abs().max(y.abs())
+                    || tol.ulps.is_some_and(|ulps| {
+                        // Maps the bits to integers that are ordered like the floats, with both zeros at 0.
+                        let ordered = |x: $t| {
+                            let i = x.to_bits() as $bits;
+                            if i < 0 { <$bits>::MIN - i } else { i }
+                        };
+                        let distance = (ordered(x) as i128 - ordered(y) as i128).unsigned_abs();
+                        distance <= ulps as u128
+                    })
+            }
+
+            fn abs_diff(&self, other: &Self) -> Option<f64> {
+                Some((*self as f64 - *other as f64).abs())
+            }
+        }
+    };
+}
+
+impl_approx_eq_float!(f32, i32);
+impl_approx_eq_float!(f64, i64);
+
+//=================
+// References, collections, options and tuples
+
+/// Maximum of differences, or [`None`] if any is [`None`].
+fn max_diff(diffs: impl IntoIterator<Item = Option<f64>>) -> Option<f64> {
+    diffs
+        .into_iter()
+        .try_fold(0_f64, |acc, d| d.map(|d| acc.max(d)))
+}
+
+impl<T: ApproxEq + ?Sized> ApproxEq for &T {
+    type Scalar = T::Scalar;
+
+    fn approx_eq_with(&self, other: &Self, tol: &Tolerance<T::Scalar>) -> bool {
+        T::approx_eq_with(self, other, tol)
+    }
+
+    fn abs_diff(&self, other: &Self) -> Option<f64> {
+        T::abs_diff(self, other)
+    }
+}
+
+impl<T: ApproxEq> ApproxEq for [T] {
+    type Scalar = T::Scalar;
+
+    fn approx_eq_with(&self, other: &Self, tol: &Tolerance<T::Scalar>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .zip(other)
+                .all(|(a, b)| a.approx_eq_with(b, tol))
+    }
+
+    fn abs_diff(&self, other: &Self) -> Option<f64> {
+        if self.len() != other.len() {
+            return None;
+        }
+        max_diff(self.iter().zip(other).map(|(a, b)| a.abs_diff(b)))
+    }
+}
+
+impl<T: ApproxEq, const N: usize> ApproxEq for [T; N] {
+    type Scalar = T::Scalar;
+
+    fn approx_eq_with(&self, other: &Self, tol: &Tolerance<T::Scalar>) -> bool {
+        self.as_slice().approx_eq_with(other.as_slice(), tol)
+    }
+
+    fn abs_diff(&self, other: &Self) -> Option<f64> {
+        self.as_slice().abs_diff(other.as_slice())
+    }
+}
+
+impl<T: ApproxEq> ApproxEq for Vec<T> {
+    type Scalar = T::Scalar;
+
+    fn approx_eq_with(&self, other: &Self, tol: &Tolerance<T::Scalar>) -> bool {
+        self.as_slice().approx_eq_with(other.as_slice(), tol)
+    }
+
+    fn abs_diff(&self, other: &Self) -> Option<f64> {
+        self.as_slice().abs_diff(other.as_slice())
+    }
 }
 
-impl ApproxEq for f32 {
-    fn approx_eq(self, other: Self, epsilon: Self) -> bool {
-        (self - other).abs() <= epsilon
+impl<T: ApproxEq> ApproxEq for Option<T> {
+    type Scalar = T::Scalar;
+
+    fn approx_eq_with(&self, other: &Self, tol: &Tolerance<T::Scalar>) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => a.approx_eq_with(b, tol),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    fn abs_diff(&self, other: &Self) -> Option<f64> {
+        match (self, other) {
+            (Some(a), Some(b)) => a.abs_diff(b),
+            (None, None) => Some(0.),
+            _ => None,
+        }
     }
 }
 
-impl ApproxEq for f64 {
-    fn approx_eq(self, other: Self, epsilon: Self) -> bool {
-        (self - other).abs() < epsilon
+macro_rules! impl_approx_eq_tuple {
+    ($($t:ident $i:tt),+) => {
+        impl<S: Copy + Default + Debug, $($t: ApproxEq<Scalar = S>),+> ApproxEq for ($($t,)+) {
+            type Scalar = S;
+
+            fn approx_eq_with(&self, other: &Self, tol: &Tolerance<S>) -> bool {
+                $(self.$i.approx_eq_with(&other.$i, tol))&&+
+            }
+
+            fn abs_diff(&self, other: &Self) -> Option<f64> {
+                max_diff([$(self.$i.abs_diff(&other.$i)),+])
+            }
+        }
+    };
+}
+
+impl_approx_eq_tuple!(A 0);
+impl_approx_eq_tuple!(A 0, B 1);
+impl_approx_eq_tuple!(A 0, B 1, C 2);
+impl_approx_eq_tuple!(A 0, B 1, C 2, D 3);
+impl_approx_eq_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_approx_eq_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+
+impl<K: Eq + Hash, V: ApproxEq, H: BuildHasher> ApproxEq for HashMap<K, V, H> {
+    type Scalar = V::Scalar;
+
+    fn approx_eq_with(&self, other: &Self, tol: &Tolerance<V::Scalar>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(k, v)| other.get(k).is_some_and(|w| v.approx_eq_with(w, tol)))
+    }
+
+    fn abs_diff(&self, other: &Self) -> Option<f64> {
+        if self.len() != other.len() {
+            return None;
+        }
+        max_diff(self.iter().map(|(k, v)| v.abs_diff(other.get(k)?)))
     }
 }
 
+impl<K: Ord, V: ApproxEq> ApproxEq for BTreeMap<K, V> {
+    type Scalar = V::Scalar;
+
+    fn approx_eq_with(&self, other: &Self, tol: &Tolerance<V::Scalar>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(k, v)| other.get(k).is_some_and(|w| v.approx_eq_with(w, tol)))
+    }
+
+    fn abs_diff(&self, other: &Self) -> Option<f64> {
+        if self.len() != other.len() {
+            return None;
+        }
+        max_diff(self.iter().map(|(k, v)| v.abs_diff(other.get(k)?)))
+    }
+}
+
+//=================
+// Assertions
+
+/// Asserts that two [`ApproxEq`] values are approximately equal, printing both, their maximum absolute difference
+/// and the tolerance on failure. The values are evaluated once and compared by reference.
+///
+/// The tolerance is given as an absolute epsilon, or as one of `abs = `, `rel = `, `ulps = ` or `tol = ` followed
+/// by a value of the corresponding type, where `tol` takes a [`Tolerance`].
+///
+/// ```
+/// use general::assert_approx_eq;
+/// use general::fwk::approx_eq::Tolerance;
+///
+/// assert_approx_eq!(1.0_f64, 1.0 + 1e-10, 1e-9);
+/// assert_approx_eq!([1000.0_f64, 2.0], [1000.1, 2.0], rel = 1e-3);
+/// assert_approx_eq!(0.1_f64 + 0.2, 0.3, ulps = 1);
+/// assert_approx_eq!(f64::NAN, f64::NAN, tol = Tolerance::exact().nan_eq(true));
+/// ```
+#[macro_export]
+macro_rules! assert_approx_eq {
+    ($left:expr, $right:expr, abs = $abs:expr $(,)?) => {
+        $crate::assert_approx_eq!($left, $right, tol = $crate::fwk::approx_eq::Tolerance::abs($abs))
+    };
+    ($left:expr, $right:expr, rel = $rel:expr $(,)?) => {
+        $crate::assert_approx_eq!($left, $right, tol = $crate::fwk::approx_eq::Tolerance::rel($rel))
+    };
+    ($left:expr, $right:expr, ulps = $ulps:expr $(,)?) => {
+        $crate::assert_approx_eq!($left, $right, tol = $crate::fwk::approx_eq::Tolerance::ulps($ulps))
+    };
+    ($left:expr, $right:expr, tol = $tol:expr $(,)?) => {
+        match (&$left, &$right, $tol) {
+            (left, right, tol) => {
+                if !$crate::fwk::approx_eq::ApproxEq::approx_eq_with(left, right, &tol) {
+                    panic!(
+                        "assertion `left approx_eq right` failed\n  left: {:?}\n right: {:?}\n  diff: {:?}\n   tol: {:?}",
+                        left,
+                        right,
+                        $crate::fwk::approx_eq::ApproxEq::abs_diff(left, right),
+                        tol,
+                    );
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $epsilon:expr $(,)?) => {
+        $crate::assert_approx_eq!($left, $right, abs = $epsilon)
+    };
+}
+
+/// Asserts that two [`ApproxEq`] values are not approximately equal, with the tolerance given as in
+/// [`assert_approx_eq!`].
+#[macro_export]
+macro_rules! assert_approx_ne {
+    ($left:expr, $right:expr, abs = $abs:expr $(,)?) => {
+        $crate::assert_approx_ne!($left, $right, tol = $crate::fwk::approx_eq::Tolerance::abs($abs))
+    };
+    ($left:expr, $right:expr, rel = $rel:expr $(,)?) => {
+        $crate::assert_approx_ne!($left, $right, tol = $crate::fwk::approx_eq::Tolerance::rel($rel))
+    };
+    ($left:expr, $right:expr, ulps = $ulps:expr $(,)?) => {
+        $crate::assert_approx_ne!($left, $right, tol = $crate::fwk::approx_eq::Tolerance::ulps($ulps))
+    };
+    ($left:expr, $right:expr, tol = $tol:expr $(,)?) => {
+        match (&$left, &$right, $tol) {
+            (left, right, tol) => {
+                if $crate::fwk::approx_eq::ApproxEq::approx_eq_with(left, right, &tol) {
+                    panic!(
+                        "assertion `left approx_ne right` failed\n  left: {:?}\n right: {:?}\n  diff: {:?}\n   tol: {:?}",
+                        left,
+                        right,
+                        $crate::fwk::approx_eq::ApproxEq::abs_diff(left, right),
+                        tol,
+                    );
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $epsilon:expr $(,)?) => {
+        $crate::assert_approx_ne!($left, $right, abs = $epsilon)
+    };
+}
+
 #[cfg(test)]
 mod test {
-    use super::ApproxEq;
+    use super::*;
 
     #[test]
     fn test() {
@@ -28,9 +399,9 @@ mod test {
             let z32: f32 = 123.444455;
             let epsilon: f32 = 0.00001;
 
-            assert!(x32.approx_eq(y32, epsilon), "x32 must be approx_eq to y32");
+            assert!(x32.approx_eq(&y32, epsilon), "x32 must be approx_eq to y32");
             assert!(
-                !x32.approx_eq(z32, epsilon),
+                !x32.approx_eq(&z32, epsilon),
                 "x32 must not be approx_eq to z32"
             );
         }
@@ -41,11 +412,192 @@ mod test {
             let z64: f64 = 123.444455;
             let epsilon: f64 = 0.00001;
 
-            assert!(x64.approx_eq(y64, epsilon), "x64 must be approx_eq to y64");
+            assert!(x64.approx_eq(&y64, epsilon), "x64 must be approx_eq to y64");
             assert!(
-                !x64.approx_eq(z64, epsilon),
+                !x64.approx_eq(&z64, epsilon),
                 "x64 must not be approx_eq to z64"
             );
         }
     }
+
+    #[test]
+    fn test_consistent_bounds() {
+        // Both types accept a difference equal to epsilon.
+        assert!(1.5_f32.approx_eq(&1.75, 0.25));
+        assert!(1.5_f64.approx_eq(&1.75, 0.25));
+    }
+
+    #[test]
+    fn test_rel_and_ulps() {
+        assert!(1e9_f64.approx_eq_rel(&(1e9 + 1.), 1e-9));
+        assert!(!1e9_f64.approx_eq(&(1e9 + 1.), 1e-9));
+        assert!(!1e-12_f64.approx_eq_rel(&0., 0.5));
+        assert!(1e-12_f64.approx_eq_abs_rel(&0., 1e-9, 0.5));
+
+        let x = 1_f64;
+        let next = f64::from_bits(x.to_bits() + 1);
+        assert!(x.approx_eq_ulps(&next, 1));
+        assert!(!x.approx_eq_ulps(&f64::from_bits(x.to_bits() + 2), 1));
+        assert!((0.1_f64 + 0.2).approx_eq_ulps(&0.3, 1));
+        assert!((-0_f64).approx_eq_ulps(&0., 0));
+        assert!(f64::from_bits(1).approx_eq_ulps(&-f64::from_bits(1), 2));
+        assert!(1_f32.approx_eq_ulps(&f32::from_bits(1_f32.to_bits() + 3), 3));
+    }
+
+    #[test]
+    fn test_nan_and_infinity() {
+        let loose = Tolerance::abs(f64::MAX);
+        assert!(!f64::NAN.approx_eq_with(&f64::NAN, &loose));
+        assert!(f64::NAN.approx_eq_with(&f64::NAN, &loose.nan_eq(true)));
+        assert!(!f64::NAN.approx_eq_with(&1., &loose.nan_eq(true)));
+
+        assert!(f64::INFINITY.approx_eq_with(&f64::INFINITY, &loose));
+        assert!(!f64::INFINITY.approx_eq_with(&f64::INFINITY, &loose.inf_eq(false)));
+        assert!(!f64::INFINITY.approx_eq_with(&f64::NEG_INFINITY, &loose));
+        assert!(!f64::INFINITY.approx_eq_with(&f64::MAX, &loose));
+    }
+
+    #[test]
+    fn test_collections() {
+        let a = [1., 2., 3.];
+        let b = [1., 2.01, 3.];
+        assert!(a.approx_eq(&b, 0.1));
+        assert!(!a.approx_eq(&b, 0.001));
+        assert!(!a.as_slice().approx_eq(&b[..2], 0.1));
+        assert_eq!(a.as_slice().abs_diff(&b[..2]), None);
+
+        assert!((1_f64, 2_f64).approx_eq(&(1.05, 2.), 0.1));
+        assert!(!(1_f64, 2_f64).approx_eq(&(1., 2.5), 0.1));
+        assert!(Some(1_f64).approx_eq(&Some(1.05), 0.1));
+        assert!(!Some(1_f64).approx_eq(&None, 0.1));
+        assert!(None::<f64>.approx_eq(&None, 0.));
+
+        let m1 = HashMap::from([("a", 1.), ("b", 2.)]);
+        let m2 = HashMap::from([("a", 1.05), ("b", 2.)]);
+        let m3 = HashMap::from([("a", 1.), ("c", 2.)]);
+        assert!(m1.approx_eq(&m2, 0.1));
+        assert!(!m1.approx_eq(&m3, 0.1));
+        assert_eq!(m1.abs_diff(&m3), None);
+
+        let b1 = BTreeMap::from([(1, [1_f32, 2.]), (2, [3., 4.])]);
+        let b2 = BTreeMap::from([(1, [1_f32, 2.]), (2, [3., 4.5])]);
+        assert!(b1.approx_eq_rel(&b2, 0.2));
+        assert!(!b1.approx_eq_rel(&b2, 0.1));
+        assert_eq!(b1.abs_diff(&b2), Some(0.5));
+    }
+
+    #[test]
+    fn test_assert_macros() {
+        assert_approx_eq!(1_f64, 1.05, 0.1);
+        assert_approx_eq!([1000_f64, 2.], [1000.5, 2.], rel = 1e-3);
+        assert_approx_eq!(0.1_f64 + 0.2, 0.3, ulps = 1);
+        assert_approx_eq!(f64::NAN, f64::NAN, tol = Tolerance::exact().nan_eq(true));
+        assert_approx_ne!(1_f64, 1.5, abs = 0.1);
+        assert_approx_ne!(f64::NAN, f64::NAN, 1.);
+    }
+
+    #[test]
+    #[should_panic(expected = "diff: Some(0.5)")]
+    fn test_assert_message() {
+        assert_approx_eq!((1_f64, 2_f64), (1., 2.5), 0.1);
+    }
+
+    #[derive(Debug, ApproxEq)]
+    struct Point {
+        x: f64,
+        y: f64,
+        #[approx_eq(skip)]
+        label: &'static str,
+        #[approx_eq(exact)]
+        id: u32,
+        #[approx_eq(abs = 1.)]
+        weight: f64,
+    }
+
+    #[derive(Debug, ApproxEq)]
+    #[approx_eq(scalar = f32)]
+    struct Pair(f32, #[approx_eq(ulps = 1)] f32);
+
+    #[derive(Debug, ApproxEq)]
+    struct Samples<T> {
+        values: Vec<T>,
+        mean: Option<T>,
+    }
+
+    #[derive(Debug, ApproxEq)]
+    enum Shape {
+        Circle { r: f64 },
+        Rect(f64, f64),
+        Empty,
+    }
+
+    #[test]
+    fn test_derive_struct() {
+        let p = Point {
+            x: 1.,
+            y: 2.,
+            label: "a",
+            id: 7,
+            weight: 10.,
+        };
+        let q = Point {
+            x: 1.05,
+            label: "b",
+            weight: 10.9,
+            ..p
+        };
+        assert_ne!(p.label, q.label);
+        assert!(p.approx_eq(&q, 0.1));
+        assert!(!p.approx_eq(&q, 0.01));
+        assert_eq!(p.abs_diff(&q), Some(q.weight - p.weight));
+        assert!(!p.approx_eq(&Point { id: 8, ..q }, 0.1));
+        assert_eq!(p.abs_diff(&Point { id: 8, ..q }), None);
+        assert!(!p.approx_eq(&Point { weight: 11.5, ..q }, 0.1));
+
+        // The override keeps the `NaN` option of the outer tolerance.
+        let r = Point {
+            weight: f64::NAN,
+            ..p
+        };
+        assert!(!r.approx_eq(&r, 0.1));
+        assert!(r.approx_eq_with(&r, &Tolerance::abs(0.1).nan_eq(true)));
+    }
+
+    #[test]
+    fn test_derive_tuple_and_generic() {
+        let a = Pair(1., 1.);
+        let b = Pair(1.5, f32::from_bits(1_f32.to_bits() + 1));
+        assert!(a.approx_eq(&b, 0.5));
+        assert!(!a.approx_eq(&Pair(1., 1.5), 0.5));
+
+        let s = Samples {
+            values: vec![1., 2.],
+            mean: Some(1.5),
+        };
+        let t = Samples {
+            values: vec![1., 2.1],
+            mean: Some(1.55),
+        };
+        assert_approx_eq!(s, t, 0.2);
+        assert_approx_ne!(
+            s,
+            Samples {
+                values: vec![1.],
+                mean: Some(1.),
+            },
+            0.2
+        );
+    }
+
+    #[test]
+    fn test_derive_enum() {
+        use Shape::*;
+        assert!(Circle { r: 1. }.approx_eq(&Circle { r: 1.05 }, 0.1));
+        assert!(Rect(1., 2.).approx_eq(&Rect(1., 2.05), 0.1));
+        assert!(!Rect(1., 2.).approx_eq(&Rect(1., 2.5), 0.1));
+        assert!(Empty.approx_eq(&Empty, 0.));
+        assert!(!Circle { r: 1. }.approx_eq(&Rect(1., 1.), 1.));
+        assert_eq!(Empty.abs_diff(&Rect(1., 1.)), None);
+        assert_eq!(Rect(1., 2.).abs_diff(&Rect(1.5, 2.)), Some(0.5));
+    }
 }
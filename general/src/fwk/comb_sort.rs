@@ -1,7 +1,10 @@
-/// Implementation of bubble sort algorithm.
-///
-/// Sorts `arr` in place without heap allocations.
-pub fn bubble_sort<T: PartialOrd>(arr: &mut [T]) {
+//! Silent bubble sort and comb sort, with `_by` and `_by_key` variants. Pass counts are printed only with the
+//! `sort-trace` feature. Incomparable items such as `NaN` yield an arbitrary order, see [`super::total_order`].
+
+use super::sort::{compare_by_key, less_from};
+use std::cmp::Ordering;
+
+fn bubble_sort_with<T>(arr: &mut [T], is_less: &mut impl FnMut(&T, &T) -> bool) {
     let len = arr.len();
     if len <= 1 {
         return;
@@ -13,20 +16,34 @@ pub fn bubble_sort<T: PartialOrd>(arr: &mut [T]) {
     while let Some(k) = swapped {
         swapped = None;
         for i in 0..k - 1 {
-            if arr[i] > arr[i + 1] {
+            if is_less(&arr[i + 1], &arr[i]) {
                 arr.swap(i, i + 1);
                 swapped = Some(i + 1);
             }
         }
         passes += 1;
     }
-    println!("bubble sort passes: {passes}");
+    sort_trace!("bubble sort passes: {passes}");
 }
 
-/// Implementation of comb sort algorithm.
+/// Implementation of bubble sort algorithm.
 ///
 /// Sorts `arr` in place without heap allocations.
-pub fn comb_sort<T: PartialOrd>(arr: &mut [T]) {
+pub fn bubble_sort<T: PartialOrd>(arr: &mut [T]) {
+    bubble_sort_with(arr, &mut |a, b| a < b)
+}
+
+/// Like [`bubble_sort`] but with comparator `compare`.
+pub fn bubble_sort_by<T>(arr: &mut [T], compare: impl FnMut(&T, &T) -> Ordering) {
+    bubble_sort_with(arr, &mut less_from(compare))
+}
+
+/// Like [`bubble_sort`] but compares the keys extracted by `f`.
+pub fn bubble_sort_by_key<T, K: Ord>(arr: &mut [T], f: impl FnMut(&T) -> K) {
+    bubble_sort_by(arr, compare_by_key(f))
+}
+
+fn comb_sort_with<T>(arr: &mut [T], is_less: &mut impl FnMut(&T, &T) -> bool) {
     let len = arr.len();
     if len <= 1 {
         return;
@@ -48,17 +65,34 @@ pub fn comb_sort<T: PartialOrd>(arr: &mut [T]) {
 
         // Single "comb" pass through the array
         while i + gap < len {
-            if arr[i] > arr[i + gap] {
+            if is_less(&arr[i + gap], &arr[i]) {
                 arr.swap(i, i + gap);
             }
             i += 1;
         }
         passes += 1;
     }
-    println!("comb sort passes pre-bubble: {passes}");
+    sort_trace!("comb sort passes pre-bubble: {passes}");
 
     // Now gap == 1, do bubble sort
-    bubble_sort(arr);
+    bubble_sort_with(arr, is_less);
+}
+
+/// Implementation of comb sort algorithm.
+///
+/// Sorts `arr` in place without heap allocations.
+pub fn comb_sort<T: PartialOrd>(arr: &mut [T]) {
+    comb_sort_with(arr, &mut |a, b| a < b)
+}
+
+/// Like [`comb_sort`] but with comparator `compare`.
+pub fn comb_sort_by<T>(arr: &mut [T], compare: impl FnMut(&T, &T) -> Ordering) {
+    comb_sort_with(arr, &mut less_from(compare))
+}
+
+/// Like [`comb_sort`] but compares the keys extracted by `f`.
+pub fn comb_sort_by_key<T, K: Ord>(arr: &mut [T], f: impl FnMut(&T) -> K) {
+    comb_sort_by(arr, compare_by_key(f))
 }
 
 #[cfg(test)]
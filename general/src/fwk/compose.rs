@@ -0,0 +1,352 @@
+//! Function composition for plain, `Result`-returning (Kleisli) and async functions.
+//!
+//! Two directions are supported throughout:
+//! - *compose*, as in mathematics: `compose(f, g)` is `f ∘ g`, i.e., `|x| f(g(x))`;
+//! - *pipe* or *and then*, in application order: `and_then(f, g)` is `|x| g(f(x))`.
+//!
+//! The variadic macros [`compose!`](crate::compose), [`pipe!`](crate::pipe), [`try_pipe!`](crate::try_pipe) and
+//! [`pipe_async!`](crate::pipe_async) accept arbitrary expressions that evaluate to functions, each evaluated
+//! once, and build on the free functions and extension traits of this module. Async functions are composed as
+//! functions returning a [`BoxFuture`], as produced by [`pin_async_fn!`](crate::pin_async_fn).
+
+use std::{future::Future, pin::Pin, sync::Arc};
+
+/// Boxed and pinned future, as returned by composed async functions. Like `futures::future::BoxFuture`, it is
+/// [`Send`] but not necessarily [`Sync`], and it may borrow data that lives for `'a`.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+//=================
+// Plain functions
+
+/// Returns `f ∘ g`, which applies `g` and then `f`.
+pub fn compose<A, B, C>(f: impl Fn(B) -> C, g: impl Fn(A) -> B) -> impl Fn(A) -> C {
+    move |a| f(g(a))
+}
+
+/// Returns the function that applies `f` and then `g`.
+pub fn and_then<A, B, C>(f: impl Fn(A) -> B, g: impl Fn(B) -> C) -> impl Fn(A) -> C {
+    move |a| g(f(a))
+}
+
+/// Extension trait to compose a function with a function applied before it.
+pub trait Compose<B, C>: Fn(B) -> C + Sized {
+    /// Returns `self ∘ g`, which applies `g` and then `self`.
+    fn compose<A>(self, g: impl Fn(A) -> B) -> impl Fn(A) -> C {
+        compose(self, g)
+    }
+}
+
+impl<B, C, F: Fn(B) -> C> Compose<B, C> for F {}
+
+/// Extension trait to compose a function with a function applied after it.
+pub trait AndThen<A, B>: Fn(A) -> B + Sized {
+    /// Returns the function that applies `self` and then `g`.
+    fn and_then<C>(self, g: impl Fn(B) -> C) -> impl Fn(A) -> C {
+        and_then(self, g)
+    }
+}
+
+impl<A, B, F: Fn(A) -> B> AndThen<A, B> for F {}
+
+/// Composes functions in mathematical order: `compose!(f, g, h)` is `|x| f(g(h(x)))`.
+///
+/// ```
+/// use general::compose;
+///
+/// let f = compose!(|x: i32| x.to_string(), |x| x * 2, |x| x + 1);
+/// assert_eq!(f(4), "10");
+/// ```
+#[macro_export]
+macro_rules! compose {
+    ($f:expr $(,)?) => {
+        $f
+    };
+    ($f:expr, $($rest:expr),+ $(,)?) => {
+        $crate::fwk::compose::compose($f, $crate::compose!($($rest),+))
+    };
+}
+
+/// Composes functions in application order: `pipe!(f, g, h)` is `|x| h(g(f(x)))`. With a leading `x =>`, applies
+/// the functions to `x` instead.
+///
+/// ```
+/// use general::pipe;
+///
+/// let f = pipe!(|x: i32| x + 1, |x| x * 2, |x: i32| x.to_string());
+/// assert_eq!(f(4), "10");
+/// assert_eq!(pipe!(4 => |x| x + 1, |x| x * 2), 10);
+/// ```
+#[macro_export]
+macro_rules! pipe {
+    ($x:expr => $($f:expr),+ $(,)?) => {
+        $crate::pipe!($($f),+)($x)
+    };
+    ($f:expr $(,)?) => {
+        $f
+    };
+    ($f:expr, $g:expr $(, $rest:expr)* $(,)?) => {
+        $crate::pipe!($crate::fwk::compose::and_then($f, $g) $(, $rest)*)
+    };
+}
+
+//=================
+// Kleisli composition
+
+/// Returns the function that applies `f` and then, if it succeeds, `g`. Errors of `g` are converted with [`From`],
+/// as with the `?` operator.
+pub fn try_and_then<A, B, C, E, E2>(
+    f: impl Fn(A) -> Result<B, E>,
+    g: impl Fn(B) -> Result<C, E2>,
+) -> impl Fn(A) -> Result<C, E>
+where
+    E: From<E2>,
+{
+    move |a| f(a).and_then(|b| g(b).map_err(E::from))
+}
+
+/// Extension trait for the Kleisli composition of `Result`-returning functions.
+pub trait TryAndThen<A, B, E>: Fn(A) -> Result<B, E> + Sized {
+    /// Returns the function that applies `self` and then, if it succeeds, `g`. See [`try_and_then`].
+    fn try_and_then<C, E2>(self, g: impl Fn(B) -> Result<C, E2>) -> impl Fn(A) -> Result<C, E>
+    where
+        E: From<E2>,
+    {
+        try_and_then(self, g)
+    }
+}
+
+impl<A, B, E, F: Fn(A) -> Result<B, E>> TryAndThen<A, B, E> for F {}
+
+/// Kleisli composition in application order of `Result`-returning functions, short-circuiting on the first error.
+/// With a leading `x =>`, applies the functions to `x` instead.
+///
+/// ```
+/// use general::try_pipe;
+///
+/// let parse_half = try_pipe!(
+///     |s: &str| s.parse::<i32>().map_err(|e| e.to_string()),
+///     |x| if x % 2 == 0 { Ok(x / 2) } else { Err(format!("{x} is odd")) },
+/// );
+/// assert_eq!(parse_half("8"), Ok(4));
+/// assert_eq!(parse_half("7"), Err("7 is odd".to_owned()));
+/// assert!(try_pipe!("x" => parse_half).is_err());
+/// ```
+#[macro_export]
+macro_rules! try_pipe {
+    ($x:expr => $($f:expr),+ $(,)?) => {
+        $crate::try_pipe!($($f),+)($x)
+    };
+    ($f:expr $(,)?) => {
+        $f
+    };
+    ($f:expr, $g:expr $(, $rest:expr)* $(,)?) => {
+        $crate::try_pipe!($crate::fwk::compose::try_and_then($f, $g) $(, $rest)*)
+    };
+}
+
+//=================
+// Async functions
+
+/// Turns an async function into a function that returns a [`BoxFuture`].
+///
+/// ```
+/// use general::pin_async_fn;
+///
+/// async fn double(x: u64) -> u64 {
+///     x * 2
+/// }
+///
+/// let f = pin_async_fn!(double);
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// assert_eq!(f(21).await, 42);
+/// # });
+/// ```
+#[macro_export]
+macro_rules! pin_async_fn {
+    ($f:expr) => {{
+        let f = $f;
+        move |x| {
+            let fut: $crate::fwk::compose::BoxFuture<'_, _> = ::std::boxed::Box::pin(f(x));
+            fut
+        }
+    }};
+}
+
+/// Returns the async function that awaits `f` and then `g` on its output. The futures of `f` and `g`, and `g`
+/// itself, may borrow data that lives for `'a`.
+pub fn and_then_async<'a, A, B, C, FB, FC>(
+    f: impl Fn(A) -> FB,
+    g: impl Fn(B) -> FC + Send + Sync + 'a,
+) -> impl Fn(A) -> BoxFuture<'a, C>
+where
+    FB: Future<Output = B> + Send + 'a,
+    FC: Future<Output = C> + Send + 'a,
+{
+    let g = Arc::new(g);
+    move |a| {
+        let (fb, g) = (f(a), g.clone());
+        Box::pin(async move { g(fb.await).await })
+    }
+}
+
+/// Extension trait for the composition of async functions.
+pub trait AndThenAsync<A, FB: Future>: Fn(A) -> FB + Sized {
+    /// Returns the async function that awaits `self` and then `g` on its output. See [`and_then_async`].
+    fn and_then_async<'a, C, FC>(
+        self,
+        g: impl Fn(FB::Output) -> FC + Send + Sync + 'a,
+    ) -> impl Fn(A) -> BoxFuture<'a, C>
+    where
+        FB: Send + 'a,
+        FC: Future<Output = C> + Send + 'a,
+    {
+        and_then_async(self, g)
+    }
+}
+
+impl<A, FB: Future, F: Fn(A) -> FB> AndThenAsync<A, FB> for F {}
+
+/// Composes async functions in application order: `pipe_async!(f, g, h)` returns a [`BoxFuture`] that awaits `f`,
+/// `g` and `h` in turn. With a leading `x =>`, applies the functions to `x` instead.
+///
+/// ```
+/// use general::pipe_async;
+///
+/// async fn inc(x: u64) -> u64 {
+///     x + 1
+/// }
+///
+/// let f = pipe_async!(inc, |x| async move { x * 2 }, inc);
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// assert_eq!(f(4).await, 11);
+/// assert_eq!(pipe_async!(4 => inc, inc).await, 6);
+/// # });
+/// ```
+#[macro_export]
+macro_rules! pipe_async {
+    ($x:expr => $($f:expr),+ $(,)?) => {
+        $crate::pipe_async!($($f),+)($x)
+    };
+    ($f:expr $(,)?) => {
+        $crate::pin_async_fn!($f)
+    };
+    ($f:expr, $g:expr $(, $rest:expr)* $(,)?) => {
+        $crate::pipe_async!($crate::fwk::compose::and_then_async($f, $g) $(, $rest)*)
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn add_one(x: i32) -> i32 {
+        x + 1
+    }
+
+    fn to_string(x: i32) -> String {
+        x.to_string()
+    }
+
+    fn parse(s: &str) -> Result<i32, String> {
+        s.parse().map_err(|_| format!("not a number: {s}"))
+    }
+
+    fn positive(x: i32) -> Result<u32, &'static str> {
+        u32::try_from(x).map_err(|_| "negative")
+    }
+
+    fn block_on<T>(fut: impl Future<Output = T>) -> T {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(fut)
+    }
+
+    #[test]
+    fn test_compose_and_pipe() {
+        let f: fn(i32) -> String = |x| compose!(to_string, add_one)(x);
+        assert_eq!(f(5), "6");
+        assert_eq!(pipe!(add_one, to_string)(5), "6");
+        assert_eq!(pipe!(5 => add_one, add_one, to_string), "7");
+        assert_eq!(compose!(add_one)(1), 2);
+
+        let offset = 10;
+        let g = pipe!(add_one, move |x| x * offset, |x| x - 1,);
+        assert_eq!(g(1), 19);
+        assert_eq!(g(2), 29);
+        assert_eq!(compose!(|x| x - 1, move |x| x * offset, add_one)(1), 19);
+    }
+
+    #[test]
+    fn test_traits() {
+        let f = add_one.and_then(|x| x * 2).and_then(to_string);
+        assert_eq!(f(1), "4");
+        let g = to_string.compose(|x| x * 2).compose(add_one);
+        assert_eq!(g(1), "4");
+    }
+
+    #[test]
+    fn test_kleisli() {
+        let f = try_pipe!(parse, |x| Ok::<_, String>(x * 2), positive);
+        assert_eq!(f("21"), Ok(42));
+        assert_eq!(f("-1"), Err("negative".to_owned()));
+        assert_eq!(f("x"), Err("not a number: x".to_owned()));
+        assert_eq!(try_pipe!("3" => parse, positive), Ok(3));
+
+        let g = parse.try_and_then(positive);
+        assert_eq!(g("-3"), Err("negative".to_owned()));
+    }
+
+    async fn double(x: u64) -> u64 {
+        x * 2
+    }
+
+    async fn describe(x: u64) -> String {
+        format!("<{x}>")
+    }
+
+    #[test]
+    fn test_async() {
+        let f = pin_async_fn!(double);
+        assert_eq!(block_on(f(2)), 4);
+
+        let g = pipe_async!(double, |x| async move { x + 1 }, describe);
+        assert_eq!(block_on(g(2)), "<5>");
+        assert_eq!(block_on(g(3)), "<7>");
+        assert_eq!(block_on(pipe_async!(1 => double, double)), 4);
+
+        let h = double.and_then_async(describe);
+        assert_eq!(block_on(h(21)), "<42>");
+    }
+
+    #[test]
+    fn test_async_borrowing() {
+        // Neither the functions nor their futures are `'static`.
+        let table = vec![10, 20, 30];
+        let lookup = |i: usize| {
+            let table = &table;
+            async move { table[i] }
+        };
+        let suffix = String::from("!");
+        let describe = |x: u64| {
+            let suffix = &suffix;
+            async move { format!("{x}{suffix}") }
+        };
+
+        let f = pipe_async!(lookup, double, describe);
+        assert_eq!(block_on(f(1)), "40!");
+        let g = lookup.and_then_async(double);
+        assert_eq!(block_on(g(2)), 60);
+
+        // Futures holding a `!Sync` value across an await point are `Send` and can be composed.
+        let h = pipe_async!(
+            |x: u64| async move {
+                let cell = std::cell::Cell::new(x);
+                tokio::task::yield_now().await;
+                cell.get()
+            },
+            double,
+        );
+        assert_eq!(block_on(h(4)), 8);
+    }
+}
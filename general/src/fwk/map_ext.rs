@@ -0,0 +1,704 @@
+//! Extension trait [`MapExt`] with map, filter, grouping, aggregation, join and diff methods implemented directly on [`HashMap`],
+//! [`BTreeMap`] and [`IndexMap`].
+//!
+//! The methods return maps of the same kind as `self`, given by [`MapExt::Map`]: a [`HashMap`] or [`IndexMap`]
+//! keeps its hasher type, a [`BTreeMap`] is sorted by key, and an [`IndexMap`] keeps the order of `self`.
+//! [`MapExt::map_entries_into`] converts between map kinds. Results are built through [`MapBuild`], which other
+//! map types can implement to be used as results.
+//!
+//! The borrowing methods clone the keys and values that they copy. For large maps that are no longer needed, the
+//! consuming `into_*` methods move the keys and values instead, and the in-place methods
+//! ([`MapExt::retain_map`], [`MapExt::map_values_in_place`]) update `self` without allocating.
+
+use super::aggregate::Aggregator;
+use indexmap::IndexMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::{BuildHasher, Hash},
+};
+
+//=================
+// MapBuild
+
+/// Maps that can be built entry by entry, used as the results of the [`MapExt`] methods.
+pub trait MapBuild<K, V>: Default + FromIterator<(K, V)> {
+    /// Inserts `v` for key `k`, returning the replaced value, if any.
+    fn insert_entry(&mut self, k: K, v: V) -> Option<V>;
+
+    /// Returns the value for key `k`, first inserting `default()` if there is none.
+    fn value_or_insert_with(&mut self, k: K, default: impl FnOnce() -> V) -> &mut V;
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Default> MapBuild<K, V> for HashMap<K, V, S> {
+    fn insert_entry(&mut self, k: K, v: V) -> Option<V> {
+        self.insert(k, v)
+    }
+
+    fn value_or_insert_with(&mut self, k: K, default: impl FnOnce() -> V) -> &mut V {
+        self.entry(k).or_insert_with(default)
+    }
+}
+
+impl<K: Ord, V> MapBuild<K, V> for BTreeMap<K, V> {
+    fn insert_entry(&mut self, k: K, v: V) -> Option<V> {
+        self.insert(k, v)
+    }
+
+    fn value_or_insert_with(&mut self, k: K, default: impl FnOnce() -> V) -> &mut V {
+        self.entry(k).or_insert_with(default)
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Default> MapBuild<K, V> for IndexMap<K, V, S> {
+    fn insert_entry(&mut self, k: K, v: V) -> Option<V> {
+        self.insert(k, v)
+    }
+
+    fn value_or_insert_with(&mut self, k: K, default: impl FnOnce() -> V) -> &mut V {
+        self.entry(k).or_insert_with(default)
+    }
+}
+
+//=================
+// MapDiff
+
+/// Differences between two maps, as returned by [`MapExt::diff`]. Each field is a map of the same kind as the
+/// compared maps.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MapDiff<M, C> {
+    /// Entries whose keys are only in the second map.
+    pub added: M,
+    /// Entries whose keys are only in the first map.
+    pub removed: M,
+    /// Keys in both maps with different values, mapped to the `(old, new)` values.
+    pub changed: C,
+    /// Entries with the same key and value in both maps.
+    pub unchanged: M,
+}
+
+/// [`MapDiff`] of maps of the kind of `M`, with keys `K` and values `V`.
+pub type MapDiffOf<M, K, V> =
+    MapDiff<<M as MapExt<K, V>>::Map<K, V>, <M as MapExt<K, V>>::Map<K, (V, V)>>;
+
+impl<M, C> MapDiff<M, C>
+where
+    for<'a> &'a M: IntoIterator,
+    for<'a> &'a C: IntoIterator,
+{
+    /// Whether the compared maps are equal, i.e., there are no added, removed or changed entries.
+    pub fn is_empty(&self) -> bool {
+        self.added.into_iter().next().is_none()
+            && self.removed.into_iter().next().is_none()
+            && self.changed.into_iter().next().is_none()
+    }
+}
+
+//=================
+// MapExt
+
+/// Map, filter, grouping and join methods for maps with keys `K` and values `V`.
+///
+/// Only [`entries`](Self::entries) and [`lookup`](Self::lookup) are required. The other methods borrow `self` and
+/// clone the keys and values that they copy to their results. Each method requires the result map type to
+/// implement [`MapBuild`], which holds for the implementations in this module whenever the result keys are
+/// [`Hash`] + [`Eq`] (for [`HashMap`] and [`IndexMap`]) or [`Ord`] (for [`BTreeMap`]).
+pub trait MapExt<K, V> {
+    /// Map of the same kind as `Self`, with keys `K1` and values `V1`.
+    type Map<K1, V1>;
+
+    /// Iterator over the entries of `self`, in its iteration order.
+    fn entries<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a V)>
+    where
+        K: 'a,
+        V: 'a;
+
+    /// Value for key `k`, if any.
+    fn lookup(&self, k: &K) -> Option<&V>;
+
+    /// Iterator over the entries of `self` with mutable values, in its iteration order.
+    fn entries_mut<'a>(&'a mut self) -> impl Iterator<Item = (&'a K, &'a mut V)>
+    where
+        K: 'a,
+        V: 'a;
+
+    /// Applies `f` to each entry of `self`, which may update the value, and keeps only the entries for which it
+    /// returns `true`. Does not allocate.
+    fn retain_map(&mut self, f: impl FnMut(&K, &mut V) -> bool);
+
+    /// Map with the keys of `self` and the values obtained by applying `f` to the values of `self`.
+    fn map_values<V1>(&self, mut f: impl FnMut(&V) -> V1) -> Self::Map<K, V1>
+    where
+        K: Clone,
+        Self::Map<K, V1>: MapBuild<K, V1>,
+    {
+        self.entries().map(|(k, v)| (k.clone(), f(v))).collect()
+    }
+
+    /// Map with the entries obtained by applying `f` to the entries of `self`. For entries mapped to the same key,
+    /// the last one in the iteration order of `self` is kept.
+    fn map_entries<K1, V1>(&self, f: impl FnMut(&K, &V) -> (K1, V1)) -> Self::Map<K1, V1>
+    where
+        Self::Map<K1, V1>: MapBuild<K1, V1>,
+    {
+        self.map_entries_into(f)
+    }
+
+    /// Like [`map_entries`](Self::map_entries) but collects the entries into a map of any type `M`, e.g., to
+    /// convert a [`HashMap`] to a [`BTreeMap`].
+    fn map_entries_into<M, K1, V1>(&self, mut f: impl FnMut(&K, &V) -> (K1, V1)) -> M
+    where
+        M: FromIterator<(K1, V1)>,
+    {
+        self.entries().map(|(k, v)| f(k, v)).collect()
+    }
+
+    /// Map with the entries of `self` that satisfy predicate `f`.
+    fn filter(&self, mut f: impl FnMut(&K, &V) -> bool) -> Self::Map<K, V>
+    where
+        K: Clone,
+        V: Clone,
+        Self::Map<K, V>: MapBuild<K, V>,
+    {
+        self.entries()
+            .filter(|(k, v)| f(k, v))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    /// Splits the entries of `self` into a map of those that satisfy predicate `f` and a map of the others.
+    fn partition(&self, mut f: impl FnMut(&K, &V) -> bool) -> (Self::Map<K, V>, Self::Map<K, V>)
+    where
+        K: Clone,
+        V: Clone,
+        Self::Map<K, V>: MapBuild<K, V>,
+    {
+        let (mut yes, mut no) = (Self::Map::default(), Self::Map::default());
+        for (k, v) in self.entries() {
+            let target = if f(k, v) { &mut yes } else { &mut no };
+            target.insert_entry(k.clone(), v.clone());
+        }
+        (yes, no)
+    }
+
+    /// Map from the groups `key_grouper(k)` of the keys of `self` to the aggregation of the values in each group.
+    /// The aggregate of a group is created by the seed factory `seed` and updated with `value_aggregator` for each
+    /// of its values. See [`group_aggregate`](Self::group_aggregate) for the built-in aggregators.
+    fn aggregate_by<G, V1>(
+        &self,
+        mut key_grouper: impl FnMut(&K) -> G,
+        mut value_aggregator: impl FnMut(&mut V1, &V),
+        mut seed: impl FnMut() -> V1,
+    ) -> Self::Map<G, V1>
+    where
+        Self::Map<G, V1>: MapBuild<G, V1>,
+    {
+        let mut res = Self::Map::<G, V1>::default();
+        for (k, v) in self.entries() {
+            let agg = res.value_or_insert_with(key_grouper(k), &mut seed);
+            value_aggregator(agg, v);
+        }
+        res
+    }
+
+    /// Map from the groups `key_grouper(k, v)` of the entries of `self` to the aggregation of their values with
+    /// `agg`, e.g., `map.group_aggregate(|k, _| k.len(), mean())`. See [`super::aggregate`] for the built-in
+    /// aggregators, including the grouping ones that produce nested maps.
+    fn group_aggregate<'a, G, A>(
+        &'a self,
+        mut key_grouper: impl FnMut(&K, &V) -> G,
+        mut agg: A,
+    ) -> Self::Map<G, A::Output>
+    where
+        K: 'a,
+        V: 'a,
+        A: Aggregator<&'a V>,
+        Self::Map<G, A::Acc>: MapBuild<G, A::Acc> + IntoIterator<Item = (G, A::Acc)>,
+        Self::Map<G, A::Output>: MapBuild<G, A::Output>,
+    {
+        let mut accs = Self::Map::<G, A::Acc>::default();
+        for (k, v) in self.entries() {
+            let acc = accs.value_or_insert_with(key_grouper(k, v), || agg.seed());
+            agg.add(acc, v);
+        }
+        accs.into_iter()
+            .map(|(g, acc)| (g, agg.finish(acc)))
+            .collect()
+    }
+
+    /// Map from the groups `f(k, v)` of the entries of `self` to maps with the entries in each group.
+    fn group_by<G>(&self, mut f: impl FnMut(&K, &V) -> G) -> Self::Map<G, Self::Map<K, V>>
+    where
+        K: Clone,
+        V: Clone,
+        Self::Map<K, V>: MapBuild<K, V>,
+        Self::Map<G, Self::Map<K, V>>: MapBuild<G, Self::Map<K, V>>,
+    {
+        let mut res = Self::Map::<G, Self::Map<K, V>>::default();
+        for (k, v) in self.entries() {
+            res.value_or_insert_with(f(k, v), Default::default)
+                .insert_entry(k.clone(), v.clone());
+        }
+        res
+    }
+
+    /// Map from the values of `self` to their keys. For a value with several keys, the last key in the iteration
+    /// order of `self` is kept.
+    fn invert(&self) -> Self::Map<V, K>
+    where
+        K: Clone,
+        V: Clone,
+        Self::Map<V, K>: MapBuild<V, K>,
+    {
+        self.entries()
+            .map(|(k, v)| (v.clone(), k.clone()))
+            .collect()
+    }
+
+    /// Map with the keys that are in both `self` and `other`, and values obtained by applying `f` to each key and
+    /// its values in `self` and `other`. The entries are in the iteration order of `self`.
+    fn zip_with<V2, V3>(
+        &self,
+        other: &impl MapExt<K, V2>,
+        mut f: impl FnMut(&K, &V, &V2) -> V3,
+    ) -> Self::Map<K, V3>
+    where
+        K: Clone,
+        Self::Map<K, V3>: MapBuild<K, V3>,
+    {
+        self.entries()
+            .filter_map(|(k, v)| other.lookup(k).map(|w| (k.clone(), f(k, v, w))))
+            .collect()
+    }
+
+    /// Map with the entries of `self` and `other`, with `f(k, v, w)` as the value of each key `k` in both, where `v`
+    /// and `w` are its values in `self` and `other`. The entries of `self` come first in the iteration order.
+    fn merge_with(
+        &self,
+        other: &impl MapExt<K, V>,
+        mut f: impl FnMut(&K, &V, &V) -> V,
+    ) -> Self::Map<K, V>
+    where
+        K: Clone,
+        V: Clone,
+        Self::Map<K, V>: MapBuild<K, V>,
+    {
+        self.outer_join(other, |k, v, w| match (v, w) {
+            (Some(v), Some(w)) => f(k, v, w),
+            (Some(v), None) | (None, Some(v)) => v.clone(),
+            (None, None) => unreachable!("each key is in at least one map"),
+        })
+    }
+
+    /// Map with the keys of `self` and `other`, and values obtained by applying `f` to each key and its values in
+    /// `self` and `other`, if any. At least one of the values is present. The keys of `self` come first in the
+    /// iteration order, followed by those only in `other`.
+    fn outer_join<V2, V3>(
+        &self,
+        other: &impl MapExt<K, V2>,
+        mut f: impl FnMut(&K, Option<&V>, Option<&V2>) -> V3,
+    ) -> Self::Map<K, V3>
+    where
+        K: Clone,
+        Self::Map<K, V3>: MapBuild<K, V3>,
+    {
+        let mut res = Self::Map::<K, V3>::default();
+        for (k, v) in self.entries() {
+            res.insert_entry(k.clone(), f(k, Some(v), other.lookup(k)));
+        }
+        for (k, w) in other.entries() {
+            if self.lookup(k).is_none() {
+                res.insert_entry(k.clone(), f(k, None, Some(w)));
+            }
+        }
+        res
+    }
+
+    /// Differences from `self` to `other`: the entries added, removed, changed and unchanged in `other`.
+    fn diff(&self, other: &impl MapExt<K, V>) -> MapDiffOf<Self, K, V>
+    where
+        K: Clone,
+        V: Clone + PartialEq,
+        Self::Map<K, V>: MapBuild<K, V>,
+        Self::Map<K, (V, V)>: MapBuild<K, (V, V)>,
+    {
+        self.diff_by(other, V::eq)
+    }
+
+    /// Like [`diff`](Self::diff) but with `eq` deciding whether values are unchanged, e.g., to compare floats
+    /// approximately.
+    fn diff_by(
+        &self,
+        other: &impl MapExt<K, V>,
+        mut eq: impl FnMut(&V, &V) -> bool,
+    ) -> MapDiffOf<Self, K, V>
+    where
+        K: Clone,
+        V: Clone,
+        Self::Map<K, V>: MapBuild<K, V>,
+        Self::Map<K, (V, V)>: MapBuild<K, (V, V)>,
+    {
+        let mut res = MapDiffOf::<Self, K, V>::default();
+        for (k, v) in self.entries() {
+            match other.lookup(k) {
+                None => {
+                    res.removed.insert_entry(k.clone(), v.clone());
+                }
+                Some(w) if eq(v, w) => {
+                    res.unchanged.insert_entry(k.clone(), v.clone());
+                }
+                Some(w) => {
+                    res.changed.insert_entry(k.clone(), (v.clone(), w.clone()));
+                }
+            }
+        }
+        for (k, w) in other.entries() {
+            if self.lookup(k).is_none() {
+                res.added.insert_entry(k.clone(), w.clone());
+            }
+        }
+        res
+    }
+
+    /// Like [`map_values`](Self::map_values) but consumes `self`, moving its keys and values instead of cloning
+    /// them. Allocates only the result map.
+    fn into_map_values<V1>(self, mut f: impl FnMut(V) -> V1) -> Self::Map<K, V1>
+    where
+        Self: Sized + IntoIterator<Item = (K, V)>,
+        Self::Map<K, V1>: MapBuild<K, V1>,
+    {
+        self.into_iter().map(|(k, v)| (k, f(v))).collect()
+    }
+
+    /// Like [`map_entries`](Self::map_entries) but consumes `self`, passing its keys and values to `f` by value.
+    fn into_map_entries<K1, V1>(self, mut f: impl FnMut(K, V) -> (K1, V1)) -> Self::Map<K1, V1>
+    where
+        Self: Sized + IntoIterator<Item = (K, V)>,
+        Self::Map<K1, V1>: MapBuild<K1, V1>,
+    {
+        self.into_iter().map(|(k, v)| f(k, v)).collect()
+    }
+
+    /// Like [`filter`](Self::filter) but consumes `self` and removes the other entries from it, without cloning or
+    /// allocating.
+    fn into_filter(mut self, mut f: impl FnMut(&K, &V) -> bool) -> Self
+    where
+        Self: Sized,
+    {
+        self.retain_map(|k, v| f(k, v));
+        self
+    }
+
+    /// Applies `f` to each value of `self`, in place.
+    fn map_values_in_place(&mut self, mut f: impl FnMut(&K, &mut V)) {
+        for (k, v) in self.entries_mut() {
+            f(k, v);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> MapExt<K, V> for HashMap<K, V, S> {
+    type Map<K1, V1> = HashMap<K1, V1, S>;
+
+    fn entries<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a V)>
+    where
+        K: 'a,
+        V: 'a,
+    {
+        self.iter()
+    }
+
+    fn lookup(&self, k: &K) -> Option<&V> {
+        self.get(k)
+    }
+
+    fn entries_mut<'a>(&'a mut self) -> impl Iterator<Item = (&'a K, &'a mut V)>
+    where
+        K: 'a,
+        V: 'a,
+    {
+        self.iter_mut()
+    }
+
+    fn retain_map(&mut self, f: impl FnMut(&K, &mut V) -> bool) {
+        self.retain(f)
+    }
+}
+
+impl<K: Ord, V> MapExt<K, V> for BTreeMap<K, V> {
+    type Map<K1, V1> = BTreeMap<K1, V1>;
+
+    fn entries<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a V)>
+    where
+        K: 'a,
+        V: 'a,
+    {
+        self.iter()
+    }
+
+    fn lookup(&self, k: &K) -> Option<&V> {
+        self.get(k)
+    }
+
+    fn entries_mut<'a>(&'a mut self) -> impl Iterator<Item = (&'a K, &'a mut V)>
+    where
+        K: 'a,
+        V: 'a,
+    {
+        self.iter_mut()
+    }
+
+    fn retain_map(&mut self, f: impl FnMut(&K, &mut V) -> bool) {
+        self.retain(f)
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> MapExt<K, V> for IndexMap<K, V, S> {
+    type Map<K1, V1> = IndexMap<K1, V1, S>;
+
+    fn entries<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a V)>
+    where
+        K: 'a,
+        V: 'a,
+    {
+        self.iter()
+    }
+
+    fn lookup(&self, k: &K) -> Option<&V> {
+        self.get(k)
+    }
+
+    fn entries_mut<'a>(&'a mut self) -> impl Iterator<Item = (&'a K, &'a mut V)>
+    where
+        K: 'a,
+        V: 'a,
+    {
+        self.iter_mut()
+    }
+
+    fn retain_map(&mut self, f: impl FnMut(&K, &mut V) -> bool) {
+        self.retain(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fwk::aggregate::{count, group_hash, histogram, max, mean};
+
+    fn btree() -> BTreeMap<u32, &'static str> {
+        BTreeMap::from([(1, "one"), (2, "two"), (3, "three"), (4, "four")])
+    }
+
+    /// Generic over the map kind, as the methods are available through the trait alone.
+    fn lengths<M: MapExt<u32, &'static str>>(m: &M) -> M::Map<u32, usize>
+    where
+        M::Map<u32, usize>: MapBuild<u32, usize>,
+    {
+        m.map_values(|v| v.len())
+    }
+
+    #[test]
+    fn test_map_values_and_entries() {
+        let b = btree();
+        assert_eq!(
+            lengths(&b),
+            BTreeMap::from([(1, 3), (2, 3), (3, 5), (4, 4)])
+        );
+
+        let h = b.iter().map(|(k, v)| (*k, *v)).collect::<HashMap<_, _>>();
+        assert_eq!(lengths(&h), HashMap::from([(1, 3), (2, 3), (3, 5), (4, 4)]));
+
+        let squares = b.map_entries(|k, v| (k * k, v.to_uppercase()));
+        assert_eq!(squares[&9], "THREE");
+
+        let converted: BTreeMap<u32, &str> = h.map_entries_into(|k, v| (*k, *v));
+        assert_eq!(converted, b);
+    }
+
+    #[test]
+    fn test_filter_and_partition() {
+        let b = btree();
+        assert_eq!(
+            b.filter(|k, _| k % 2 == 0),
+            BTreeMap::from([(2, "two"), (4, "four")])
+        );
+
+        let (short, long) = b.partition(|_, v| v.len() <= 3);
+        assert_eq!(short, BTreeMap::from([(1, "one"), (2, "two")]));
+        assert_eq!(long, BTreeMap::from([(3, "three"), (4, "four")]));
+    }
+
+    #[test]
+    fn test_aggregate_and_group_by() {
+        let b = btree();
+        let lengths_by_parity = b.aggregate_by(|k| k % 2, |acc, v| *acc += v.len(), || 0);
+        assert_eq!(lengths_by_parity, BTreeMap::from([(0, 7), (1, 8)]));
+
+        let h = b.iter().map(|(k, v)| (*k, *v)).collect::<HashMap<_, _>>();
+        let groups = h.group_by(|_, v| v.len());
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[&3], HashMap::from([(1, "one"), (2, "two")]));
+        assert_eq!(groups[&5], HashMap::from([(3, "three")]));
+    }
+
+    #[test]
+    fn test_invert_and_zip_with() {
+        let b = btree();
+        let inverted = b.invert();
+        assert_eq!(inverted["three"], 3);
+        assert_eq!(inverted.len(), 4);
+
+        let lengths = b.map_values(|v| v.len());
+        let parity = BTreeMap::from([(1, 'o'), (2, 'e'), (5, 'o')]);
+        let zipped = lengths.zip_with(&parity, |k, n, p| format!("{k}:{n}:{p}"));
+        assert_eq!(
+            zipped,
+            BTreeMap::from([(1, "1:3:o".to_owned()), (2, "2:3:e".to_owned())])
+        );
+    }
+
+    #[test]
+    fn test_index_map_keeps_order() {
+        let m = IndexMap::from([("c", 3), ("a", 1), ("b", 2), ("d", 4)]);
+
+        let doubled = m.map_values(|v| v * 2);
+        assert_eq!(
+            doubled.keys().copied().collect::<Vec<_>>(),
+            ["c", "a", "b", "d"]
+        );
+
+        let odd = m.filter(|_, v| v % 2 == 1);
+        assert_eq!(odd.into_iter().collect::<Vec<_>>(), [("c", 3), ("a", 1)]);
+
+        let groups = m.group_by(|_, v| v % 2 == 0);
+        assert_eq!(groups.keys().copied().collect::<Vec<_>>(), [false, true]);
+        assert_eq!(
+            groups[&true].keys().copied().collect::<Vec<_>>(),
+            ["b", "d"]
+        );
+
+        let other = HashMap::from([("a", 10), ("d", 40)]);
+        let sums = m.zip_with(&other, |_, v, w| v + w);
+        assert_eq!(sums.into_iter().collect::<Vec<_>>(), [("a", 11), ("d", 44)]);
+    }
+
+    #[test]
+    fn test_consuming_and_in_place() {
+        let h = HashMap::from([("a".to_owned(), vec![1, 2]), ("b".to_owned(), vec![3])]);
+
+        let sums = h.clone().into_map_values(|v| v.into_iter().sum::<i32>());
+        assert_eq!(
+            sums,
+            HashMap::from([("a".to_owned(), 3), ("b".to_owned(), 3)])
+        );
+
+        let swapped = h.clone().into_map_entries(|k, v| (v.len(), k));
+        assert_eq!(
+            swapped,
+            HashMap::from([(2, "a".to_owned()), (1, "b".to_owned())])
+        );
+
+        let long = h.clone().into_filter(|_, v| v.len() > 1);
+        assert_eq!(long, HashMap::from([("a".to_owned(), vec![1, 2])]));
+
+        let mut b = BTreeMap::from_iter(h);
+        b.map_values_in_place(|k, v| v.push(k.len() as i32 * 10));
+        assert_eq!(b["a"], [1, 2, 10]);
+        b.retain_map(|k, v| {
+            v.retain(|x| x % 2 == 1);
+            k != "a"
+        });
+        assert_eq!(b, BTreeMap::from([("b".to_owned(), vec![3])]));
+
+        let mut m = IndexMap::from([(3, 'c'), (1, 'a'), (2, 'b')]);
+        m.retain_map(|k, v| {
+            *v = v.to_ascii_uppercase();
+            *k != 1
+        });
+        assert_eq!(m.into_iter().collect::<Vec<_>>(), [(3, 'C'), (2, 'B')]);
+    }
+
+    #[test]
+    fn test_group_aggregate() {
+        let latencies = HashMap::from([
+            ((1, "get"), 10.),
+            ((1, "put"), 30.),
+            ((2, "get"), 20.),
+            ((2, "put"), 60.),
+            ((3, "get"), 40.),
+        ]);
+
+        let by_endpoint = latencies.group_aggregate(|k, _| k.1, mean());
+        assert_eq!(
+            by_endpoint,
+            HashMap::from([("get", Some(70. / 3.)), ("put", Some(45.))])
+        );
+
+        let slow_by_thread =
+            latencies.group_aggregate(|k, _| k.0, histogram(|v: &&f64| **v >= 30.));
+        assert_eq!(slow_by_thread[&2], BTreeMap::from([(false, 1), (true, 1)]));
+
+        // Two levels, into a BTreeMap of HashMaps.
+        let b = BTreeMap::from_iter(latencies);
+        let nested = b.group_aggregate(|k, _| k.0 % 2, group_hash(|v: &&f64| **v > 25., count()));
+        assert_eq!(nested[&0], HashMap::from([(false, 1), (true, 1)]));
+        assert_eq!(nested[&1], HashMap::from([(false, 1), (true, 2)]));
+        assert_eq!(
+            b.group_aggregate(|_, _| (), max()),
+            BTreeMap::from([((), Some(&60.))])
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = BTreeMap::from([("a", 1), ("b", 2), ("c", 3)]);
+        let new = HashMap::from([("b", 2), ("c", 4), ("d", 5)]);
+        let diff = old.diff(&new);
+        assert_eq!(
+            diff,
+            MapDiff {
+                added: BTreeMap::from([("d", 5)]),
+                removed: BTreeMap::from([("a", 1)]),
+                changed: BTreeMap::from([("c", (3, 4))]),
+                unchanged: BTreeMap::from([("b", 2)]),
+            }
+        );
+        assert!(!diff.is_empty());
+        assert!(old.diff(&old).is_empty());
+
+        let p50 = IndexMap::from([("get", 10_f64), ("put", 20.)]);
+        let p50_new = IndexMap::from([("put", 20.5), ("get", 10.01)]);
+        let diff = p50.diff_by(&p50_new, |a, b| (a - b).abs() < 0.1);
+        assert_eq!(diff.changed, IndexMap::from([("put", (20.0, 20.5))]));
+        assert_eq!(diff.unchanged, IndexMap::from([("get", 10.0)]));
+    }
+
+    #[test]
+    fn test_merge_and_outer_join() {
+        let a = BTreeMap::from([(3, 30), (1, 10)]);
+        let b = HashMap::from([(2, 200), (3, 300)]);
+        assert_eq!(
+            a.merge_with(&b, |_, x, y| x + y),
+            BTreeMap::from([(1, 10), (2, 200), (3, 330)])
+        );
+
+        let joined = a.outer_join(&b, |_, x, y| (x.copied(), y.copied()));
+        assert_eq!(
+            joined.into_iter().collect::<Vec<_>>(),
+            [
+                (1, (Some(10), None)),
+                (2, (None, Some(200))),
+                (3, (Some(30), Some(300)))
+            ]
+        );
+
+        // IndexMap keeps the keys of `self` first, then the new keys of `other`.
+        let m = IndexMap::from([("z", 1), ("a", 2)]);
+        let n = IndexMap::from([("b", 3), ("z", 4)]);
+        let merged = m.merge_with(&n, |_, x, y| x.max(y).to_owned());
+        assert_eq!(
+            merged.into_iter().collect::<Vec<_>>(),
+            [("z", 4), ("a", 2), ("b", 3)]
+        );
+    }
+}
@@ -0,0 +1,347 @@
+//! Stable merge sorts: an allocation-free in-place merge sort and a buffered merge sort with natural run detection.
+//!
+//! Both share the API of [`super::sort`]: a plain function for [`Ord`] items, a `_by` one taking a comparator, and
+//! a `_by_key` one taking a key extraction function. Items that compare equal keep their relative order.
+
+use super::sort::{INSERTION_SORT_THRESHOLD, compare_by_key, insertion_sort_with, less_from};
+use std::cmp::Ordering;
+
+//=================
+// In-place merge sort
+
+/// Merges the sorted `arr[..mid]` and `arr[mid..]` without a buffer, by recursively rotating the middle blocks into
+/// place. Takes O(n log n) time.
+fn merge_in_place<T>(arr: &mut [T], mid: usize, is_less: &mut impl FnMut(&T, &T) -> bool) {
+    let len = arr.len();
+    if mid == 0 || mid == len || !is_less(&arr[mid], &arr[mid - 1]) {
+        return;
+    }
+    if len == 2 {
+        arr.swap(0, 1);
+        return;
+    }
+
+    // Split the longer run in half and the shorter one at the matching position. Right items equal to the left cut
+    // item stay after it, and left items equal to the right cut item stay before it, which preserves stability.
+    let (cut1, cut2) = if mid >= len - mid {
+        let cut1 = mid / 2;
+        let cut2 = mid + arr[mid..].partition_point(|x| is_less(x, &arr[cut1]));
+        (cut1, cut2)
+    } else {
+        let cut2 = mid + (len - mid) / 2;
+        let cut1 = arr[..mid].partition_point(|x| !is_less(&arr[cut2], x));
+        (cut1, cut2)
+    };
+
+    arr[cut1..cut2].rotate_left(mid - cut1);
+    let new_mid = cut1 + (cut2 - mid);
+    let (left, right) = arr.split_at_mut(new_mid);
+    merge_in_place(left, cut1, is_less);
+    merge_in_place(right, cut2 - new_mid, is_less);
+}
+
+fn merge_sort_in_place_with<T>(arr: &mut [T], is_less: &mut impl FnMut(&T, &T) -> bool) {
+    let len = arr.len();
+    if len <= INSERTION_SORT_THRESHOLD {
+        insertion_sort_with(arr, is_less);
+        return;
+    }
+    let mid = len / 2;
+    merge_sort_in_place_with(&mut arr[..mid], is_less);
+    merge_sort_in_place_with(&mut arr[mid..], is_less);
+    merge_in_place(arr, mid, is_less);
+}
+
+/// Implementation of merge sort that merges by rotations. Stable, O(n log² n) worst case.
+///
+/// Sorts `arr` in place without heap allocations. Stack usage is O(log n).
+pub fn merge_sort_in_place<T: Ord>(arr: &mut [T]) {
+    merge_sort_in_place_by(arr, T::cmp)
+}
+
+/// Like [`merge_sort_in_place`] but with comparator `compare`.
+pub fn merge_sort_in_place_by<T>(arr: &mut [T], compare: impl FnMut(&T, &T) -> Ordering) {
+    merge_sort_in_place_with(arr, &mut less_from(compare))
+}
+
+/// Like [`merge_sort_in_place`] but compares the keys extracted by `f`.
+pub fn merge_sort_in_place_by_key<T, K: Ord>(arr: &mut [T], f: impl FnMut(&T) -> K) {
+    merge_sort_in_place_by(arr, compare_by_key(f))
+}
+
+//=================
+// Buffered merge sort with natural runs
+
+/// Runs shorter than this are extended with insertion sort before merging.
+const MIN_RUN: usize = 32;
+
+/// Run of sorted items `arr[start..start + len]`.
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    start: usize,
+    len: usize,
+}
+
+/// Moves items so that `arr[p]` receives the item previously at `arr[perm[p]]`. Resets `perm` to the identity.
+fn apply_permutation<T>(arr: &mut [T], perm: &mut [usize]) {
+    for start in 0..perm.len() {
+        let mut cur = start;
+        while perm[cur] != cur {
+            let next = perm[cur];
+            perm[cur] = cur;
+            if next == start {
+                break;
+            }
+            arr.swap(cur, next);
+            cur = next;
+        }
+    }
+}
+
+/// Merges the sorted `arr[..mid]` and `arr[mid..]`. The merged order is computed in `buf` as indices, which are
+/// then applied by swapping, so items need not be [`Clone`]. Takes O(n) time.
+pub(crate) fn merge_buffered<T>(
+    arr: &mut [T],
+    mid: usize,
+    buf: &mut Vec<usize>,
+    is_less: &mut impl FnMut(&T, &T) -> bool,
+) {
+    let len = arr.len();
+    if mid == 0 || mid == len || !is_less(&arr[mid], &arr[mid - 1]) {
+        return;
+    }
+
+    buf.clear();
+    let (mut i, mut j) = (0, mid);
+    while i < mid && j < len {
+        // Take from the right run only if strictly less, which preserves stability.
+        if is_less(&arr[j], &arr[i]) {
+            buf.push(j);
+            j += 1;
+        } else {
+            buf.push(i);
+            i += 1;
+        }
+    }
+    buf.extend(i..mid);
+    buf.extend(j..len);
+
+    apply_permutation(arr, buf);
+}
+
+/// Finds the run that starts at `arr[0]`, reversing it if strictly descending, and returns its length.
+fn find_run<T>(arr: &[T], is_less: &mut impl FnMut(&T, &T) -> bool) -> usize {
+    let len = arr.len();
+    if len < 2 {
+        return len;
+    }
+    let mut end = 2;
+    if is_less(&arr[1], &arr[0]) {
+        while end < len && is_less(&arr[end], &arr[end - 1]) {
+            end += 1;
+        }
+    } else {
+        while end < len && !is_less(&arr[end], &arr[end - 1]) {
+            end += 1;
+        }
+    }
+    end
+}
+
+fn merge_runs<T>(
+    arr: &mut [T],
+    runs: &mut Vec<Run>,
+    at: usize,
+    buf: &mut Vec<usize>,
+    is_less: &mut impl FnMut(&T, &T) -> bool,
+) {
+    let (left, right) = (runs[at], runs[at + 1]);
+    let merged = &mut arr[left.start..right.start + right.len];
+    merge_buffered(merged, left.len, buf, is_less);
+    runs[at].len += right.len;
+    runs.remove(at + 1);
+}
+
+/// Merges runs at the top of the stack until their lengths satisfy timsort's invariants, which keeps merges
+/// balanced and the stack O(log n) deep.
+fn collapse<T>(
+    arr: &mut [T],
+    runs: &mut Vec<Run>,
+    buf: &mut Vec<usize>,
+    is_less: &mut impl FnMut(&T, &T) -> bool,
+) {
+    while runs.len() >= 2 {
+        let n = runs.len();
+        let at = if (n >= 3 && runs[n - 3].len <= runs[n - 2].len + runs[n - 1].len)
+            || (n >= 4 && runs[n - 4].len <= runs[n - 3].len + runs[n - 2].len)
+        {
+            if runs[n - 3].len < runs[n - 1].len {
+                n - 3
+            } else {
+                n - 2
+            }
+        } else if runs[n - 2].len <= runs[n - 1].len {
+            n - 2
+        } else {
+            return;
+        };
+        merge_runs(arr, runs, at, buf, is_less);
+    }
+}
+
+pub(crate) fn merge_sort_with<T>(arr: &mut [T], is_less: &mut impl FnMut(&T, &T) -> bool) {
+    let len = arr.len();
+    if len <= INSERTION_SORT_THRESHOLD {
+        insertion_sort_with(arr, is_less);
+        return;
+    }
+
+    let mut runs = Vec::new();
+    let mut buf = Vec::with_capacity(len);
+    let mut start = 0;
+    while start < len {
+        let rest = &mut arr[start..];
+        let mut run_len = find_run(rest, is_less);
+        if run_len >= 2 && is_less(&rest[1], &rest[0]) {
+            rest[..run_len].reverse();
+        }
+        if run_len < MIN_RUN {
+            run_len = MIN_RUN.min(rest.len());
+            insertion_sort_with(&mut rest[..run_len], is_less);
+        }
+
+        runs.push(Run {
+            start,
+            len: run_len,
+        });
+        start += run_len;
+        collapse(arr, &mut runs, &mut buf, is_less);
+    }
+
+    while runs.len() >= 2 {
+        let at = runs.len() - 2;
+        merge_runs(arr, &mut runs, at, &mut buf, is_less);
+    }
+}
+
+/// Implementation of natural merge sort in the style of timsort: detects ascending and strictly descending runs,
+/// extends short runs with insertion sort, and merges them with a buffer. Stable, O(n log n) worst case and O(n) for
+/// presorted input.
+///
+/// Sorts `arr` in place, using a heap-allocated buffer of `arr.len()` indices.
+pub fn merge_sort<T: Ord>(arr: &mut [T]) {
+    merge_sort_by(arr, T::cmp)
+}
+
+/// Like [`merge_sort`] but with comparator `compare`.
+pub fn merge_sort_by<T>(arr: &mut [T], compare: impl FnMut(&T, &T) -> Ordering) {
+    merge_sort_with(arr, &mut less_from(compare))
+}
+
+/// Like [`merge_sort`] but compares the keys extracted by `f`.
+pub fn merge_sort_by_key<T, K: Ord>(arr: &mut [T], f: impl FnMut(&T) -> K) {
+    merge_sort_by(arr, compare_by_key(f))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Records with a sort key and their original position, to detect reordering of equal keys.
+    fn records(keys: &[u8]) -> Vec<(u8, usize)> {
+        keys.iter().copied().zip(0..).collect()
+    }
+
+    fn key(r: &(u8, usize)) -> u8 {
+        r.0
+    }
+
+    #[test]
+    fn test_runs() {
+        let ascending = (0..1000).collect::<Vec<i32>>();
+        let descending = (0..1000).rev().collect::<Vec<i32>>();
+        let saw = (0..1000).map(|x| x % 100).collect::<Vec<i32>>();
+        let mixed = (0..500).chain((0..500).rev()).collect::<Vec<i32>>();
+        for v in [ascending, descending, saw, mixed] {
+            let mut expected = v.clone();
+            expected.sort();
+            let mut a = v.clone();
+            merge_sort(&mut a);
+            assert_eq!(a, expected);
+            let mut a = v.clone();
+            merge_sort_in_place(&mut a);
+            assert_eq!(a, expected);
+        }
+    }
+
+    #[test]
+    fn test_stability_on_descending_keys() {
+        // Runs of equal keys inside a descending sequence must not be reversed.
+        let keys = (0..200).map(|i| (200 - i) as u8 / 10).collect::<Vec<_>>();
+        let mut expected = records(&keys);
+        expected.sort_by_key(key);
+
+        let mut a = records(&keys);
+        merge_sort_by_key(&mut a, key);
+        assert_eq!(a, expected);
+
+        let mut a = records(&keys);
+        merge_sort_in_place_by_key(&mut a, key);
+        assert_eq!(a, expected);
+    }
+
+    #[test]
+    fn test_apply_permutation() {
+        let mut arr = ['a', 'b', 'c', 'd', 'e'];
+        let mut perm = [3, 0, 4, 1, 2];
+        apply_permutation(&mut arr, &mut perm);
+        assert_eq!(arr, ['d', 'a', 'e', 'b', 'c']);
+        assert_eq!(perm, [0, 1, 2, 3, 4]);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_stable_by_key(keys in prop::collection::vec(0..10_u8, 0..500)) {
+            let mut expected = records(&keys);
+            expected.sort_by_key(key);
+
+            let mut a = records(&keys);
+            merge_sort_by_key(&mut a, key);
+            prop_assert_eq!(&a, &expected);
+
+            let mut a = records(&keys);
+            merge_sort_in_place_by_key(&mut a, key);
+            prop_assert_eq!(&a, &expected);
+        }
+
+        #[test]
+        fn prop_stable_by(keys in prop::collection::vec(0..10_u8, 0..500)) {
+            let reverse = |a: &(u8, usize), b: &(u8, usize)| b.0.cmp(&a.0);
+            let mut expected = records(&keys);
+            expected.sort_by(reverse);
+
+            let mut a = records(&keys);
+            merge_sort_by(&mut a, reverse);
+            prop_assert_eq!(&a, &expected);
+
+            let mut a = records(&keys);
+            merge_sort_in_place_by(&mut a, reverse);
+            prop_assert_eq!(&a, &expected);
+        }
+
+        #[test]
+        fn prop_sorts_match_std(v in prop::collection::vec(any::<i32>(), 0..500)) {
+            let mut expected = v.clone();
+            expected.sort();
+
+            let mut a = v.clone();
+            merge_sort(&mut a);
+            prop_assert_eq!(&a, &expected);
+
+            let mut a = v.clone();
+            merge_sort_in_place(&mut a);
+            prop_assert_eq!(&a, &expected);
+        }
+    }
+}
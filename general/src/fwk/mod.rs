@@ -1,13 +1,29 @@
+/// Prints sorting steps when the `sort-trace` feature is enabled; otherwise, the arguments are type-checked but
+/// nothing is printed.
+macro_rules! sort_trace {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "sort-trace") {
+            println!($($arg)*);
+        }
+    };
+}
+
 mod higher_order_functions;
 pub use higher_order_functions::*;
 
+pub mod aggregate;
 pub mod approx_eq;
-pub mod btreemap_ext;
 pub mod comb_sort;
-pub mod map_ext_owned;
-pub mod map_ext_ref;
-pub mod map_iter;
+pub mod compose;
+pub mod map_ext;
+pub mod merge_sort;
+pub mod par_sort;
+pub mod partial;
+pub mod partial_sort;
 pub mod quicksort;
 pub mod ref_into_make;
+pub mod select;
+pub mod sort;
+pub mod total_order;
 pub mod wrapper;
 pub mod wrapper_discr;
@@ -0,0 +1,263 @@
+//! Parallel versions of [`super::sort::introsort`] and [`super::merge_sort::merge_sort`] for large slices.
+//!
+//! Both split the slice and sort the parts on scoped threads ([`std::thread::scope`]) until the parts are shorter
+//! than [`PARALLEL_THRESHOLD`] or there are enough threads to keep all cores busy, and then sort sequentially.
+//! Merge sort also merges the sorted parts in parallel, by splitting each merge into independent ones.
+//! Comparators are shared between threads, so they must be [`Fn`] and [`Sync`] rather than [`FnMut`].
+
+use super::{
+    merge_sort::{merge_buffered, merge_sort_with},
+    sort::{depth_limit, introsort_with, partition},
+};
+use std::{cmp::Ordering, thread};
+
+/// Slices shorter than this are sorted sequentially.
+pub const PARALLEL_THRESHOLD: usize = 1 << 14;
+
+/// Levels of splitting that spawn threads: none on a single core, otherwise enough for about 4 parts per core, to
+/// absorb unbalanced partitions.
+fn spawn_levels() -> u32 {
+    match thread::available_parallelism().map_or(1, |n| n.get()) {
+        1 => 0,
+        cores => cores.next_power_of_two().ilog2() + 2,
+    }
+}
+
+//=================
+// Parallel introsort
+
+fn par_introsort_with<T: Send>(
+    arr: &mut [T],
+    is_less: &(impl Fn(&T, &T) -> bool + Sync),
+    threshold: usize,
+    spawn_levels: u32,
+    limit: u32,
+) {
+    if arr.len() < threshold || spawn_levels == 0 || limit == 0 {
+        introsort_with(arr, &mut |a, b| is_less(a, b), limit);
+        return;
+    }
+
+    let p = partition(arr, &mut |a, b| is_less(a, b));
+    let (left, right) = arr.split_at_mut(p);
+    let right = &mut right[1..];
+    thread::scope(|s| {
+        s.spawn(|| par_introsort_with(left, is_less, threshold, spawn_levels - 1, limit - 1));
+        par_introsort_with(right, is_less, threshold, spawn_levels - 1, limit - 1);
+    });
+}
+
+/// Parallel version of [`super::sort::introsort`]. Unstable, O(n log n) worst case.
+///
+/// Sorts `arr` in place without heap allocations, other than for the spawned threads.
+pub fn par_introsort<T: Ord + Send>(arr: &mut [T]) {
+    par_introsort_by(arr, T::cmp)
+}
+
+/// Like [`par_introsort`] but with comparator `compare`.
+pub fn par_introsort_by<T: Send>(arr: &mut [T], compare: impl Fn(&T, &T) -> Ordering + Sync) {
+    let limit = depth_limit(arr.len());
+    let is_less = |a: &T, b: &T| compare(a, b) == Ordering::Less;
+    par_introsort_with(arr, &is_less, PARALLEL_THRESHOLD, spawn_levels(), limit)
+}
+
+/// Like [`par_introsort`] but compares the keys extracted by `f`.
+pub fn par_introsort_by_key<T: Send, K: Ord>(arr: &mut [T], f: impl Fn(&T) -> K + Sync) {
+    par_introsort_by(arr, |a, b| f(a).cmp(&f(b)))
+}
+
+//=================
+// Parallel merge sort
+
+/// Co-rank of output position `k` in the stable merge of the sorted `arr[..mid]` and `arr[mid..]`: the numbers
+/// `(i, j)` of left and right items, with `i + j == k`, that make up the first `k` merged items.
+fn co_rank<T>(
+    arr: &[T],
+    mid: usize,
+    k: usize,
+    is_less: &impl Fn(&T, &T) -> bool,
+) -> (usize, usize) {
+    let (left, right) = arr.split_at(mid);
+    let (mut lo, mut hi) = (k.saturating_sub(right.len()), k.min(mid));
+    while lo < hi {
+        let i = lo + (hi - lo) / 2;
+        // `left[i]` belongs to the first `k` items if it is not greater than `right[k - i - 1]`, as ties are taken
+        // from the left.
+        if !is_less(&right[k - i - 1], &left[i]) {
+            lo = i + 1;
+        } else {
+            hi = i;
+        }
+    }
+    (lo, k - lo)
+}
+
+/// Merges the sorted `arr[..mid]` and `arr[mid..]`, using `buf` for the sequential merges on this thread.
+///
+/// Until the parts are shorter than `threshold` or `spawn_levels` is exhausted, the merge is split at the co-rank
+/// `(i, j)` of its midpoint: rotating `arr[i..mid + j]` brings the first `i` left items next to the first `j` right
+/// items, after which both halves are independent merges run on separate threads.
+fn par_merge<T: Send>(
+    arr: &mut [T],
+    mid: usize,
+    buf: &mut Vec<usize>,
+    is_less: &(impl Fn(&T, &T) -> bool + Sync),
+    threshold: usize,
+    spawn_levels: u32,
+) {
+    let len = arr.len();
+    if len < threshold || spawn_levels == 0 {
+        merge_buffered(arr, mid, buf, &mut |a, b| is_less(a, b));
+        return;
+    }
+    if mid == 0 || mid == len || !is_less(&arr[mid], &arr[mid - 1]) {
+        return;
+    }
+
+    let (i, j) = co_rank(arr, mid, len / 2, is_less);
+    arr[i..mid + j].rotate_left(mid - i);
+    let (left, right) = arr.split_at_mut(i + j);
+    thread::scope(|s| {
+        s.spawn(|| {
+            par_merge(
+                left,
+                i,
+                &mut Vec::new(),
+                is_less,
+                threshold,
+                spawn_levels - 1,
+            )
+        });
+        par_merge(right, mid - i, buf, is_less, threshold, spawn_levels - 1);
+    });
+}
+
+fn par_merge_sort_with<T: Send>(
+    arr: &mut [T],
+    buf: &mut Vec<usize>,
+    is_less: &(impl Fn(&T, &T) -> bool + Sync),
+    threshold: usize,
+    spawn_levels: u32,
+) {
+    let len = arr.len();
+    if len < threshold || spawn_levels == 0 {
+        merge_sort_with(arr, &mut |a, b| is_less(a, b));
+        return;
+    }
+
+    let mid = len / 2;
+    let (left, right) = arr.split_at_mut(mid);
+    thread::scope(|s| {
+        s.spawn(|| {
+            par_merge_sort_with(left, &mut Vec::new(), is_less, threshold, spawn_levels - 1)
+        });
+        par_merge_sort_with(right, buf, is_less, threshold, spawn_levels - 1);
+    });
+    par_merge(arr, mid, buf, is_less, threshold, spawn_levels);
+}
+
+/// Parallel version of [`super::merge_sort::merge_sort`]. Stable, O(n log n) worst case.
+///
+/// Sorts `arr` in place, using a heap-allocated buffer of indices per thread, each up to the length of the parts
+/// that the thread merges.
+pub fn par_merge_sort<T: Ord + Send>(arr: &mut [T]) {
+    par_merge_sort_by(arr, T::cmp)
+}
+
+/// Like [`par_merge_sort`] but with comparator `compare`.
+pub fn par_merge_sort_by<T: Send>(arr: &mut [T], compare: impl Fn(&T, &T) -> Ordering + Sync) {
+    let is_less = |a: &T, b: &T| compare(a, b) == Ordering::Less;
+    par_merge_sort_with(
+        arr,
+        &mut Vec::new(),
+        &is_less,
+        PARALLEL_THRESHOLD,
+        spawn_levels(),
+    )
+}
+
+/// Like [`par_merge_sort`] but compares the keys extracted by `f`.
+pub fn par_merge_sort_by_key<T: Send, K: Ord>(arr: &mut [T], f: impl Fn(&T) -> K + Sync) {
+    par_merge_sort_by(arr, |a, b| f(a).cmp(&f(b)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Threshold small enough for the proptest inputs to be split across threads.
+    const TEST_THRESHOLD: usize = 8;
+
+    fn less(a: &(u8, usize), b: &(u8, usize)) -> bool {
+        a.0 < b.0
+    }
+
+    #[test]
+    fn test_large() {
+        let n = 200_000;
+        let v = (0..n).map(|i| (i * 7919) % n).collect::<Vec<u64>>();
+        let mut expected = v.clone();
+        expected.sort_unstable();
+
+        let mut a = v.clone();
+        par_introsort(&mut a);
+        assert_eq!(a, expected);
+
+        let mut a = v.clone();
+        par_merge_sort(&mut a);
+        assert_eq!(a, expected);
+
+        let mut a = v.clone();
+        par_introsort_by_key(&mut a, |x| n - x);
+        expected.reverse();
+        assert_eq!(a, expected);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_par_sorts_match_std(keys in prop::collection::vec(0..10_u8, 0..2000)) {
+            let records = keys.iter().copied().zip(0..).collect::<Vec<_>>();
+            let mut expected = records.clone();
+            expected.sort_by_key(|r| r.0);
+
+            let mut a = records.clone();
+            par_merge_sort_with(&mut a, &mut Vec::new(), &less, TEST_THRESHOLD, 3);
+            prop_assert_eq!(&a, &expected);
+
+            // Unstable, so only the keys must match.
+            let mut a = records.clone();
+            let limit = depth_limit(a.len());
+            par_introsort_with(&mut a, &less, TEST_THRESHOLD, 3, limit);
+            prop_assert_eq!(a.iter().map(|r| r.0).collect::<Vec<_>>(), expected.iter().map(|r| r.0).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn prop_par_merge_is_stable(
+            mut left in prop::collection::vec(0..10_u8, 0..500),
+            mut right in prop::collection::vec(0..10_u8, 0..500),
+        ) {
+            left.sort_unstable();
+            right.sort_unstable();
+            let mid = left.len();
+            let records = left.into_iter().chain(right).zip(0..).collect::<Vec<_>>();
+            let mut expected = records.clone();
+            expected.sort_by_key(|r| r.0);
+
+            let mut a = records.clone();
+            par_merge(&mut a, mid, &mut Vec::new(), &less, TEST_THRESHOLD, 4);
+            prop_assert_eq!(&a, &expected);
+
+            for k in [0, a.len() / 3, a.len() / 2, a.len()] {
+                let (i, j) = co_rank(&records, mid, k, &less);
+                prop_assert_eq!(i + j, k);
+                // Compare as sets of records, ordered by their original positions.
+                let mut firsts = records[..i].iter().chain(&records[mid..mid + j]).collect::<Vec<_>>();
+                let mut expected_firsts = expected[..k].iter().collect::<Vec<_>>();
+                firsts.sort_by_key(|r| r.1);
+                expected_firsts.sort_by_key(|r| r.1);
+                prop_assert_eq!(firsts, expected_firsts);
+            }
+        }
+    }
+}
@@ -0,0 +1,351 @@
+//! Partial application and currying.
+//!
+//! The [`partial!`](crate::partial) macro binds arbitrary arguments of a function of any arity, leaving `_`
+//! placeholders as the parameters of the returned closure, and [`curry!`](crate::curry) turns a function into nested
+//! single-argument closures. The `PartialApply*` extension traits bind the leading argument of functions of up to 8
+//! arguments, and can be chained to bind several leading arguments.
+//!
+//! Bound arguments are evaluated once, when the partial application is created. How they are passed to the
+//! function on each call determines whether the result is [`Fn`], [`FnMut`] or [`FnOnce`]:
+//! - by value, moved into the call, so the result is `FnOnce` unless the argument is `Copy`;
+//! - by clone, so the result is `Fn` (or `FnMut` if the function is);
+//! - by reference to the captured value, so the function can take `&T` while the result owns the `T`;
+//! - by mutable reference to the captured value, so the result is `FnMut`.
+//!
+//! The async versions return [`BoxFuture`]s that own everything they need, as the `'static` futures expected by
+//! e.g. `tokio::spawn`.
+
+use super::compose::BoxFuture;
+
+/// Binds arguments of a function: `partial!(f, x, _, ref y, _)` is `|a, b| f(x, a, &y, b)`, with `x` and `y`
+/// evaluated and captured when the closure is created.
+///
+/// Each bound argument can be preceded by a mode:
+/// - none: the captured value is moved into the call;
+/// - `clone`: the captured value is cloned for each call;
+/// - `ref`: a reference to the captured value is passed;
+/// - `ref mut`: a mutable reference to the captured value is passed.
+///
+/// ```
+/// use general::partial;
+///
+/// fn scale(factor: u32, x: u32, offset: &u32) -> u32 {
+///     factor * x + offset
+/// }
+///
+/// let f = partial!(scale, 10, _, ref 5);
+/// assert_eq!(f(2), 25);
+///
+/// let greet = partial!(|greeting: String, name: &str| format!("{greeting}, {name}!"), clone "Hello".to_owned(), _);
+/// assert_eq!(greet("Ann"), "Hello, Ann!");
+///
+/// let mut log = partial!(|log: &mut Vec<u32>, x| { log.push(x); log.len() }, ref mut Vec::new(), _);
+/// assert_eq!(log(1), 1);
+/// assert_eq!(log(2), 2);
+/// ```
+#[macro_export]
+macro_rules! partial {
+    ($f:expr $(, $($args:tt)*)?) => {
+        $crate::partial!(@munch sync [$f] [] [] [] [] $($($args)*)?)
+    };
+
+    // Accumulates the `let`s before the closure, the closure parameters, the statements at the start of the
+    // closure body and the call arguments. The identifiers introduced at each step are distinct by hygiene.
+    (@munch $mode:ident [$f:expr] [$($lets:tt)*] [$($params:tt)*] [$($prep:tt)*] [$($call:tt)*]
+        _ $(, $($rest:tt)*)?) => {
+        $crate::partial!(@munch $mode [$f] [$($lets)*] [$($params)* a,] [$($prep)*] [$($call)* a,]
+            $($($rest)*)?)
+    };
+    (@munch sync [$f:expr] [$($lets:tt)*] [$($params:tt)*] [$($prep:tt)*] [$($call:tt)*]
+        ref mut $e:expr $(, $($rest:tt)*)?) => {
+        $crate::partial!(@munch sync [$f] [$($lets)* let mut v = $e;] [$($params)*] [$($prep)*] [$($call)* &mut v,]
+            $($($rest)*)?)
+    };
+    (@munch async [$f:expr] [$($lets:tt)*] [$($params:tt)*] [$($prep:tt)*] [$($call:tt)*]
+        ref mut $e:expr $(, $($rest:tt)*)?) => {
+        ::core::compile_error!("`ref mut` arguments are not supported by `partial_async!`")
+    };
+    (@munch sync [$f:expr] [$($lets:tt)*] [$($params:tt)*] [$($prep:tt)*] [$($call:tt)*]
+        ref $e:expr $(, $($rest:tt)*)?) => {
+        $crate::partial!(@munch sync [$f] [$($lets)* let v = $e;] [$($params)*] [$($prep)*] [$($call)* &v,]
+            $($($rest)*)?)
+    };
+    (@munch async [$f:expr] [$($lets:tt)*] [$($params:tt)*] [$($prep:tt)*] [$($call:tt)*]
+        ref $e:expr $(, $($rest:tt)*)?) => {
+        $crate::partial!(@munch async [$f] [$($lets)* let v = ::std::sync::Arc::new($e);] [$($params)*]
+            [$($prep)* let v = ::std::sync::Arc::clone(&v);] [$($call)* &*v,] $($($rest)*)?)
+    };
+    (@munch sync [$f:expr] [$($lets:tt)*] [$($params:tt)*] [$($prep:tt)*] [$($call:tt)*]
+        clone $e:expr $(, $($rest:tt)*)?) => {
+        $crate::partial!(@munch sync [$f] [$($lets)* let v = $e;] [$($params)*] [$($prep)*]
+            [$($call)* ::core::clone::Clone::clone(&v),] $($($rest)*)?)
+    };
+    (@munch async [$f:expr] [$($lets:tt)*] [$($params:tt)*] [$($prep:tt)*] [$($call:tt)*]
+        clone $e:expr $(, $($rest:tt)*)?) => {
+        $crate::partial!(@munch async [$f] [$($lets)* let v = $e;] [$($params)*]
+            [$($prep)* let v = ::core::clone::Clone::clone(&v);] [$($call)* v,] $($($rest)*)?)
+    };
+    (@munch $mode:ident [$f:expr] [$($lets:tt)*] [$($params:tt)*] [$($prep:tt)*] [$($call:tt)*]
+        $e:expr $(, $($rest:tt)*)?) => {
+        $crate::partial!(@munch $mode [$f] [$($lets)* let v = $e;] [$($params)*] [$($prep)*] [$($call)* v,]
+            $($($rest)*)?)
+    };
+
+    (@munch sync [$f:expr] [$($lets:tt)*] [$($params:tt)*] [] [$($call:tt)*]) => {{
+        #[allow(unused_mut)]
+        let mut f = $f;
+        $($lets)*
+        move |$($params)*| f($($call)*)
+    }};
+    (@munch async [$f:expr] [$($lets:tt)*] [$($params:tt)*] [$($prep:tt)*] [$($call:tt)*]) => {{
+        let f = ::std::sync::Arc::new($f);
+        $($lets)*
+        move |$($params)*| -> $crate::fwk::compose::BoxFuture<'static, _> {
+            let f = ::std::sync::Arc::clone(&f);
+            $($prep)*
+            ::std::boxed::Box::pin(async move { f($($call)*).await })
+        }
+    }};
+}
+
+/// Async version of [`partial!`](crate::partial), whose closure returns a [`BoxFuture`] that owns the function and
+/// the arguments it needs. `ref` arguments are shared through an `Arc`, and `ref mut` arguments are not supported.
+///
+/// ```
+/// use general::partial_async;
+///
+/// async fn total(prices: &[u64], qty: u64) -> u64 {
+///     prices.iter().sum::<u64>() * qty
+/// }
+///
+/// let f = partial_async!(total, ref vec![1, 2], _);
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// assert_eq!(tokio::spawn(f(10)).await.unwrap(), 30);
+/// # });
+/// ```
+#[macro_export]
+macro_rules! partial_async {
+    ($f:expr $(, $($args:tt)*)?) => {
+        $crate::partial!(@munch async [$f] [] [] [] [] $($($args)*)?)
+    };
+}
+
+/// Curries a function of the given number of `_` arguments: `curry!(f, _, _, _)` is `|a| |b| |c| f(a, b, c)`.
+///
+/// The function and all arguments but the last must be [`Clone`], as they are cloned into each intermediate
+/// closure.
+///
+/// ```
+/// use general::curry;
+///
+/// let f = curry!(|a: u32, b: u32, c: u32| a * 100 + b * 10 + c, _, _, _);
+/// let g = f(1);
+/// assert_eq!(g(2)(3), 123);
+/// assert_eq!(g(4)(5), 145);
+/// ```
+#[macro_export]
+macro_rules! curry {
+    ($f:expr, $u:tt $(,)?) => {{
+        let f = $f;
+        move |a| f(a)
+    }};
+    ($f:expr, $u0:tt, $($u:tt),+ $(,)?) => {{
+        let f = $f;
+        move |a| $crate::curry!($crate::partial!(::core::clone::Clone::clone(&f), clone a, $($u),+), $($u),+)
+    }};
+}
+
+macro_rules! impl_partial_apply {
+    ($name:ident, $n:literal, ($A1:ident $a1:ident) $(, ($A:ident $a:ident))*) => {
+        #[doc = concat!("Extension trait to bind the first argument of functions of ", $n, " argument(s).")]
+        pub trait $name<$A1, $($A,)* R>: FnOnce($A1 $(, $A)*) -> R + Sized {
+            /// Binds the first argument, which is cloned for each call.
+            fn bind(self, $a1: $A1) -> impl Fn($($A),*) -> R
+            where
+                Self: Fn($A1 $(, $A)*) -> R,
+                $A1: Clone,
+            {
+                move |$($a),*| self($a1.clone() $(, $a)*)
+            }
+
+            /// Binds the first argument of a mutable function, which is cloned for each call.
+            fn bind_mut(mut self, $a1: $A1) -> impl FnMut($($A),*) -> R
+            where
+                Self: FnMut($A1 $(, $A)*) -> R,
+                $A1: Clone,
+            {
+                move |$($a),*| self($a1.clone() $(, $a)*)
+            }
+
+            /// Binds the first argument, which is moved into the only call.
+            fn bind_once(self, $a1: $A1) -> impl FnOnce($($A),*) -> R {
+                move |$($a),*| self($a1 $(, $a)*)
+            }
+
+            /// Binds the first argument of a function taking it by reference, capturing `value` and passing a
+            /// reference to it on each call.
+            fn bind_ref<T>(self, value: T) -> impl Fn($($A),*) -> R
+            where
+                Self: Fn(&T $(, $A)*) -> R,
+            {
+                move |$($a),*| self(&value $(, $a)*)
+            }
+
+            /// Binds the first argument of an async function, which is cloned for each call, boxing the futures.
+            fn bind_async(self, $a1: $A1) -> impl Fn($($A),*) -> BoxFuture<'static, R::Output>
+            where
+                Self: Fn($A1 $(, $A)*) -> R,
+                $A1: Clone,
+                R: Future + Send + 'static,
+            {
+                move |$($a),*| -> BoxFuture<'static, R::Output> { Box::pin(self($a1.clone() $(, $a)*)) }
+            }
+        }
+
+        impl<$A1, $($A,)* R, F: FnOnce($A1 $(, $A)*) -> R> $name<$A1, $($A,)* R> for F {}
+    };
+}
+
+impl_partial_apply!(PartialApply1, 1, (A1 a1));
+impl_partial_apply!(PartialApply2, 2, (A1 a1), (A2 a2));
+impl_partial_apply!(PartialApply3, 3, (A1 a1), (A2 a2), (A3 a3));
+impl_partial_apply!(PartialApply4, 4, (A1 a1), (A2 a2), (A3 a3), (A4 a4));
+impl_partial_apply!(PartialApply5, 5, (A1 a1), (A2 a2), (A3 a3), (A4 a4), (A5 a5));
+impl_partial_apply!(PartialApply6, 6, (A1 a1), (A2 a2), (A3 a3), (A4 a4), (A5 a5), (A6 a6));
+impl_partial_apply!(PartialApply7, 7, (A1 a1), (A2 a2), (A3 a3), (A4 a4), (A5 a5), (A6 a6), (A7 a7));
+impl_partial_apply!(PartialApply8, 8, (A1 a1), (A2 a2), (A3 a3), (A4 a4), (A5 a5), (A6 a6), (A7 a7), (A8 a8));
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[allow(clippy::too_many_arguments)]
+    fn sum8(a: u64, b: u64, c: u64, d: u64, e: u64, f: u64, g: u64, h: u64) -> u64 {
+        [a, b, c, d, e, f, g, h]
+            .iter()
+            .enumerate()
+            .map(|(i, x)| x * 10_u64.pow(i as u32))
+            .sum()
+    }
+
+    fn label(name: &String, n: u32) -> String {
+        format!("{name}-{n}")
+    }
+
+    async fn repeat(s: String, n: usize) -> String {
+        s.repeat(n)
+    }
+
+    async fn count(xs: &[u32], min: u32) -> usize {
+        xs.iter().filter(|x| **x >= min).count()
+    }
+
+    async fn repeat_later(s: String, n: usize) -> String {
+        let n = std::cell::Cell::new(n);
+        tokio::task::yield_now().await;
+        s.repeat(n.get())
+    }
+
+    fn block_on<T>(fut: impl Future<Output = T>) -> T {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(fut)
+    }
+
+    #[test]
+    fn test_partial_modes() {
+        let f = partial!(sum8, 1, _, 3, _, _, 6, _, 8);
+        assert_eq!(f(2, 4, 5, 7), 87654321);
+
+        let name = "x".to_owned();
+        let by_ref = partial!(label, ref name, _);
+        assert_eq!(by_ref(1), "x-1");
+        assert_eq!(by_ref(2), "x-2");
+
+        let by_clone = partial!(|s: String, n: u32| s + &n.to_string(), clone "y".to_owned(), _);
+        assert_eq!(by_clone(1), "y1");
+        assert_eq!(by_clone(2), "y2");
+
+        let by_value = partial!(|s: String, n: u32| s + &n.to_string(), "z".to_owned(), _);
+        assert_eq!(by_value(3), "z3");
+
+        let mut counter = partial!(|total: &mut u32, x: u32| { *total += x; *total }, ref mut 0, _);
+        assert_eq!(counter(2), 2);
+        assert_eq!(counter(3), 5);
+
+        let evaluated = std::cell::Cell::new(0);
+        let g = partial!(
+            |a: u32, b: u32| a + b,
+            {
+                evaluated.set(evaluated.get() + 1);
+                1
+            },
+            _
+        );
+        assert_eq!(g(1) + g(2), 5);
+        assert_eq!(evaluated.get(), 1);
+    }
+
+    #[test]
+    fn test_curry() {
+        let f = curry!(label, _, _);
+        let x = f(&"x".to_owned())(1);
+        assert_eq!(x, "x-1");
+
+        let g = curry!(sum8, _, _, _, _, _, _, _, _);
+        assert_eq!(g(1)(2)(3)(4)(5)(6)(7)(8), 87654321);
+        let h = g(0)(0)(0)(0)(0)(0);
+        assert_eq!(h(1)(2) + h(3)(4), 64_000_000);
+    }
+
+    #[test]
+    fn test_partial_apply() {
+        let f = sum8.bind(1).bind(2).bind(3).bind(4).bind(5).bind(6).bind(7);
+        assert_eq!(f(8), 87654321);
+        assert_eq!(f(0), 7654321);
+
+        let g = label.bind_ref("y".to_owned());
+        assert_eq!(g(2), "y-2");
+
+        let mut calls = 0;
+        let mut h = (|a: u32, b: u32| {
+            calls += 1;
+            a * b
+        })
+        .bind_mut(3);
+        assert_eq!(h(2) + h(4), 18);
+        drop(h);
+        assert_eq!(calls, 2);
+
+        let v = vec![1, 2];
+        let once = (move |extra: u32, mut v: Vec<u32>| {
+            v.push(extra);
+            v
+        })
+        .bind_once(3)
+        .bind_once(v);
+        assert_eq!(once(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_async() {
+        let f = repeat.bind_async("ab".to_owned());
+        assert_eq!(block_on(f(2)), "abab");
+
+        let g = partial_async!(count, ref vec![1, 5, 10], _);
+        assert_eq!(block_on(g(5)), 2);
+        assert_eq!(block_on(g(11)), 0);
+
+        let h = partial_async!(repeat, clone "c".to_owned(), _);
+        let (h1, h2) = (h(1), h(3));
+        assert_eq!(block_on(async { h1.await + &h2.await }), "cccc");
+
+        let once = partial_async!(repeat, "d".to_owned(), 2);
+        assert_eq!(block_on(once()), "dd");
+
+        // Futures holding a `!Sync` value across an await point are `Send` and can be bound.
+        let k = repeat_later.bind_async("e".to_owned());
+        assert_eq!(block_on(async { tokio::spawn(k(3)).await.unwrap() }), "eee");
+    }
+}
@@ -0,0 +1,297 @@
+//! Partial sorting, top-k and percentile APIs built on [`super::select`], for when only some ranks of a slice or
+//! stream are needed.
+
+use super::{
+    select::{select_nth_by, select_nth_many_by, select_with},
+    sort::{compare_by_key, depth_limit, heapsort_with, introsort_with, less_from, sift_down},
+};
+use std::cmp::Ordering;
+
+//=================
+// Partial sort
+
+fn partial_sort_with<T>(arr: &mut [T], k: usize, is_less: &mut impl FnMut(&T, &T) -> bool) {
+    let k = k.min(arr.len());
+    if k == 0 {
+        return;
+    }
+    if k < arr.len() {
+        select_with(arr, k - 1, is_less);
+    }
+    let head = &mut arr[..k];
+    let limit = depth_limit(head.len());
+    introsort_with(head, is_less, limit);
+}
+
+/// Rearranges `arr` so that `arr[..k]` holds its `k` least items in ascending order. The order of the remaining
+/// items is unspecified. If `k >= arr.len()`, the whole slice is sorted.
+///
+/// Takes O(n + k log k) time, without heap allocations.
+pub fn partial_sort<T: Ord>(arr: &mut [T], k: usize) {
+    partial_sort_by(arr, k, T::cmp)
+}
+
+/// Like [`partial_sort`] but with comparator `compare`.
+pub fn partial_sort_by<T>(arr: &mut [T], k: usize, compare: impl FnMut(&T, &T) -> Ordering) {
+    partial_sort_with(arr, k, &mut less_from(compare))
+}
+
+/// Like [`partial_sort`] but compares the keys extracted by `f`.
+pub fn partial_sort_by_key<T, K: Ord>(arr: &mut [T], k: usize, f: impl FnMut(&T) -> K) {
+    partial_sort_by(arr, k, compare_by_key(f))
+}
+
+//=================
+// Top-k
+
+/// Rearranges `arr` so that `arr[..k]` holds its `k` greatest items in descending order, and returns that prefix.
+/// If `k >= arr.len()`, the whole slice is sorted in descending order.
+///
+/// Takes O(n + k log k) time, without heap allocations.
+pub fn top_k<T: Ord>(arr: &mut [T], k: usize) -> &mut [T] {
+    top_k_by(arr, k, T::cmp)
+}
+
+/// Like [`top_k`] but with comparator `compare`.
+pub fn top_k_by<T>(
+    arr: &mut [T],
+    k: usize,
+    mut compare: impl FnMut(&T, &T) -> Ordering,
+) -> &mut [T] {
+    partial_sort_by(arr, k, |a, b| compare(b, a));
+    let k = k.min(arr.len());
+    &mut arr[..k]
+}
+
+/// Like [`top_k`] but compares the keys extracted by `f`.
+pub fn top_k_by_key<T, K: Ord>(arr: &mut [T], k: usize, f: impl FnMut(&T) -> K) -> &mut [T] {
+    top_k_by(arr, k, compare_by_key(f))
+}
+
+/// Restores the max-heap property of `heap` for the item at `node`, moving it towards the root.
+fn sift_up<T>(heap: &mut [T], mut node: usize, is_less: &mut impl FnMut(&T, &T) -> bool) {
+    while node > 0 {
+        let parent = (node - 1) / 2;
+        if !is_less(&heap[parent], &heap[node]) {
+            return;
+        }
+        heap.swap(parent, node);
+        node = parent;
+    }
+}
+
+fn top_k_iter_with<T>(
+    items: impl IntoIterator<Item = T>,
+    k: usize,
+    is_less: &mut impl FnMut(&T, &T) -> bool,
+) -> Vec<T> {
+    if k == 0 {
+        return Vec::new();
+    }
+
+    // Min-heap of the greatest items seen so far, i.e., a max-heap under the reversed order.
+    let mut is_greater = |a: &T, b: &T| is_less(b, a);
+    let mut heap = Vec::with_capacity(k);
+    for item in items {
+        if heap.len() < k {
+            heap.push(item);
+            let last = heap.len() - 1;
+            sift_up(&mut heap, last, &mut is_greater);
+        } else if is_greater(&item, &heap[0]) {
+            heap[0] = item;
+            sift_down(&mut heap, 0, &mut is_greater);
+        }
+    }
+
+    heapsort_with(&mut heap, &mut is_greater);
+    heap
+}
+
+/// Returns the `k` greatest items of `items` in descending order, or all of them if there are fewer than `k`.
+///
+/// Streams the items through a bounded heap, so it takes O(n log k) time and O(k) memory.
+pub fn top_k_iter<T: Ord>(items: impl IntoIterator<Item = T>, k: usize) -> Vec<T> {
+    top_k_iter_by(items, k, T::cmp)
+}
+
+/// Like [`top_k_iter`] but with comparator `compare`.
+pub fn top_k_iter_by<T>(
+    items: impl IntoIterator<Item = T>,
+    k: usize,
+    compare: impl FnMut(&T, &T) -> Ordering,
+) -> Vec<T> {
+    top_k_iter_with(items, k, &mut less_from(compare))
+}
+
+/// Like [`top_k_iter`] but compares the keys extracted by `f`.
+pub fn top_k_iter_by_key<T, K: Ord>(
+    items: impl IntoIterator<Item = T>,
+    k: usize,
+    f: impl FnMut(&T) -> K,
+) -> Vec<T> {
+    top_k_iter_by(items, k, compare_by_key(f))
+}
+
+//=================
+// Percentiles
+
+/// Returns the zero-based nearest rank of percentile `p` in `0..len`, or [`None`] if `len == 0` or `p` is not in
+/// `[0, 100]`.
+pub fn percentile_rank(len: usize, p: f64) -> Option<usize> {
+    if len == 0 || !(0. ..=100.).contains(&p) {
+        return None;
+    }
+    let rank = (p / 100. * len as f64).ceil() as usize;
+    Some(rank.saturating_sub(1))
+}
+
+/// Returns the item at percentile `p` of `arr` by the nearest-rank method, i.e., the least item such that at least
+/// `p` percent of the items are less than or equal to it. Rearranges `arr` as [`super::select::select_nth`] does.
+/// Returns [`None`] if `arr` is empty or `p` is not in `[0, 100]`.
+pub fn nth_percentile<T: Ord>(arr: &mut [T], p: f64) -> Option<&mut T> {
+    nth_percentile_by(arr, p, T::cmp)
+}
+
+/// Like [`nth_percentile`] but with comparator `compare`.
+pub fn nth_percentile_by<T>(
+    arr: &mut [T],
+    p: f64,
+    compare: impl FnMut(&T, &T) -> Ordering,
+) -> Option<&mut T> {
+    let k = percentile_rank(arr.len(), p)?;
+    select_nth_by(arr, k, compare)
+}
+
+/// Like [`nth_percentile`] but compares the keys extracted by `f`.
+pub fn nth_percentile_by_key<T, K: Ord>(
+    arr: &mut [T],
+    p: f64,
+    f: impl FnMut(&T) -> K,
+) -> Option<&mut T> {
+    nth_percentile_by(arr, p, compare_by_key(f))
+}
+
+/// Returns the items at percentiles `ps` of `arr`, in the order of `ps`, as [`nth_percentile`] does for each.
+/// Shares partitions between percentiles, see [`super::select::select_nth_many`].
+pub fn nth_percentiles<'a, T: Ord>(arr: &'a mut [T], ps: &[f64]) -> Option<Vec<&'a T>> {
+    nth_percentiles_by(arr, ps, T::cmp)
+}
+
+/// Like [`nth_percentiles`] but with comparator `compare`.
+pub fn nth_percentiles_by<'a, T>(
+    arr: &'a mut [T],
+    ps: &[f64],
+    compare: impl FnMut(&T, &T) -> Ordering,
+) -> Option<Vec<&'a T>> {
+    let ks = ps
+        .iter()
+        .map(|p| percentile_rank(arr.len(), *p))
+        .collect::<Option<Vec<_>>>()?;
+    select_nth_many_by(arr, &ks, compare)
+}
+
+/// Like [`nth_percentiles`] but compares the keys extracted by `f`.
+pub fn nth_percentiles_by_key<'a, T, K: Ord>(
+    arr: &'a mut [T],
+    ps: &[f64],
+    f: impl FnMut(&T) -> K,
+) -> Option<Vec<&'a T>> {
+    nth_percentiles_by(arr, ps, compare_by_key(f))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn sorted(v: &[i32]) -> Vec<i32> {
+        let mut v = v.to_vec();
+        v.sort();
+        v
+    }
+
+    fn sorted_desc(v: &[i32]) -> Vec<i32> {
+        let mut v = sorted(v);
+        v.reverse();
+        v
+    }
+
+    #[test]
+    fn test_percentile_rank() {
+        assert_eq!(percentile_rank(0, 50.), None);
+        assert_eq!(percentile_rank(10, -1.), None);
+        assert_eq!(percentile_rank(10, 100.5), None);
+        assert_eq!(percentile_rank(10, f64::NAN), None);
+        assert_eq!(percentile_rank(10, 0.), Some(0));
+        assert_eq!(percentile_rank(10, 50.), Some(4));
+        assert_eq!(percentile_rank(10, 95.), Some(9));
+        assert_eq!(percentile_rank(10, 100.), Some(9));
+        assert_eq!(percentile_rank(1000, 99.), Some(989));
+    }
+
+    #[test]
+    fn test_percentiles_of_spans() {
+        // Span names with latencies, queried by latency.
+        let mut spans = (1..=100)
+            .map(|i| (format!("span{i}"), i * 10))
+            .collect::<Vec<_>>();
+        let p90 = nth_percentile_by_key(&mut spans, 90., |s| s.1).unwrap();
+        assert_eq!(p90.0, "span90");
+
+        let ps = nth_percentiles_by_key(&mut spans, &[50., 99.], |s| s.1).unwrap();
+        assert_eq!(ps.iter().map(|s| s.1).collect::<Vec<_>>(), vec![500, 990]);
+
+        let slowest = top_k_iter_by_key(spans, 3, |s| s.1);
+        assert_eq!(
+            slowest.iter().map(|s| s.0.as_str()).collect::<Vec<_>>(),
+            vec!["span100", "span99", "span98"]
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_partial_sort_matches_sort(v in prop::collection::vec(0..100_i32, 0..1000), k in 0..1100_usize) {
+            let expected = sorted(&v);
+            let k_eff = k.min(v.len());
+
+            let mut a = v.clone();
+            partial_sort(&mut a, k);
+            prop_assert_eq!(&a[..k_eff], &expected[..k_eff]);
+            prop_assert_eq!(sorted(&a), expected.clone());
+
+            let mut a = v.clone();
+            partial_sort_by_key(&mut a, k, |x| -x);
+            prop_assert_eq!(&a[..k_eff], &sorted_desc(&v)[..k_eff]);
+        }
+
+        #[test]
+        fn prop_top_k_matches_sort(v in prop::collection::vec(any::<i32>(), 0..1000), k in 0..1100_usize) {
+            let expected = sorted_desc(&v);
+            let k_eff = k.min(v.len());
+
+            let mut a = v.clone();
+            prop_assert_eq!(&*top_k(&mut a, k), &expected[..k_eff]);
+            prop_assert_eq!(top_k_iter(v.iter().copied(), k), &expected[..k_eff]);
+
+            let ascending = sorted(&v);
+            let mut a = v.clone();
+            prop_assert_eq!(&*top_k_by(&mut a, k, |x, y| y.cmp(x)), &ascending[..k_eff]);
+            prop_assert_eq!(top_k_iter_by(v.iter().copied(), k, |x, y| y.cmp(x)), &ascending[..k_eff]);
+        }
+
+        #[test]
+        fn prop_percentiles_match_sort(v in prop::collection::vec(any::<i32>(), 1..1000), p in 0. ..=100.) {
+            let expected = sorted(&v);
+            let k = percentile_rank(v.len(), p).unwrap();
+
+            let mut a = v.clone();
+            prop_assert_eq!(*nth_percentile(&mut a, p).unwrap(), expected[k]);
+
+            let mut a = v.clone();
+            let ps = nth_percentiles(&mut a, &[p, 50., 99.]).unwrap();
+            prop_assert_eq!(*ps[0], expected[k]);
+            prop_assert_eq!(*ps[1], expected[percentile_rank(v.len(), 50.).unwrap()]);
+            prop_assert_eq!(*ps[2], expected[percentile_rank(v.len(), 99.).unwrap()]);
+        }
+    }
+}
@@ -1,4 +1,7 @@
-//! In-place quicksort algorithm.
+//! In-place quicksort algorithm. Steps are printed only with the `sort-trace` feature.
+//!
+//! See [`super::sort::introsort`] for a variant with worst-case O(n log n) time, and [`super::total_order`] for
+//! slices with incomparable items such as `NaN`.
 
 use std::{cmp, fmt::Debug};
 
@@ -33,39 +36,9 @@ fn move_j<T: PartialOrd>(arr: &[T], i: usize, j: &mut usize) -> Movement {
     }
 }
 
-fn recurse_sort<T: PartialOrd + Debug>(arr: &mut [T], pivot: usize, pivot_landing: usize) {
-    arr.swap(pivot_landing, pivot);
-    println!(
-        "    >>> left={:?}, pivot_val={:?} right={:?}",
-        &arr[..pivot_landing],
-        arr[pivot_landing],
-        &arr[pivot_landing + 1..]
-    );
-    quicksort(&mut arr[..pivot_landing]);
-    quicksort(&mut arr[pivot_landing + 1..]);
-}
-
-/// Implementation of quicksort algorithm.
-///
-/// Sorts `arr` in place without heap allocations, but there is stack allocation with recursion.
-pub fn quicksort<T: PartialOrd + Debug>(arr: &mut [T]) {
+/// Partitions `arr`, of length at least 3, around its middle item and returns the final index of that item.
+fn partition<T: PartialOrd + Debug>(arr: &mut [T]) -> usize {
     let len = arr.len();
-
-    println!("*** arr={arr:?}, len={len}");
-
-    if len <= 1 {
-        println!("    sorted_arr={arr:?}");
-        return;
-    }
-
-    if len == 2 {
-        if arr[0] > arr[1] {
-            arr.swap(0, 1);
-        }
-        println!("    sorted_arr={arr:?}");
-        return;
-    }
-
     let m = len / 2;
     arr.swap(m, len - 1);
     let pivot = len - 1;
@@ -73,124 +46,102 @@ pub fn quicksort<T: PartialOrd + Debug>(arr: &mut [T]) {
     let mut i = 0;
     let mut j = pivot - 1;
 
-    println!("    arr_after_pivot={arr:?}, len={len}, pivot={pivot}");
+    sort_trace!("    arr_after_pivot={arr:?}, len={len}, pivot={pivot}");
 
-    loop {
-        println!("    i={i}, j={j}");
+    let pivot_landing = loop {
+        sort_trace!("    i={i}, j={j}");
         let i_movement = move_i(arr, &mut i, j);
         let j_movement = move_j(arr, i, &mut j);
-        println!("    i_movement={i_movement:?}, i={i}, j_movement={j_movement:?}, j={j}");
+        sort_trace!("    i_movement={i_movement:?}, i={i}, j_movement={j_movement:?}, j={j}");
 
         match (i_movement, j_movement) {
-            (Movement::Stuck, Movement::Met) => {
-                let pivot_landing = i;
-                recurse_sort(arr, pivot, pivot_landing);
-                break;
-            }
-            (Movement::Met, Movement::Stuck) => {
-                let pivot_landing = j + 1;
-                recurse_sort(arr, pivot, pivot_landing);
-                break;
-            }
-            (Movement::Met, Movement::Met) => {
-                let pivot_landing = j;
-                recurse_sort(arr, pivot, pivot_landing);
-                break;
-            }
+            (Movement::Stuck, Movement::Met) => break i,
+            (Movement::Met, Movement::Stuck) => break j + 1,
+            (Movement::Met, Movement::Met) => break j,
             (Movement::Stuck, Movement::Stuck) => {
                 arr.swap(i, j);
             }
             (Movement::Free, _) | (_, Movement::Free) => (),
         }
-    }
-    println!("    sorted_arr={arr:?}");
-}
+    };
 
-fn recurse_select<T: PartialOrd + Debug>(
-    arr: &mut [T],
-    pivot: usize,
-    pivot_landing: usize,
-    k: usize,
-) {
     arr.swap(pivot_landing, pivot);
-    println!(
+    sort_trace!(
         "    >>> left={:?}, pivot_val={:?} right={:?}",
         &arr[..pivot_landing],
         arr[pivot_landing],
         &arr[pivot_landing + 1..]
     );
+    pivot_landing
+}
 
-    match k.cmp(&pivot_landing) {
-        cmp::Ordering::Less => quickselect(&mut arr[..pivot_landing], k),
-        cmp::Ordering::Equal => return,
-        cmp::Ordering::Greater => quickselect(&mut arr[pivot_landing + 1..], k - pivot_landing - 1),
+/// Sorts `arr` if its length is at most 2 and returns whether it did so.
+fn sort_short<T: PartialOrd + Debug>(arr: &mut [T]) -> bool {
+    if arr.len() > 2 {
+        return false;
     }
+    if arr.len() == 2 && arr[0] > arr[1] {
+        arr.swap(0, 1);
+    }
+    true
 }
 
-/// Implementation of quickselect algorithm.
-///
-/// Rearranges `arr`, with in-place changes, so that its `k`th ranked item is in the correct position.
-/// No heap allocations, but there is stack allocation with recursion.
+/// Implementation of quicksort algorithm.
 ///
-/// # Panics
-/// - If `k` >= `arr.len()`.
-pub fn quickselect<T: PartialOrd + Debug>(arr: &mut [T], k: usize) {
-    let len = arr.len();
-    assert!(k < len, "`k` must be less than `arr.len()`");
+/// Sorts `arr` in place without heap allocations. Recurses only into the smaller partition, so stack usage is
+/// O(log n), but time is O(n²) in the worst case.
+pub fn quicksort<T: PartialOrd + Debug>(mut arr: &mut [T]) {
+    loop {
+        sort_trace!("*** arr={arr:?}, len={}", arr.len());
 
-    println!("*** arr={arr:?}, len={len}");
+        if sort_short(arr) {
+            sort_trace!("    sorted_arr={arr:?}");
+            return;
+        }
 
-    if len <= 1 {
-        println!("    final_arr={arr:?}");
-        return;
-    }
+        let pivot_landing = partition(arr);
+        let (left, right) = arr.split_at_mut(pivot_landing);
+        let right = &mut right[1..];
 
-    if len == 2 {
-        if arr[0] > arr[1] {
-            arr.swap(0, 1);
+        if left.len() < right.len() {
+            quicksort(left);
+            arr = right;
+        } else {
+            quicksort(right);
+            arr = left;
         }
-        println!("    final_arr={arr:?}");
-        return;
     }
+}
 
-    let m = len / 2;
-    arr.swap(m, len - 1);
-    let pivot = len - 1;
-
-    let mut i = 0;
-    let mut j = pivot - 1;
-
-    println!("    arr_after_pivot={arr:?}, len={len}, pivot={pivot}");
+/// Implementation of quickselect algorithm.
+///
+/// Rearranges `arr`, with in-place changes, so that its `k`th ranked item is in the correct position.
+/// No heap allocations and no recursion. See [`super::select::select_nth`] for a variant with worst-case linear time
+/// that does not panic.
+///
+/// # Panics
+/// - If `k` >= `arr.len()`.
+pub fn quickselect<T: PartialOrd + Debug>(mut arr: &mut [T], mut k: usize) {
+    assert!(k < arr.len(), "`k` must be less than `arr.len()`");
 
     loop {
-        println!("    i={i}, j={j}");
-        let i_movement = move_i(arr, &mut i, j);
-        let j_movement = move_j(arr, i, &mut j);
-        println!("    i_movement={i_movement:?}, i={i}, j_movement={j_movement:?}, j={j}");
+        sort_trace!("*** arr={arr:?}, len={}", arr.len());
 
-        match (i_movement, j_movement) {
-            (Movement::Stuck, Movement::Met) => {
-                let pivot_landing = i;
-                recurse_select(arr, pivot, pivot_landing, k);
-                break;
-            }
-            (Movement::Met, Movement::Stuck) => {
-                let pivot_landing = j + 1;
-                recurse_select(arr, pivot, pivot_landing, k);
-                break;
-            }
-            (Movement::Met, Movement::Met) => {
-                let pivot_landing = j;
-                recurse_select(arr, pivot, pivot_landing, k);
-                break;
-            }
-            (Movement::Stuck, Movement::Stuck) => {
-                arr.swap(i, j);
+        if sort_short(arr) {
+            sort_trace!("    final_arr={arr:?}");
+            return;
+        }
+
+        let pivot_landing = partition(arr);
+        match k.cmp(&pivot_landing) {
+            cmp::Ordering::Less => arr = &mut arr[..pivot_landing],
+            cmp::Ordering::Equal => return,
+            cmp::Ordering::Greater => {
+                arr = &mut arr[pivot_landing + 1..];
+                k -= pivot_landing + 1;
             }
-            (Movement::Free, _) | (_, Movement::Free) => (),
         }
     }
-    println!("    final_arr={arr:?}");
 }
 
 fn move_max_to_end<T: PartialOrd + Debug>(arr: &mut [T]) {
@@ -0,0 +1,356 @@
+//! Selection of the items of given ranks in worst-case linear time, without panics on out-of-range ranks.
+//!
+//! The implementation is an introselect: it narrows the range containing the rank in a loop, choosing pivots with
+//! Floyd–Rivest sampling for large ranges and median-of-three for smaller ones, and switches to median-of-medians
+//! pivots once the work done exceeds a linear budget. Both of these pivot strategies select within a sample of the
+//! range, which is done iteratively with a fixed-size stack of pending selections, and partitioning is three-way,
+//! so runs of equal items do not degrade it.
+
+use super::sort::{INSERTION_SORT_THRESHOLD, compare_by_key, insertion_sort_with, less_from};
+use std::cmp::Ordering;
+
+/// Ranges longer than this use Floyd–Rivest sampling to choose pivots.
+const FLOYD_RIVEST_THRESHOLD: usize = 600;
+
+/// Multiple of the input length that the cheap pivot strategies may process before median-of-medians kicks in.
+const WORK_BUDGET_FACTOR: usize = 4;
+
+/// Returns the index of the median of `arr[a]`, `arr[b]` and `arr[c]`.
+fn median_of_three<T>(
+    arr: &[T],
+    a: usize,
+    b: usize,
+    c: usize,
+    is_less: &mut impl FnMut(&T, &T) -> bool,
+) -> usize {
+    let (lo, hi) = if is_less(&arr[b], &arr[a]) {
+        (b, a)
+    } else {
+        (a, b)
+    };
+    if is_less(&arr[c], &arr[lo]) {
+        lo
+    } else if is_less(&arr[hi], &arr[c]) {
+        hi
+    } else {
+        c
+    }
+}
+
+/// Returns the bounds `(left, right)` of the sample around position `k` whose item of rank `k` is the Floyd–Rivest
+/// pivot. The sample size grows as n^(2/3).
+fn floyd_rivest_sample(len: usize, k: usize) -> (usize, usize) {
+    let n = len as f64;
+    let i = k as f64;
+    let z = n.ln();
+    let s = 0.5 * (2. * z / 3.).exp();
+    let sd = 0.5 * (z * s * (n - s) / n).sqrt() * (i - n / 2.).signum();
+    let left = ((i - i * s / n + sd).max(0.) as usize).min(k);
+    let right = ((i + (n - i) * s / n + sd) as usize).clamp(k, len - 1);
+    (left, right + 1)
+}
+
+/// Moves the medians of groups of 5 items to the front of `arr` and returns their number. The median of these
+/// medians guarantees that at least 30% of the items fall on each side of it.
+fn group_medians<T>(arr: &mut [T], is_less: &mut impl FnMut(&T, &T) -> bool) -> usize {
+    let groups = arr.len() / 5;
+    for g in 0..groups {
+        let group = &mut arr[5 * g..5 * g + 5];
+        insertion_sort_with(group, is_less);
+        arr.swap(g, 5 * g + 2);
+    }
+    groups
+}
+
+/// Partitions `arr` into items less than, equal to, and greater than `arr[pivot]`. Returns the bounds `(lt, gt)` of
+/// the items equal to the pivot, i.e., `arr[lt..gt]`.
+fn partition3<T>(
+    arr: &mut [T],
+    pivot: usize,
+    is_less: &mut impl FnMut(&T, &T) -> bool,
+) -> (usize, usize) {
+    // The pivot is kept at index `lt` throughout, as the first of the items equal to it.
+    arr.swap(0, pivot);
+    let (mut lt, mut i, mut gt) = (0, 1, arr.len());
+    while i < gt {
+        if is_less(&arr[i], &arr[lt]) {
+            arr.swap(i, lt);
+            lt += 1;
+            i += 1;
+        } else if is_less(&arr[lt], &arr[i]) {
+            gt -= 1;
+            arr.swap(i, gt);
+        } else {
+            i += 1;
+        }
+    }
+    (lt, gt)
+}
+
+/// Maximum number of nested pending selections. Each nested selection is within a sample of at most half of the
+/// enclosing range, so this is never reached for slices that fit in memory.
+const MAX_DEPTH: usize = 64;
+
+/// Pending selection of the item of rank `k` within `start..end`, where ranks and bounds are indices into the
+/// whole slice.
+#[derive(Clone, Copy, Default)]
+struct Selection {
+    start: usize,
+    end: usize,
+    k: usize,
+    budget: usize,
+    work: usize,
+    /// Index of the pivot once the selection within its sample, the next pending selection, is done.
+    pivot: Option<usize>,
+}
+
+impl Selection {
+    fn new(start: usize, end: usize, k: usize) -> Self {
+        Self {
+            start,
+            end,
+            k,
+            budget: WORK_BUDGET_FACTOR * (end - start),
+            ..Default::default()
+        }
+    }
+}
+
+/// Rearranges `arr` so that `arr[k]` is the item of rank `k`, with no greater items before it and no lesser items
+/// after it. Requires `k < arr.len()`.
+pub(crate) fn select_with<T>(arr: &mut [T], k: usize, is_less: &mut impl FnMut(&T, &T) -> bool) {
+    let mut stack = [Selection::default(); MAX_DEPTH];
+    stack[0] = Selection::new(0, arr.len(), k);
+    let mut depth = 1;
+
+    while depth > 0 {
+        let can_nest = depth < MAX_DEPTH;
+        let sel = &mut stack[depth - 1];
+        let (start, len) = (sel.start, sel.end - sel.start);
+        let range = &mut arr[sel.start..sel.end];
+
+        let pivot = match sel.pivot.take() {
+            Some(pivot) => pivot - start,
+            None if len <= INSERTION_SORT_THRESHOLD => {
+                insertion_sort_with(range, is_less);
+                depth -= 1;
+                continue;
+            }
+            None if can_nest && sel.work > sel.budget => {
+                // The pivot is the median of the group medians, selected before resuming.
+                let groups = group_medians(range, is_less);
+                sel.pivot = Some(start + groups / 2);
+                stack[depth] = Selection::new(start, start + groups, start + groups / 2);
+                depth += 1;
+                continue;
+            }
+            None if can_nest && len > FLOYD_RIVEST_THRESHOLD => {
+                // The pivot is the item of rank `k` in the sample, selected before resuming.
+                let (left, right) = floyd_rivest_sample(len, sel.k - start);
+                sel.pivot = Some(sel.k);
+                stack[depth] = Selection::new(start + left, start + right, sel.k);
+                depth += 1;
+                continue;
+            }
+            None => median_of_three(range, 0, len / 2, len - 1, is_less),
+        };
+        sel.work += len;
+
+        let (lt, gt) = partition3(range, pivot, is_less);
+        if sel.k < start + lt {
+            sel.end = start + lt;
+        } else if sel.k >= start + gt {
+            sel.start = start + gt;
+        } else {
+            depth -= 1;
+        }
+    }
+}
+
+/// Rearranges `arr` so that `arr[k]` is the item of rank `k` (zero-based), with no greater items before it and no
+/// lesser items after it, and returns it. Returns [`None`] if `k >= arr.len()`.
+///
+/// Takes O(n) time in the worst case, without heap allocations.
+pub fn select_nth<T: Ord>(arr: &mut [T], k: usize) -> Option<&mut T> {
+    select_nth_by(arr, k, T::cmp)
+}
+
+/// Like [`select_nth`] but with comparator `compare`.
+pub fn select_nth_by<T>(
+    arr: &mut [T],
+    k: usize,
+    compare: impl FnMut(&T, &T) -> Ordering,
+) -> Option<&mut T> {
+    if k >= arr.len() {
+        return None;
+    }
+    select_with(arr, k, &mut less_from(compare));
+    Some(&mut arr[k])
+}
+
+/// Like [`select_nth`] but compares the keys extracted by `f`.
+pub fn select_nth_by_key<T, K: Ord>(
+    arr: &mut [T],
+    k: usize,
+    f: impl FnMut(&T) -> K,
+) -> Option<&mut T> {
+    select_nth_by(arr, k, compare_by_key(f))
+}
+
+/// Rearranges `arr` so that, for every `k` in `ks`, `arr[k]` is the item of rank `k`, and returns those items in
+/// the order of `ks`. Returns [`None`] if any `k >= arr.len()`.
+///
+/// Partitions are shared between ranks, so selecting m ranks takes O(n log m) time rather than O(n m). Allocates
+/// only O(m) memory.
+pub fn select_nth_many<'a, T: Ord>(arr: &'a mut [T], ks: &[usize]) -> Option<Vec<&'a T>> {
+    select_nth_many_by(arr, ks, T::cmp)
+}
+
+/// Like [`select_nth_many`] but with comparator `compare`.
+pub fn select_nth_many_by<'a, T>(
+    arr: &'a mut [T],
+    ks: &[usize],
+    compare: impl FnMut(&T, &T) -> Ordering,
+) -> Option<Vec<&'a T>> {
+    if ks.iter().any(|k| *k >= arr.len()) {
+        return None;
+    }
+    let is_less = &mut less_from(compare);
+
+    let mut ranks = ks.to_vec();
+    ranks.sort_unstable();
+    ranks.dedup();
+
+    // Each pending task is a range of `arr` and the range of `ranks` that fall within it.
+    let mut tasks = vec![(0, arr.len(), 0, ranks.len())];
+    while let Some((start, end, r_lo, r_hi)) = tasks.pop() {
+        if r_lo == r_hi {
+            continue;
+        }
+        let r_mid = r_lo + (r_hi - r_lo) / 2;
+        let k = ranks[r_mid];
+        select_with(&mut arr[start..end], k - start, is_less);
+        tasks.push((start, k, r_lo, r_mid));
+        tasks.push((k + 1, end, r_mid + 1, r_hi));
+    }
+
+    let arr = &*arr;
+    Some(ks.iter().map(|k| &arr[*k]).collect())
+}
+
+/// Like [`select_nth_many`] but compares the keys extracted by `f`.
+pub fn select_nth_many_by_key<'a, T, K: Ord>(
+    arr: &'a mut [T],
+    ks: &[usize],
+    f: impl FnMut(&T) -> K,
+) -> Option<Vec<&'a T>> {
+    select_nth_many_by(arr, ks, compare_by_key(f))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn sorted(v: &[i32]) -> Vec<i32> {
+        let mut v = v.to_vec();
+        v.sort();
+        v
+    }
+
+    fn check_partitioned(a: &[i32], k: usize) {
+        assert!(a[..k].iter().all(|x| *x <= a[k]));
+        assert!(a[k + 1..].iter().all(|x| *x >= a[k]));
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let mut empty: [i32; 0] = [];
+        assert_eq!(select_nth(&mut empty, 0), None);
+        assert_eq!(select_nth(&mut [1, 2, 3], 3), None);
+        assert_eq!(select_nth_many(&mut [1, 2, 3], &[0, 3]), None);
+        assert_eq!(select_nth_many(&mut [1, 2, 3], &[]), Some(vec![]));
+    }
+
+    #[test]
+    fn test_linear_comparisons() {
+        // Adversarial patterns for naive pivot choices, including all-equal items.
+        let n = 100_000;
+        let ascending = (0..n).collect::<Vec<i32>>();
+        let descending = (0..n).rev().collect::<Vec<i32>>();
+        let organ_pipe = (0..n / 2).chain((0..n / 2).rev()).collect::<Vec<i32>>();
+        let all_equal = vec![7; n as usize];
+        let few_distinct = (0..n).map(|x| x % 3).collect::<Vec<i32>>();
+
+        for v in [ascending, descending, organ_pipe, all_equal, few_distinct] {
+            let expected = sorted(&v);
+            for k in [0, n as usize / 2, n as usize * 9 / 10, n as usize - 1] {
+                let mut a = v.clone();
+                let mut comparisons = 0;
+                let nth = *select_nth_by(&mut a, k, |x, y| {
+                    comparisons += 1;
+                    x.cmp(y)
+                })
+                .unwrap();
+                assert_eq!(nth, expected[k]);
+                check_partitioned(&a, k);
+                assert!(comparisons < 20 * n, "k={k}, comparisons={comparisons}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_median_of_medians() {
+        // Forcing the fallback from the start must still select correctly.
+        let mut a = (0..1000).rev().collect::<Vec<i32>>();
+        let groups = group_medians(&mut a, &mut |x, y| x < y);
+        select_with(&mut a[..groups], groups / 2, &mut |x, y| x < y);
+        let (lt, gt) = partition3(&mut a, groups / 2, &mut |x, y| x < y);
+        assert_eq!(gt - lt, 1);
+        assert!((300..=700).contains(&lt), "lt={lt}");
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut a = (1..=1000).rev().collect::<Vec<i32>>();
+        let ps = select_nth_many(&mut a, &[499, 899, 989]).unwrap();
+        assert_eq!(ps, vec![&500, &900, &990]);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_select_nth_matches_sort(
+            v in prop::collection::vec(0..100_i32, 1..2000),
+            k in any::<prop::sample::Index>(),
+        ) {
+            let k = k.index(v.len());
+            let mut a = v.clone();
+            let nth = *select_nth(&mut a, k).unwrap();
+            prop_assert_eq!(nth, sorted(&v)[k]);
+            check_partitioned(&a, k);
+
+            let mut a = v.clone();
+            let nth = *select_nth_by(&mut a, k, |x, y| y.cmp(x)).unwrap();
+            prop_assert_eq!(nth, sorted(&v)[v.len() - 1 - k]);
+
+            let mut a = v.clone();
+            let nth = *select_nth_by_key(&mut a, k, |x| -x).unwrap();
+            prop_assert_eq!(nth, sorted(&v)[v.len() - 1 - k]);
+        }
+
+        #[test]
+        fn prop_select_nth_many_matches_sort(
+            v in prop::collection::vec(any::<i32>(), 1..2000),
+            ks in prop::collection::vec(any::<prop::sample::Index>(), 0..10),
+        ) {
+            let ks = ks.iter().map(|k| k.index(v.len())).collect::<Vec<_>>();
+            let expected = sorted(&v);
+            let mut a = v.clone();
+            let nths = select_nth_many(&mut a, &ks).unwrap();
+            prop_assert_eq!(nths, ks.iter().map(|k| &expected[*k]).collect::<Vec<_>>());
+            for k in &ks {
+                check_partitioned(&a, *k);
+            }
+        }
+    }
+}
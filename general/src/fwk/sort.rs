@@ -0,0 +1,312 @@
+//! Silent, generic in-place sorting routines: insertion sort, heapsort, and introsort.
+//!
+//! Each routine comes in three forms, mirroring the standard slice methods: a plain one for [`Ord`] items, a `_by`
+//! one taking a comparator, and a `_by_key` one taking a key extraction function.
+
+use std::cmp::Ordering;
+
+/// Slices at most this long are sorted with insertion sort by [`introsort_by`].
+pub(crate) const INSERTION_SORT_THRESHOLD: usize = 16;
+
+/// Turns a comparator into the "less than" predicate used internally by the sorting routines.
+pub(crate) fn less_from<T>(
+    mut compare: impl FnMut(&T, &T) -> Ordering,
+) -> impl FnMut(&T, &T) -> bool {
+    move |a, b| compare(a, b) == Ordering::Less
+}
+
+/// Turns a key extraction function into a comparator.
+pub(crate) fn compare_by_key<T, K: Ord>(
+    mut f: impl FnMut(&T) -> K,
+) -> impl FnMut(&T, &T) -> Ordering {
+    move |a, b| f(a).cmp(&f(b))
+}
+
+//=================
+// Insertion sort
+
+pub(crate) fn insertion_sort_with<T>(arr: &mut [T], is_less: &mut impl FnMut(&T, &T) -> bool) {
+    for i in 1..arr.len() {
+        let mut j = i;
+        while j > 0 && is_less(&arr[j], &arr[j - 1]) {
+            arr.swap(j, j - 1);
+            j -= 1;
+        }
+    }
+}
+
+/// Implementation of insertion sort. Stable, O(n²) worst case, efficient for short or nearly sorted slices.
+///
+/// Sorts `arr` in place without heap allocations.
+pub fn insertion_sort<T: Ord>(arr: &mut [T]) {
+    insertion_sort_by(arr, T::cmp)
+}
+
+/// Like [`insertion_sort`] but with comparator `compare`.
+pub fn insertion_sort_by<T>(arr: &mut [T], compare: impl FnMut(&T, &T) -> Ordering) {
+    insertion_sort_with(arr, &mut less_from(compare))
+}
+
+/// Like [`insertion_sort`] but compares the keys extracted by `f`.
+pub fn insertion_sort_by_key<T, K: Ord>(arr: &mut [T], f: impl FnMut(&T) -> K) {
+    insertion_sort_by(arr, compare_by_key(f))
+}
+
+//=================
+// Heapsort
+
+/// Restores the max-heap property of `heap` for the subtree rooted at `node`.
+pub(crate) fn sift_down<T>(
+    heap: &mut [T],
+    mut node: usize,
+    is_less: &mut impl FnMut(&T, &T) -> bool,
+) {
+    loop {
+        let mut child = 2 * node + 1;
+        if child >= heap.len() {
+            return;
+        }
+        if child + 1 < heap.len() && is_less(&heap[child], &heap[child + 1]) {
+            child += 1;
+        }
+        if !is_less(&heap[node], &heap[child]) {
+            return;
+        }
+        heap.swap(node, child);
+        node = child;
+    }
+}
+
+pub(crate) fn heapsort_with<T>(arr: &mut [T], is_less: &mut impl FnMut(&T, &T) -> bool) {
+    let len = arr.len();
+    for node in (0..len / 2).rev() {
+        sift_down(arr, node, is_less);
+    }
+    for end in (1..len).rev() {
+        arr.swap(0, end);
+        sift_down(&mut arr[..end], 0, is_less);
+    }
+}
+
+/// Implementation of heapsort. Unstable, O(n log n) worst case.
+///
+/// Sorts `arr` in place without heap allocations.
+pub fn heapsort<T: Ord>(arr: &mut [T]) {
+    heapsort_by(arr, T::cmp)
+}
+
+/// Like [`heapsort`] but with comparator `compare`.
+pub fn heapsort_by<T>(arr: &mut [T], compare: impl FnMut(&T, &T) -> Ordering) {
+    heapsort_with(arr, &mut less_from(compare))
+}
+
+/// Like [`heapsort`] but compares the keys extracted by `f`.
+pub fn heapsort_by_key<T, K: Ord>(arr: &mut [T], f: impl FnMut(&T) -> K) {
+    heapsort_by(arr, compare_by_key(f))
+}
+
+//=================
+// Introsort
+
+/// Moves the median of the first, middle and last items of `arr` to its last position.
+fn median_of_three_to_end<T>(arr: &mut [T], is_less: &mut impl FnMut(&T, &T) -> bool) {
+    let (a, b, c) = (0, arr.len() / 2, arr.len() - 1);
+    if is_less(&arr[b], &arr[a]) {
+        arr.swap(a, b);
+    }
+    if is_less(&arr[c], &arr[b]) {
+        arr.swap(b, c);
+        if is_less(&arr[b], &arr[a]) {
+            arr.swap(a, b);
+        }
+    }
+    arr.swap(b, c);
+}
+
+/// Partitions `arr`, with a median-of-three pivot, into items less than the pivot, the pivot, and items not less
+/// than the pivot. Returns the final index of the pivot.
+pub(crate) fn partition<T>(arr: &mut [T], is_less: &mut impl FnMut(&T, &T) -> bool) -> usize {
+    let pivot = arr.len() - 1;
+    median_of_three_to_end(arr, is_less);
+    let mut store = 0;
+    for i in 0..pivot {
+        if is_less(&arr[i], &arr[pivot]) {
+            arr.swap(i, store);
+            store += 1;
+        }
+    }
+    arr.swap(store, pivot);
+    store
+}
+
+/// Depth limit after which [`introsort_with`] switches to heapsort.
+pub(crate) fn depth_limit(len: usize) -> u32 {
+    2 * (usize::BITS - len.leading_zeros())
+}
+
+pub(crate) fn introsort_with<T>(
+    mut arr: &mut [T],
+    is_less: &mut impl FnMut(&T, &T) -> bool,
+    mut limit: u32,
+) {
+    loop {
+        let len = arr.len();
+        if len <= INSERTION_SORT_THRESHOLD {
+            insertion_sort_with(arr, is_less);
+            return;
+        }
+        if limit == 0 {
+            heapsort_with(arr, is_less);
+            return;
+        }
+        limit -= 1;
+
+        let p = partition(arr, is_less);
+        let (left, right) = arr.split_at_mut(p);
+        let right = &mut right[1..];
+
+        // Recurse into the smaller side and loop on the larger one to keep the stack depth logarithmic.
+        if left.len() < right.len() {
+            introsort_with(left, is_less, limit);
+            arr = right;
+        } else {
+            introsort_with(right, is_less, limit);
+            arr = left;
+        }
+    }
+}
+
+/// Implementation of introsort: quicksort with median-of-three pivots that falls back to heapsort when the recursion
+/// gets too deep and to insertion sort for short slices. Unstable, O(n log n) worst case.
+///
+/// Sorts `arr` in place without heap allocations. Stack usage is O(log n).
+pub fn introsort<T: Ord>(arr: &mut [T]) {
+    introsort_by(arr, T::cmp)
+}
+
+/// Like [`introsort`] but with comparator `compare`.
+pub fn introsort_by<T>(arr: &mut [T], compare: impl FnMut(&T, &T) -> Ordering) {
+    let limit = depth_limit(arr.len());
+    introsort_with(arr, &mut less_from(compare), limit)
+}
+
+/// Like [`introsort`] but compares the keys extracted by `f`.
+pub fn introsort_by_key<T, K: Ord>(arr: &mut [T], f: impl FnMut(&T) -> K) {
+    introsort_by(arr, compare_by_key(f))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fwk::{
+        comb_sort::{
+            bubble_sort, bubble_sort_by, bubble_sort_by_key, comb_sort, comb_sort_by,
+            comb_sort_by_key,
+        },
+        quicksort::{quickselect, quicksort},
+    };
+    use proptest::prelude::*;
+
+    fn sorted<T: Ord + Clone>(v: &[T]) -> Vec<T> {
+        let mut v = v.to_vec();
+        v.sort();
+        v
+    }
+
+    fn check(v: &[i32], f: impl Fn(&mut [i32])) -> Result<(), TestCaseError> {
+        let mut a = v.to_vec();
+        f(&mut a);
+        prop_assert_eq!(a, sorted(v));
+        Ok(())
+    }
+
+    #[test]
+    fn test_adversarial() {
+        // Inputs that degrade naive quicksort, long enough to exercise the heapsort fallback.
+        let n = 10_000;
+        let ascending = (0..n).collect::<Vec<i32>>();
+        let descending = (0..n).rev().collect::<Vec<i32>>();
+        let organ_pipe = (0..n / 2).chain((0..n / 2).rev()).collect::<Vec<i32>>();
+        let all_equal = vec![7; n as usize];
+        for v in [ascending, descending, organ_pipe, all_equal] {
+            let mut a = v.clone();
+            introsort(&mut a);
+            assert_eq!(a, sorted(&v));
+        }
+    }
+
+    #[test]
+    fn test_heapsort_fallback() {
+        // With a zero depth limit, introsort must delegate to heapsort right away.
+        let mut a = (0..100).rev().collect::<Vec<i32>>();
+        introsort_with(&mut a, &mut |x, y| x < y, 0);
+        assert_eq!(a, (0..100).collect::<Vec<_>>());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_sorts_match_std(v in prop::collection::vec(any::<i32>(), 0..300)) {
+            check(&v, introsort)?;
+            check(&v, heapsort)?;
+            check(&v, insertion_sort)?;
+            check(&v, comb_sort)?;
+            check(&v, bubble_sort)?;
+            check(&v, quicksort)?;
+        }
+
+        #[test]
+        fn prop_sorts_with_duplicates_match_std(v in prop::collection::vec(0..8_i32, 0..300)) {
+            check(&v, introsort)?;
+            check(&v, heapsort)?;
+            check(&v, quicksort)?;
+        }
+
+        #[test]
+        fn prop_quickselect_matches_std(v in prop::collection::vec(0..50_i32, 1..300), k in any::<prop::sample::Index>()) {
+            let k = k.index(v.len());
+            let mut a = v.clone();
+            quickselect(&mut a, k);
+            prop_assert_eq!(a[k], sorted(&v)[k]);
+        }
+
+        #[test]
+        fn prop_by_and_by_key_match_std(v in prop::collection::vec(any::<i32>(), 0..300)) {
+            type Cmp = fn(&i32, &i32) -> Ordering;
+            type Key = fn(&i32) -> u32;
+            let reverse: Cmp = |a, b| b.cmp(a);
+            let abs: Key = |x| x.unsigned_abs();
+
+            let mut expected = v.clone();
+            expected.sort_by(reverse);
+            let sorts_by: [fn(&mut [i32], Cmp); 5] = [
+                |a, c| introsort_by(a, c),
+                |a, c| heapsort_by(a, c),
+                |a, c| insertion_sort_by(a, c),
+                |a, c| comb_sort_by(a, c),
+                |a, c| bubble_sort_by(a, c),
+            ];
+            for f in sorts_by {
+                let mut a = v.clone();
+                f(&mut a, reverse);
+                prop_assert_eq!(&a, &expected);
+            }
+
+            // Unstable sorts may order items with equal keys differently, so only the keys are compared.
+            let keys = |a: &[i32]| a.iter().map(abs).collect::<Vec<_>>();
+            let mut expected = v.clone();
+            expected.sort_by_key(abs);
+            let sorts_by_key: [fn(&mut [i32], Key); 5] = [
+                |a, k| introsort_by_key(a, k),
+                |a, k| heapsort_by_key(a, k),
+                |a, k| insertion_sort_by_key(a, k),
+                |a, k| comb_sort_by_key(a, k),
+                |a, k| bubble_sort_by_key(a, k),
+            ];
+            for f in sorts_by_key {
+                let mut a = v.clone();
+                f(&mut a, abs);
+                prop_assert_eq!(keys(&a), keys(&expected));
+            }
+        }
+    }
+}
@@ -0,0 +1,418 @@
+//! Sorting and selection for items that are only [`PartialOrd`], such as floats, which may contain `NaN`.
+//!
+//! The float entry points impose a total order based on `total_cmp`, with all `NaN`s placed first or last
+//! regardless of their sign, or rejected with an error, according to [`NanPlacement`]. The generic `try_` entry
+//! points detect incomparable items and return a [`SortError`] instead of silently producing an arbitrary order.
+
+use super::{
+    merge_sort::merge_sort_with,
+    select::select_with,
+    sort::{depth_limit, introsort_with},
+};
+use std::cmp::Ordering;
+use thiserror::Error;
+
+/// Errors returned by the functions in this module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum SortError {
+    #[error("item at index {index} is not comparable with itself, e.g., NaN")]
+    Unordered { index: usize },
+    #[error("incomparable items were found while sorting")]
+    Incomparable,
+    #[error("rank {k} is out of range for length {len}")]
+    RankOutOfRange { k: usize, len: usize },
+}
+
+/// Where float sorts put `NaN`s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NanPlacement {
+    First,
+    Last,
+    /// Fail with [`SortError::Unordered`] at the first `NaN`, leaving the slice unchanged.
+    Reject,
+}
+
+/// Floating-point types with a total order.
+pub trait TotalFloat: Copy + PartialOrd {
+    fn is_nan(self) -> bool;
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+
+macro_rules! impl_total_float {
+    ($($t:ty),*) => {
+        $(
+            impl TotalFloat for $t {
+                fn is_nan(self) -> bool {
+                    <$t>::is_nan(self)
+                }
+
+                fn total_cmp(&self, other: &Self) -> Ordering {
+                    <$t>::total_cmp(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_total_float!(f32, f64);
+
+//=================
+// Floats
+
+/// Returns [`SortError::Unordered`] if `nan` is [`NanPlacement::Reject`] and some item's key is `NaN`.
+fn reject_nan<T, F: TotalFloat>(
+    arr: &[T],
+    f: &mut impl FnMut(&T) -> F,
+    nan: NanPlacement,
+) -> Result<(), SortError> {
+    if nan == NanPlacement::Reject
+        && let Some(index) = arr.iter().position(|x| f(x).is_nan())
+    {
+        return Err(SortError::Unordered { index });
+    }
+    Ok(())
+}
+
+/// Returns the "less than" predicate for float keys, with `NaN`s first unless `nan` is [`NanPlacement::Last`].
+fn float_less<T, F: TotalFloat>(
+    mut f: impl FnMut(&T) -> F,
+    nan: NanPlacement,
+) -> impl FnMut(&T, &T) -> bool {
+    let nan_first = nan == NanPlacement::First;
+    move |a: &T, b: &T| {
+        let (a, b) = (f(a), f(b));
+        match (a.is_nan(), b.is_nan()) {
+            (false, false) => a.total_cmp(&b) == Ordering::Less,
+            (true, false) => nan_first,
+            (false, true) => !nan_first,
+            (true, true) => false,
+        }
+    }
+}
+
+/// Sorts floats in the total order of `total_cmp`, i.e., with `-0.0` before `0.0`, and `NaN`s placed according to
+/// `nan`. Unstable, see [`super::sort::introsort`].
+pub fn sort_floats<F: TotalFloat>(arr: &mut [F], nan: NanPlacement) -> Result<(), SortError> {
+    sort_floats_by_key(arr, |x| *x, nan)
+}
+
+/// Like [`sort_floats`] but sorts items by their float keys extracted by `f`.
+pub fn sort_floats_by_key<T, F: TotalFloat>(
+    arr: &mut [T],
+    mut f: impl FnMut(&T) -> F,
+    nan: NanPlacement,
+) -> Result<(), SortError> {
+    reject_nan(arr, &mut f, nan)?;
+    let mut is_less = float_less(f, nan);
+    let limit = depth_limit(arr.len());
+    introsort_with(arr, &mut is_less, limit);
+    Ok(())
+}
+
+/// Stable version of [`sort_floats`], see [`super::merge_sort::merge_sort`].
+pub fn merge_sort_floats<F: TotalFloat>(arr: &mut [F], nan: NanPlacement) -> Result<(), SortError> {
+    merge_sort_floats_by_key(arr, |x| *x, nan)
+}
+
+/// Stable version of [`sort_floats_by_key`].
+pub fn merge_sort_floats_by_key<T, F: TotalFloat>(
+    arr: &mut [T],
+    mut f: impl FnMut(&T) -> F,
+    nan: NanPlacement,
+) -> Result<(), SortError> {
+    reject_nan(arr, &mut f, nan)?;
+    let mut is_less = float_less(f, nan);
+    merge_sort_with(arr, &mut is_less);
+    Ok(())
+}
+
+/// Like [`super::select::select_nth`] but for floats in the order of [`sort_floats`].
+pub fn select_nth_float<F: TotalFloat>(
+    arr: &mut [F],
+    k: usize,
+    nan: NanPlacement,
+) -> Result<&mut F, SortError> {
+    select_nth_float_by_key(arr, k, |x| *x, nan)
+}
+
+/// Like [`select_nth_float`] but selects items by their float keys extracted by `f`.
+pub fn select_nth_float_by_key<T, F: TotalFloat>(
+    arr: &mut [T],
+    k: usize,
+    mut f: impl FnMut(&T) -> F,
+    nan: NanPlacement,
+) -> Result<&mut T, SortError> {
+    let len = arr.len();
+    if k >= len {
+        return Err(SortError::RankOutOfRange { k, len });
+    }
+    reject_nan(arr, &mut f, nan)?;
+    let mut is_less = float_less(f, nan);
+    select_with(arr, k, &mut is_less);
+    Ok(&mut arr[k])
+}
+
+//=================
+// Generic partial orders
+
+/// Checks that every item is comparable with itself, which fails, e.g., for `NaN`.
+fn check_reflexive<T>(
+    arr: &[T],
+    compare: &mut impl FnMut(&T, &T) -> Option<Ordering>,
+) -> Result<(), SortError> {
+    match arr.iter().position(|x| compare(x, x).is_none()) {
+        Some(index) => Err(SortError::Unordered { index }),
+        None => Ok(()),
+    }
+}
+
+/// Runs `sort` with a "less than" predicate that records incomparable pairs and treats them as equal.
+fn run_checked<T>(
+    arr: &mut [T],
+    mut compare: impl FnMut(&T, &T) -> Option<Ordering>,
+    sort: impl FnOnce(&mut [T], &mut dyn FnMut(&T, &T) -> bool),
+) -> Result<(), SortError> {
+    check_reflexive(arr, &mut compare)?;
+    let mut incomparable = false;
+    let mut is_less = |a: &T, b: &T| match compare(a, b) {
+        Some(ord) => ord == Ordering::Less,
+        None => {
+            incomparable = true;
+            false
+        }
+    };
+    sort(arr, &mut is_less);
+    if incomparable {
+        return Err(SortError::Incomparable);
+    }
+    Ok(())
+}
+
+/// Sorts `arr` like [`super::sort::introsort`] but checks that its items are comparable.
+///
+/// Returns [`SortError::Unordered`], with `arr` unchanged, if an item is not comparable with itself, and
+/// [`SortError::Incomparable`], with `arr` in an unspecified order, if two items are found incomparable while
+/// sorting.
+pub fn try_introsort<T: PartialOrd>(arr: &mut [T]) -> Result<(), SortError> {
+    try_introsort_by(arr, T::partial_cmp)
+}
+
+/// Like [`try_introsort`] but with partial comparator `compare`.
+pub fn try_introsort_by<T>(
+    arr: &mut [T],
+    compare: impl FnMut(&T, &T) -> Option<Ordering>,
+) -> Result<(), SortError> {
+    let limit = depth_limit(arr.len());
+    run_checked(arr, compare, |arr, is_less| {
+        introsort_with(arr, &mut |a, b| is_less(a, b), limit)
+    })
+}
+
+/// Like [`try_introsort`] but compares the keys extracted by `f`.
+pub fn try_introsort_by_key<T, K: PartialOrd>(
+    arr: &mut [T],
+    mut f: impl FnMut(&T) -> K,
+) -> Result<(), SortError> {
+    try_introsort_by(arr, |a, b| f(a).partial_cmp(&f(b)))
+}
+
+/// Stable version of [`try_introsort`], see [`super::merge_sort::merge_sort`].
+pub fn try_merge_sort<T: PartialOrd>(arr: &mut [T]) -> Result<(), SortError> {
+    try_merge_sort_by(arr, T::partial_cmp)
+}
+
+/// Like [`try_merge_sort`] but with partial comparator `compare`.
+pub fn try_merge_sort_by<T>(
+    arr: &mut [T],
+    compare: impl FnMut(&T, &T) -> Option<Ordering>,
+) -> Result<(), SortError> {
+    run_checked(arr, compare, |arr, is_less| {
+        merge_sort_with(arr, &mut |a, b| is_less(a, b))
+    })
+}
+
+/// Like [`try_merge_sort`] but compares the keys extracted by `f`.
+pub fn try_merge_sort_by_key<T, K: PartialOrd>(
+    arr: &mut [T],
+    mut f: impl FnMut(&T) -> K,
+) -> Result<(), SortError> {
+    try_merge_sort_by(arr, |a, b| f(a).partial_cmp(&f(b)))
+}
+
+/// Like [`super::select::select_nth`] but checks that the items are comparable, as [`try_introsort`] does.
+pub fn try_select_nth<T: PartialOrd>(arr: &mut [T], k: usize) -> Result<&mut T, SortError> {
+    try_select_nth_by(arr, k, T::partial_cmp)
+}
+
+/// Like [`try_select_nth`] but with partial comparator `compare`.
+pub fn try_select_nth_by<T>(
+    arr: &mut [T],
+    k: usize,
+    compare: impl FnMut(&T, &T) -> Option<Ordering>,
+) -> Result<&mut T, SortError> {
+    let len = arr.len();
+    if k >= len {
+        return Err(SortError::RankOutOfRange { k, len });
+    }
+    run_checked(arr, compare, |arr, is_less| {
+        select_with(arr, k, &mut |a, b| is_less(a, b))
+    })?;
+    Ok(&mut arr[k])
+}
+
+/// Like [`try_select_nth`] but compares the keys extracted by `f`.
+pub fn try_select_nth_by_key<T, K: PartialOrd>(
+    arr: &mut [T],
+    k: usize,
+    mut f: impl FnMut(&T) -> K,
+) -> Result<&mut T, SortError> {
+    try_select_nth_by(arr, k, |a, b| f(a).partial_cmp(&f(b)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Pairs ordered componentwise, so `(0, 1)` and `(1, 0)` are incomparable.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Pair(i32, i32);
+
+    impl PartialOrd for Pair {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            match (self.0.cmp(&other.0), self.1.cmp(&other.1)) {
+                (a, b) if a == b => Some(a),
+                (a, Ordering::Equal) => Some(a),
+                (Ordering::Equal, b) => Some(b),
+                _ => None,
+            }
+        }
+    }
+
+    fn bits(v: &[f64]) -> Vec<u64> {
+        v.iter().map(|x| x.to_bits()).collect()
+    }
+
+    #[test]
+    fn test_nan_placement() {
+        let v = [
+            1.,
+            f64::NAN,
+            -0.,
+            f64::NEG_INFINITY,
+            -f64::NAN,
+            0.,
+            f64::INFINITY,
+        ];
+
+        let mut a = v;
+        sort_floats(&mut a, NanPlacement::Last).unwrap();
+        assert_eq!(
+            bits(&a[..5]),
+            bits(&[f64::NEG_INFINITY, -0., 0., 1., f64::INFINITY])
+        );
+        assert!(a[5..].iter().all(|x| x.is_nan()));
+
+        let mut a = v;
+        merge_sort_floats(&mut a, NanPlacement::First).unwrap();
+        assert!(a[..2].iter().all(|x| x.is_nan()));
+        assert_eq!(
+            bits(&a[2..]),
+            bits(&[f64::NEG_INFINITY, -0., 0., 1., f64::INFINITY])
+        );
+
+        let mut a = v;
+        assert_eq!(
+            sort_floats(&mut a, NanPlacement::Reject),
+            Err(SortError::Unordered { index: 1 })
+        );
+        assert_eq!(bits(&a), bits(&v));
+
+        let mut a = v;
+        assert_eq!(
+            *select_nth_float(&mut a, 3, NanPlacement::First).unwrap(),
+            -0.
+        );
+        assert_eq!(
+            select_nth_float(&mut a, 7, NanPlacement::First),
+            Err(SortError::RankOutOfRange { k: 7, len: 7 })
+        );
+    }
+
+    #[test]
+    fn test_float_keys() {
+        let mut spans = vec![("a", 3.), ("b", f64::NAN), ("c", 1.), ("d", 2.)];
+        merge_sort_floats_by_key(&mut spans, |s| s.1, NanPlacement::First).unwrap();
+        assert_eq!(spans.iter().map(|s| s.0).collect::<String>(), "bcda");
+
+        let slowest = select_nth_float_by_key(&mut spans, 3, |s| s.1, NanPlacement::First).unwrap();
+        assert_eq!(slowest.0, "a");
+
+        let err = sort_floats_by_key(&mut spans, |s| s.1, NanPlacement::Reject);
+        assert_eq!(err, Err(SortError::Unordered { index: 0 }));
+    }
+
+    #[test]
+    fn test_try_sorts() {
+        let mut a = [3., 1., f64::NAN, 2.];
+        assert_eq!(
+            try_introsort(&mut a),
+            Err(SortError::Unordered { index: 2 })
+        );
+        assert_eq!(
+            try_merge_sort(&mut a),
+            Err(SortError::Unordered { index: 2 })
+        );
+        assert_eq!(
+            try_select_nth(&mut a, 0),
+            Err(SortError::Unordered { index: 2 })
+        );
+
+        let mut a = [3., 1., 2.];
+        assert_eq!(try_introsort(&mut a), Ok(()));
+        assert_eq!(a, [1., 2., 3.]);
+        assert_eq!(
+            try_select_nth(&mut a, 5),
+            Err(SortError::RankOutOfRange { k: 5, len: 3 })
+        );
+
+        let mut chain = [Pair(2, 2), Pair(0, 0), Pair(1, 2)];
+        assert_eq!(try_merge_sort(&mut chain), Ok(()));
+        assert_eq!(chain, [Pair(0, 0), Pair(1, 2), Pair(2, 2)]);
+
+        let mut antichain = [Pair(2, 2), Pair(0, 1), Pair(1, 0)];
+        assert_eq!(try_introsort(&mut antichain), Err(SortError::Incomparable));
+        assert_eq!(try_merge_sort(&mut antichain), Err(SortError::Incomparable));
+        assert_eq!(
+            try_select_nth(&mut antichain, 1),
+            Err(SortError::Incomparable)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_sort_floats_matches_total_cmp(v in prop::collection::vec(prop::num::f64::ANY, 0..300)) {
+            let mut expected = v.iter().copied().filter(|x| !x.is_nan()).collect::<Vec<_>>();
+            expected.sort_by(f64::total_cmp);
+            let nans = v.len() - expected.len();
+
+            let mut a = v.clone();
+            sort_floats(&mut a, NanPlacement::Last).unwrap();
+            prop_assert_eq!(bits(&a[..expected.len()]), bits(&expected));
+            prop_assert!(a[expected.len()..].iter().all(|x| x.is_nan()));
+
+            let mut a = v.clone();
+            merge_sort_floats(&mut a, NanPlacement::First).unwrap();
+            prop_assert!(a[..nans].iter().all(|x| x.is_nan()));
+            prop_assert_eq!(bits(&a[nans..]), bits(&expected));
+
+            let mut a = v.clone();
+            let res = try_introsort(&mut a);
+            prop_assert_eq!(res.is_ok(), nans == 0);
+            if nans == 0 {
+                // `-0.0` and `0.0` are equal in the partial order, so compare values rather than bits.
+                prop_assert_eq!(a, expected);
+            }
+        }
+    }
+}
@@ -1,4 +1,10 @@
 //! Generic wrapper to facilitate the addition of new methods to the wrapped type.
+//!
+//! With the `serde` feature, [`Wrapper`](struct@Wrapper) serializes and deserializes exactly as the wrapped value.
+//!
+//! For domain newtypes that need their own name rather than a `Wrapper` specialization, `#[derive(Wrapper)]`
+//! generates the same delegations for any single-field struct, except `IntoIterator`, which is optional like
+//! `Display`, `FromStr`, the arithmetic operators and `Index`; see [`wrapper_derive`].
 
 use std::{
     borrow::Borrow,
@@ -8,6 +14,8 @@ use std::{
     sync::Arc,
 };
 
+pub use wrapper_derive::Wrapper;
+
 /// Generic wrapper to facilitate the addition of new methods to the wrapped type.
 #[derive(PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
 pub struct Wrapper<T>(pub T);
@@ -119,3 +127,118 @@ where
         self.0.into_iter()
     }
 }
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Wrapper<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Wrapper<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{borrow::BorrowMut, ops::IndexMut};
+
+    #[derive(Wrapper, Clone, Copy, PartialEq, PartialOrd)]
+    #[wrapper(default, ops, display, from_str)]
+    struct Meters(f64);
+
+    #[derive(Wrapper)]
+    // `borrow_mut` and `index_mut` imply `borrow` and `index`.
+    #[wrapper(new, deref, as_mut, borrow_mut, index_mut, into_iterator)]
+    struct Samples {
+        values: Vec<u32>,
+    }
+
+    #[derive(Wrapper)]
+    #[wrapper(default, into_iterator)]
+    struct Labeled<T>(T);
+
+    fn first<S: IndexMut<usize, Output = u32>>(s: &mut S) -> &mut u32 {
+        &mut s[0]
+    }
+
+    #[test]
+    fn test_derive_default() {
+        let m = Meters::new(2.5);
+        assert_eq!(*m, 2.5);
+        assert_eq!(*m.value(), 2.5);
+        assert_eq!(m.into_inner(), 2.5);
+        assert_eq!(Meters::from(1.0).sqrt(), 1.0);
+        assert_eq!(format!("{:?}", Meters(1.5)), "1.5");
+
+        let mut l = Labeled(vec!["a", "b"]);
+        l.push("c");
+        assert_eq!(AsRef::<Vec<_>>::as_ref(&l).len(), 3);
+        assert_eq!(Borrow::<Vec<_>>::borrow(&l)[2], "c");
+        assert_eq!((&l).into_iter().count(), 3);
+        assert_eq!(l.into_iter().collect::<String>(), "abc");
+    }
+
+    #[test]
+    fn test_derive_display_from_str() {
+        assert_eq!(Meters(3.25).to_string(), "3.25");
+        assert_eq!("4.5".parse::<Meters>().unwrap(), Meters(4.5));
+        assert!("x".parse::<Meters>().is_err());
+    }
+
+    #[test]
+    fn test_derive_ops() {
+        let (a, b) = (Meters(6.0), Meters(4.0));
+        assert_eq!(a + b, Meters(10.0));
+        assert_eq!(a - b, Meters(2.0));
+        assert_eq!(a * b, Meters(24.0));
+        assert_eq!(a / b, Meters(1.5));
+        assert_eq!(a % b, Meters(2.0));
+        assert_eq!(-a, Meters(-6.0));
+
+        let mut c = a;
+        c += b;
+        c -= Meters(1.0);
+        c *= Meters(2.0);
+        c /= Meters(3.0);
+        c %= Meters(4.0);
+        assert_eq!(c, Meters(2.0));
+    }
+
+    #[test]
+    fn test_derive_named_field() {
+        let mut s = Samples::new(vec![3, 1, 2]);
+        assert_eq!(s[1], 1);
+        assert_eq!(s[1..], [1, 2]);
+        s[1] = 5;
+        *first(&mut s) += 1;
+        AsMut::<Vec<u32>>::as_mut(&mut s).push(7);
+        BorrowMut::<Vec<u32>>::borrow_mut(&mut s).push(0);
+        assert_eq!(Borrow::<Vec<u32>>::borrow(&s).last(), Some(&0));
+        BorrowMut::<Vec<u32>>::borrow_mut(&mut s).pop();
+        for v in &mut s {
+            *v *= 10;
+        }
+        assert_eq!(s.len(), 4);
+        assert_eq!((&s).into_iter().sum::<u32>(), 180);
+        assert_eq!(s.into_iter().collect::<Vec<_>>(), [40, 50, 20, 70]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_transparent() {
+        use std::collections::BTreeMap;
+
+        let w = Wrapper(BTreeMap::from([("a", 1), ("b", 2)]));
+        let json = serde_json::to_string(&w).unwrap();
+        assert_eq!(json, serde_json::to_string(&w.0).unwrap());
+        assert_eq!(json, r#"{"a":1,"b":2}"#);
+
+        let back: Wrapper<BTreeMap<String, i32>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back["b"], 2);
+    }
+}
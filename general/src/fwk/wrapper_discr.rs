@@ -1,5 +1,11 @@
 //! Explorations on a more general version of [`super::wrapper`] that has an additional
 //! discriminant type parameter.
+//!
+//! Named domain newtypes with the same delegations can instead be declared with `#[derive(Wrapper)]` from
+//! [`super::wrapper`], without a discriminant.
+//!
+//! With the `serde` feature, [`Wrapper`] and its specializations such as [`Mappable`] serialize and deserialize
+//! exactly as the wrapped value, without bounds on the discriminant.
 
 use std::{
     borrow::Borrow,
@@ -128,13 +134,28 @@ where
     }
 }
 
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize, P> serde::Serialize for Wrapper<T, P> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>, P> serde::Deserialize<'de> for Wrapper<T, P> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::constr)
+    }
+}
+
 //=================
 // Mappable
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct MappableDiscr<P>(P);
 
-/// Specializetion of [Wrapper] that adds a [`map`](Self::map) method.
+/// Specializetion of [Wrapper] that adds a [`map`](Self::map) method, with owned, borrowed and asynchronous
+/// combinators that keep the discriminant `P`.
 pub type Mappable<T, P = ()> = Wrapper<T, MappableDiscr<P>>;
 
 impl<T> Mappable<T> {
@@ -153,6 +174,153 @@ impl<T, P> Mappable<T, P> {
     pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> Mappable<U, P> {
         Mappable::wrap(f(&self.0))
     }
+
+    /// Owned form of [`map`](Self::map).
+    pub fn into_map<U>(self, f: impl FnOnce(T) -> U) -> Mappable<U, P> {
+        Mappable::wrap(f(self.0))
+    }
+
+    /// Returns the [`Mappable<U, P>`] produced by applying `f` to `self`'s wrapped value.
+    pub fn and_then<U>(&self, f: impl FnOnce(&T) -> Mappable<U, P>) -> Mappable<U, P> {
+        f(&self.0)
+    }
+
+    /// Owned form of [`and_then`](Self::and_then).
+    pub fn into_and_then<U>(self, f: impl FnOnce(T) -> Mappable<U, P>) -> Mappable<U, P> {
+        f(self.0)
+    }
+
+    /// Combines the wrapped values of `self` and `other` with `f`.
+    pub fn zip_with<U, V>(
+        &self,
+        other: &Mappable<U, P>,
+        f: impl FnOnce(&T, &U) -> V,
+    ) -> Mappable<V, P> {
+        Mappable::wrap(f(&self.0, &other.0))
+    }
+
+    /// Owned form of [`zip_with`](Self::zip_with).
+    pub fn into_zip_with<U, V>(
+        self,
+        other: Mappable<U, P>,
+        f: impl FnOnce(T, U) -> V,
+    ) -> Mappable<V, P> {
+        Mappable::wrap(f(self.0, other.0))
+    }
+
+    /// Pairs the wrapped values of `self` and `other`, cloning them.
+    pub fn zip<U: Clone>(&self, other: &Mappable<U, P>) -> Mappable<(T, U), P>
+    where
+        T: Clone,
+    {
+        self.zip_with(other, |t, u| (t.clone(), u.clone()))
+    }
+
+    /// Owned form of [`zip`](Self::zip).
+    pub fn into_zip<U>(self, other: Mappable<U, P>) -> Mappable<(T, U), P> {
+        self.into_zip_with(other, |t, u| (t, u))
+    }
+
+    /// Asynchronous form of [`map`](Self::map), awaiting the future returned by `f`.
+    pub async fn map_async<'a, U, F>(&'a self, f: impl FnOnce(&'a T) -> F) -> Mappable<U, P>
+    where
+        F: Future<Output = U>,
+    {
+        Mappable::wrap(f(&self.0).await)
+    }
+
+    /// Owned form of [`map_async`](Self::map_async).
+    pub async fn into_map_async<U, F>(self, f: impl FnOnce(T) -> F) -> Mappable<U, P>
+    where
+        F: Future<Output = U>,
+    {
+        Mappable::wrap(f(self.0).await)
+    }
+}
+
+/// Projections into an [`Option`] payload.
+impl<T, P> Mappable<Option<T>, P> {
+    /// Maps the [`Some`] value, if any.
+    pub fn map_some<U>(&self, f: impl FnOnce(&T) -> U) -> Mappable<Option<U>, P> {
+        Mappable::wrap(self.0.as_ref().map(f))
+    }
+
+    /// Owned form of [`map_some`](Self::map_some).
+    pub fn into_map_some<U>(self, f: impl FnOnce(T) -> U) -> Mappable<Option<U>, P> {
+        Mappable::wrap(self.0.map(f))
+    }
+
+    /// Moves the [`Option`] out of the wrapper: `None` if the wrapped value is `None`.
+    pub fn transpose(self) -> Option<Mappable<T, P>> {
+        self.0.map(Mappable::wrap)
+    }
+}
+
+/// Projections into a [`Result`] payload.
+impl<T, E, P> Mappable<Result<T, E>, P> {
+    /// Maps the [`Ok`] value, if any.
+    pub fn map_ok<U>(&self, f: impl FnOnce(&T) -> U) -> Mappable<Result<U, &E>, P> {
+        Mappable::wrap(self.0.as_ref().map(f))
+    }
+
+    /// Owned form of [`map_ok`](Self::map_ok).
+    pub fn into_map_ok<U>(self, f: impl FnOnce(T) -> U) -> Mappable<Result<U, E>, P> {
+        Mappable::wrap(self.0.map(f))
+    }
+
+    /// Maps the [`Err`] value, if any.
+    pub fn map_err<F>(&self, f: impl FnOnce(&E) -> F) -> Mappable<Result<&T, F>, P> {
+        Mappable::wrap(self.0.as_ref().map_err(f))
+    }
+
+    /// Owned form of [`map_err`](Self::map_err).
+    pub fn into_map_err<F>(self, f: impl FnOnce(E) -> F) -> Mappable<Result<T, F>, P> {
+        Mappable::wrap(self.0.map_err(f))
+    }
+
+    /// Moves the [`Result`] out of the wrapper, with the error unwrapped.
+    pub fn transpose(self) -> Result<Mappable<T, P>, E> {
+        self.0.map(Mappable::wrap)
+    }
+}
+
+/// Traversals of a [`Vec`] payload.
+impl<T, P> Mappable<Vec<T>, P> {
+    /// Applies `f` to each element, returning the first error or the wrapped results.
+    pub fn traverse<'a, U, E>(
+        &'a self,
+        f: impl FnMut(&'a T) -> Result<U, E>,
+    ) -> Result<Mappable<Vec<U>, P>, E> {
+        self.0
+            .iter()
+            .map(f)
+            .collect::<Result<_, _>>()
+            .map(Mappable::wrap)
+    }
+
+    /// Owned form of [`traverse`](Self::traverse).
+    pub fn into_traverse<U, E>(
+        self,
+        f: impl FnMut(T) -> Result<U, E>,
+    ) -> Result<Mappable<Vec<U>, P>, E> {
+        self.0
+            .into_iter()
+            .map(f)
+            .collect::<Result<_, _>>()
+            .map(Mappable::wrap)
+    }
+}
+
+impl<T, E, P> Mappable<Vec<Result<T, E>>, P> {
+    /// Returns the first error, or the wrapped [`Ok`] values.
+    pub fn sequence(self) -> Result<Mappable<Vec<T>, P>, E> {
+        self.into_traverse(|r| r)
+    }
+
+    /// Borrowed form of [`sequence`](Self::sequence).
+    pub fn sequence_ref(&self) -> Result<Mappable<Vec<&T>, P>, &E> {
+        self.traverse(|r| r.as_ref())
+    }
 }
 
 pub type Mappable1<T> = Mappable<T, String>;
@@ -190,3 +358,139 @@ impl<T, P> XMappable<T, P> {
         XMappable::wrap(f(&self.0))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Discriminant that implements no serde traits.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Discr;
+
+    fn m<T>(value: T) -> Mappable<T, Discr> {
+        Mappable::wrap(value)
+    }
+
+    fn f(x: i64) -> i64 {
+        x.wrapping_mul(3)
+    }
+
+    fn g(x: i64) -> i64 {
+        x.wrapping_add(7)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_functor_identity(x in any::<i64>()) {
+            prop_assert_eq!(m(x).map(|x| *x), m(x));
+            prop_assert_eq!(m(x).into_map(|x| x), m(x));
+        }
+
+        #[test]
+        fn prop_functor_composition(x in any::<i64>()) {
+            prop_assert_eq!(m(x).map(|x| f(*x)).map(|y| g(*y)), m(x).map(|x| g(f(*x))));
+            prop_assert_eq!(m(x).into_map(f).into_map(g), m(x).into_map(|x| g(f(x))));
+        }
+
+        #[test]
+        fn prop_functor_laws_on_payloads(v in prop::collection::vec(any::<i64>(), 0..20), o in any::<Option<i64>>()) {
+            prop_assert_eq!(m(o).map_some(|x| *x), m(o));
+            prop_assert_eq!(m(o).into_map_some(f).into_map_some(g), m(o).into_map_some(|x| g(f(x))));
+
+            let ok = m(Ok::<_, String>(v.clone()));
+            prop_assert_eq!(ok.clone().into_map_ok(|v| v), ok.clone());
+            let composed = ok.map_ok(|v| v.iter().copied().map(f).collect::<Vec<_>>()).into_map_ok(|v| v.into_iter().map(g).collect::<Vec<_>>());
+            prop_assert_eq!(composed.0.unwrap(), v.iter().map(|x| g(f(*x))).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn prop_monad_laws(x in any::<i64>()) {
+            let kf = |x: i64| m(f(x));
+            let kg = |x: i64| m(g(x));
+            prop_assert_eq!(m(x).into_and_then(kf), kf(x));
+            prop_assert_eq!(m(x).into_and_then(m), m(x));
+            prop_assert_eq!(m(x).into_and_then(kf).into_and_then(kg), m(x).into_and_then(|x| kf(x).into_and_then(kg)));
+            prop_assert_eq!(m(x).and_then(|x| kf(*x)), kf(x));
+        }
+    }
+
+    #[test]
+    fn test_discriminant_preserved() {
+        let s: Mappable1<&str> = Mappable1::new("abc");
+        let len: Mappable<usize, String> = s.map(|s| s.len());
+        let pair: Mappable<(usize, &str), String> = len.zip(&s);
+        assert_eq!(pair.0, (3, "abc"));
+        let joined: Mappable<String, String> =
+            pair.into_zip_with(s, |(n, a), b| format!("{n}{a}{b}"));
+        assert_eq!(joined.0, "3abcabc");
+    }
+
+    #[test]
+    fn test_zip() {
+        assert_eq!(m(1).zip_with(&m(2), |a, b| a + b), m(3));
+        assert_eq!(m(1).into_zip(m("a")), m((1, "a")));
+    }
+
+    #[test]
+    fn test_projections() {
+        assert_eq!(m(Some(2)).map_some(|x| x * 2), m(Some(4)));
+        assert_eq!(m(None::<i32>).map_some(|x| x * 2), m(None));
+        assert_eq!(m(Some(2)).transpose(), Some(m(2)));
+        assert_eq!(m(None::<i32>).transpose(), None);
+
+        let err = m(Err::<i32, _>("bad"));
+        assert_eq!(err.map_err(|e| e.len()).0, Err(3));
+        assert_eq!(err.into_map_err(str::to_uppercase).0, Err("BAD".to_owned()));
+        assert_eq!(m(Ok::<_, &str>(2)).map_ok(|x| x + 1).0, Ok(3));
+        assert_eq!(m(Ok::<_, &str>(2)).transpose(), Ok(m(2)));
+        assert_eq!(m(Err::<i32, _>("bad")).transpose(), Err("bad"));
+    }
+
+    #[test]
+    fn test_traverse_sequence() {
+        let parsed = m(vec!["1", "2", "3"]).traverse(|s| s.parse::<i32>());
+        assert_eq!(parsed, Ok(m(vec![1, 2, 3])));
+        let parsed = m(vec!["1", "x", "y"]).into_traverse(|s| s.parse::<i32>().map_err(|_| s));
+        assert_eq!(parsed, Err("x"));
+
+        let results = m(vec![Ok(1), Err("a"), Err("b")]);
+        assert_eq!(results.sequence_ref(), Err(&"a"));
+        assert_eq!(results.sequence(), Err("a"));
+        assert_eq!(m(vec![Ok::<_, ()>(1), Ok(2)]).sequence(), Ok(m(vec![1, 2])));
+        assert_eq!(m(Vec::<Result<i32, ()>>::new()).sequence(), Ok(m(vec![])));
+    }
+
+    #[test]
+    fn test_map_async() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        rt.block_on(async {
+            let v = m(vec![1, 2, 3]);
+            let len = v.map_async(|v| async move { v.len() }).await;
+            assert_eq!(len, m(3));
+            let sum = v
+                .into_map_async(|v| async move { v.into_iter().sum::<i32>() })
+                .await;
+            assert_eq!(sum, m(6));
+        });
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_transparent() {
+        use std::collections::BTreeMap;
+
+        let w = Wrapper::<_, Discr>::constr(vec![1, 2, 3]);
+        assert_eq!(serde_json::to_string(&w).unwrap(), "[1,2,3]");
+        let back: Wrapper<Vec<i32>, Discr> = serde_json::from_str("[4,5]").unwrap();
+        assert_eq!(back.0, [4, 5]);
+
+        let m = Mappable1::new(BTreeMap::from([("p50".to_owned(), 1.5)]));
+        let json = serde_json::to_string(&m).unwrap();
+        assert_eq!(json, r#"{"p50":1.5}"#);
+        let back: Mappable1<BTreeMap<String, f64>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.map_str(|m| format!("{m:?}")).0, r#"{"p50": 1.5}"#);
+    }
+}
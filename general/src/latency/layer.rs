@@ -0,0 +1,530 @@
+//! [`tracing_subscriber::Layer`] that records the latencies of spans by callsite:
+//! - total timings include suspend time and are based on span creation and closing;
+//! - active timings exclude suspend time and are based on span entry and exit.
+//!
+//! Timings are recorded in microseconds and can be exported as a [`LatencyReport`].
+
+use super::{LatencyReport, SpanKey, SpanLatencies};
+use hdrhistogram::{
+    Histogram,
+    sync::{Recorder, SyncHistogram},
+};
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap, HashSet},
+    future::Future,
+    ops::Deref,
+    sync::{Arc, RwLock},
+    thread,
+    time::Instant,
+};
+use tracing::{
+    Id, Metadata,
+    callsite::Identifier,
+    subscriber::{Interest, Subscriber},
+};
+use tracing_core::span::Attributes;
+use tracing_subscriber::{
+    Layer, Registry,
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+};
+
+//=================
+// Types
+
+/// Globally collected information for a callsite.
+#[derive(Debug)]
+struct CallsiteTiming {
+    total_time: SyncHistogram<u64>,
+    active_time: SyncHistogram<u64>,
+}
+
+/// Timings by callsite.
+type Timings = HashMap<Identifier, CallsiteTiming>;
+
+/// Callsite parents: all the parent callsites seen for each callsite, with `None` for root spans.
+/// Separate from [Timings] to avoid locking issues caused by [SyncHistogram].refresh.
+type Parents = HashMap<Identifier, HashSet<Option<Identifier>>>;
+
+/// Keys of the registered callsites. Spans are tracked by [`Identifier`], which is cheap to copy and compare, and
+/// only converted to [`SpanKey`]s in reports.
+type Keys = HashMap<Identifier, SpanKey>;
+
+/// Thread-local information collected for a callsite.
+struct LocalCallsiteTiming {
+    total_time: Recorder<u64>,
+    active_time: Recorder<u64>,
+}
+
+/// Information about a span stored in the registry.
+#[derive(Debug)]
+struct SpanTiming {
+    created_at: Instant,
+    entered_at: Instant,
+    acc_active_time: u64,
+    callsite: Identifier,
+    parent_callsite: Option<Identifier>,
+}
+
+/// Layer that collects the latencies of spans by callsite. See [`Self::report`].
+#[derive(Clone)]
+pub struct Latencies {
+    timings: Arc<RwLock<Timings>>,
+    parents: Arc<RwLock<Parents>>,
+    keys: Arc<RwLock<Keys>>,
+    /// Significant figures of the timing histograms.
+    sigfig: u8,
+}
+
+//=================
+// Thread-locals
+
+thread_local! {
+    static LOCAL_PARENT_INFO: RefCell<Parents> = RefCell::new(HashMap::new());
+}
+
+thread_local! {
+    static LOCAL_CALLSITE_INFO: RefCell<HashMap<Identifier, LocalCallsiteTiming>> = RefCell::new(HashMap::new());
+}
+
+//=================
+// impls
+
+impl Latencies {
+    /// Creates a layer whose timing histograms have 1 significant figure.
+    pub fn new() -> Latencies {
+        Self::with_significant_figures(1)
+    }
+
+    /// Creates a layer whose timing histograms have `sigfig` significant figures, between 0 and 5.
+    pub fn with_significant_figures(sigfig: u8) -> Latencies {
+        Latencies {
+            timings: Arc::new(RwLock::new(HashMap::new())),
+            parents: Arc::new(RwLock::new(HashMap::new())),
+            keys: Arc::new(RwLock::new(HashMap::new())),
+            sigfig,
+        }
+    }
+
+    fn refresh(&self) {
+        for (_, v) in self.timings.write().unwrap().iter_mut() {
+            v.total_time.refresh();
+            v.active_time.refresh();
+        }
+    }
+
+    /// Returns a process-independent snapshot of the timings collected so far. Distinct callsites can only share a
+    /// key if they are indistinguishable in the source code, in which case their timings are combined.
+    pub fn report(&self) -> LatencyReport {
+        let timings = self.timings.read().unwrap();
+        let parents = self.parents.read().unwrap();
+        let keys = self.keys.read().unwrap();
+        let key = |callsite: &Identifier| {
+            keys.get(callsite)
+                .expect("span callsites are registered")
+                .clone()
+        };
+
+        let mut report = LatencyReport::new();
+        for (callsite, v) in timings.iter() {
+            let callsite_parents = parents
+                .get(callsite)
+                .into_iter()
+                .flatten()
+                .map(|p| p.as_ref().map(key))
+                .collect::<BTreeSet<_>>();
+            match report.spans.get_mut(&key(callsite)) {
+                Some(span) => {
+                    span.total_time.add(v.total_time.deref()).unwrap();
+                    span.active_time.add(v.active_time.deref()).unwrap();
+                    span.parents.extend(callsite_parents);
+                }
+                None => {
+                    let latencies = SpanLatencies {
+                        parents: callsite_parents,
+                        total_time: v.total_time.deref().clone(),
+                        active_time: v.active_time.deref().clone(),
+                    };
+                    report.spans.insert(key(callsite), latencies);
+                }
+            }
+        }
+        report
+    }
+
+    pub fn print_mean_timings(&self) {
+        println!("\nMean timing values by span:");
+
+        for (callsite, v) in self.report().spans.iter() {
+            let mean_total_time = v.total_time.mean();
+            let mean_active_time = v.active_time.mean();
+            let total_time_count = v.total_time.len();
+            let active_time_count = v.active_time.len();
+            let parent = parents_to_strings(&v.parents);
+            println!(
+                "  callsite={}, parent={:?}, span_name={}, mean_total_time={}μs, total_time_count={}, mean_active_time={}μs, active_time_count={}",
+                callsite,
+                parent,
+                callsite.name,
+                mean_total_time,
+                total_time_count,
+                mean_active_time,
+                active_time_count
+            );
+        }
+    }
+
+    pub fn print_median_timings(&self) {
+        println!("\nMedian timings by span:");
+
+        for (callsite, v) in self.report().spans.iter() {
+            let median_total_time = v.total_time.value_at_quantile(0.5);
+            let median_active_time = v.active_time.value_at_quantile(0.5);
+            let total_time_count = v.total_time.len();
+            let active_time_count = v.active_time.len();
+            let parent = parents_to_strings(&v.parents);
+            println!(
+                "  callsite={}, parent_callsite={:?}, span_name={}, median_total_time={}μs, total_time_count={}, median_active_time={}μs, active_time_count={}",
+                callsite,
+                parent,
+                callsite.name,
+                median_total_time,
+                total_time_count,
+                median_active_time,
+                active_time_count
+            );
+        }
+    }
+
+    fn update_parent_info(&self, callsite: &Identifier, parent: &Option<Identifier>) {
+        log::debug!(
+            "entered `update_parent_info`for callsite id {:?} on thread {:?}",
+            callsite,
+            thread::current().id(),
+        );
+        let seen = |parents: &Parents| parents.get(callsite).is_some_and(|ps| ps.contains(parent));
+        LOCAL_PARENT_INFO.with(|parents_cell| {
+            let mut parents = parents_cell.borrow_mut();
+            if seen(&parents) {
+                // Both local and global parents info are good for this callsite and parent.
+                return;
+            }
+
+            // Update local parents
+            {
+                parents
+                    .entry(callsite.clone())
+                    .or_default()
+                    .insert(parent.clone());
+            }
+
+            // Update global parents
+            {
+                log::debug!(
+                    "`update_parent_info`getting read lock for callsite id {:?} on thread {:?}",
+                    callsite,
+                    thread::current().id()
+                );
+                let parents = self.parents.as_ref().read().unwrap();
+                if !seen(&parents) {
+                    drop(parents); // need to get write lock below;
+                    log::debug!(
+                        "`update_parent_info`getting write lock for callsite id {:?} on thread {:?}",
+                        callsite,
+                        thread::current().id()
+                    );
+                    let mut parents = self.parents.as_ref().write().unwrap();
+                    log::debug!(
+                        "`update_parent_info`got write lock for callsite id {:?} on thread {:?}",
+                        callsite,
+                        thread::current().id()
+                    );
+                    parents
+                        .entry(callsite.clone())
+                        .or_default()
+                        .insert(parent.clone());
+                }
+            }
+        });
+    }
+
+    fn with_local_callsite_info(
+        &self,
+        callsite: &Identifier,
+        f: impl Fn(&mut LocalCallsiteTiming),
+    ) {
+        LOCAL_CALLSITE_INFO.with(|local_info| {
+            let mut callsite_recorders = local_info.borrow_mut();
+            let local_info = callsite_recorders
+                .entry(callsite.clone())
+                .or_insert_with(|| {
+                    log::debug!(
+                        "***** thread-loacal CallsiteRecorder created for callsite={:?} on thread={:?}",
+                        callsite,
+                        thread::current().id()
+                    );
+
+                    let callsite_timings = self.timings.read().unwrap();
+                    let callsite_timing = callsite_timings.get(callsite).unwrap();
+
+                    LocalCallsiteTiming {
+                        total_time: callsite_timing.total_time.recorder(),
+                        active_time: callsite_timing.active_time.recorder(),
+                    }
+                });
+
+            f(local_info);
+            log::debug!(
+                "***** exiting with_local_callsite_info for callsite={:?} on thread={:?}",
+                callsite,
+                thread::current().id()
+            );
+        });
+    }
+}
+
+impl Default for Latencies {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for Latencies
+where
+    S: Subscriber,
+    S: for<'lookup> LookupSpan<'lookup>,
+{
+    fn register_callsite(&self, meta: &Metadata<'_>) -> Interest {
+        log::debug!("`register_callsite` entered");
+        if !meta.is_span() {
+            return Interest::never();
+        }
+
+        // Callsites may be registered again, e.g., when another dispatcher is created.
+        let callsite = meta.callsite();
+        let mut timings = self.timings.write().unwrap();
+        timings.entry(callsite.clone()).or_insert_with(|| {
+            let mut hist = Histogram::<u64>::new_with_bounds(1, 60 * 1000, self.sigfig).unwrap();
+            hist.auto(true);
+            CallsiteTiming {
+                total_time: hist.clone().into(),
+                active_time: hist.into(),
+            }
+        });
+        self.keys
+            .write()
+            .unwrap()
+            .entry(callsite)
+            .or_insert_with(|| SpanKey::from_metadata(meta));
+
+        log::debug!(
+            "`register_callsite` executed with meta_name={}",
+            meta.name()
+        );
+
+        Interest::always()
+    }
+
+    fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        log::debug!("entered `on_new_span`");
+        let span = ctx.span(id).unwrap();
+        let parent_callsite = span.parent().map(|parent| parent.metadata().callsite());
+
+        span.extensions_mut().insert(SpanTiming {
+            created_at: Instant::now(),
+            entered_at: Instant::now(),
+            acc_active_time: 0,
+            callsite: span.metadata().callsite(),
+            parent_callsite,
+        });
+        log::debug!("`on_new_span` executed with id={:?}", id);
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        log::debug!("entered `on_enter` wth span Id {:?}", id);
+        let span = ctx.span(id).unwrap();
+        let mut ext = span.extensions_mut();
+        let span_timing = ext.get_mut::<SpanTiming>().unwrap();
+        span_timing.entered_at = Instant::now();
+        log::debug!("`on_enter` executed with id={:?}", id);
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        log::debug!("entered `on_exit` wth span Id {:?}", id);
+        let span = ctx.span(id).unwrap();
+        let mut ext = span.extensions_mut();
+        let span_timing = ext.get_mut::<SpanTiming>().unwrap();
+        span_timing.acc_active_time += (Instant::now() - span_timing.entered_at).as_micros() as u64;
+        log::debug!("`on_exit` executed for span id {:?}", id);
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        log::debug!("entered `on_close` wth span Id {:?}", id);
+
+        let span = ctx.span(&id).unwrap();
+        let ext = span.extensions();
+        let span_timing = ext.get::<SpanTiming>().unwrap();
+        let callsite = &span_timing.callsite;
+
+        self.with_local_callsite_info(callsite, |r| {
+            r.total_time
+                .record((Instant::now() - span_timing.created_at).as_micros() as u64)
+                .unwrap();
+            r.active_time.record(span_timing.acc_active_time).unwrap();
+        });
+
+        log::debug!(
+            "`on_close` completed call to with_local_callsite_info for span id {:?}",
+            id
+        );
+
+        self.update_parent_info(callsite, &span_timing.parent_callsite);
+
+        log::debug!("`on_close` executed for span id {:?}", id);
+    }
+}
+
+//=================
+// functions
+
+/// Measures latencies of spans in `f`.
+/// May only be called once per process and will panic if called more than once.
+pub fn measure_latencies(f: impl FnOnce() + Send + 'static) -> Latencies {
+    let latencies = Latencies::new();
+    Registry::default().with(latencies.clone()).init();
+    thread::spawn(f).join().unwrap();
+    latencies.refresh();
+    latencies
+}
+
+/// Measures latencies of spans in `f` with `latencies`, which is only installed as the default subscriber of the
+/// thread that runs `f`. Unlike [`measure_latencies`], it may be called any number of times per process, but spans
+/// created on other threads, e.g., by a multi-threaded runtime, are not measured.
+pub fn measure_latencies_local<T: Send>(latencies: &Latencies, f: impl FnOnce() -> T + Send) -> T {
+    let subscriber = Registry::default().with(latencies.clone());
+    // A dedicated thread ensures that its thread-local recorders are dropped before the refresh, which would
+    // otherwise wait for them to record again.
+    let res = thread::scope(|s| {
+        s.spawn(|| tracing::subscriber::with_default(subscriber, f))
+            .join()
+            .unwrap()
+    });
+    latencies.refresh();
+    res
+}
+
+/// Measures latencies of spans in async function `f` running on the [tokio] runtime.
+/// May only be called once per process and will panic if called more than once.
+pub fn measure_latencies_tokio<F>(f: impl FnOnce() -> F + Send + 'static) -> Latencies
+where
+    F: Future<Output = ()> + Send,
+{
+    measure_latencies(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async {
+                f().await;
+            });
+    })
+}
+
+/// Formats the parents of a callsite for printing.
+fn parents_to_strings(parents: &BTreeSet<Option<SpanKey>>) -> Vec<Option<String>> {
+    parents
+        .iter()
+        .map(|p| p.as_ref().map(|p| p.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tracing::trace_span;
+
+    fn child() {
+        trace_span!("child").in_scope(|| {});
+    }
+
+    #[test]
+    fn test_measure_latencies_local() {
+        let latencies = Latencies::new();
+        let n = measure_latencies_local(&latencies, || {
+            trace_span!("a").in_scope(child);
+            trace_span!("b").in_scope(|| {
+                child();
+                child();
+            });
+            child();
+            3
+        });
+        assert_eq!(n, 3);
+
+        let report = latencies.report();
+        let by_name = |name: &str| {
+            report
+                .spans
+                .iter()
+                .find(|(k, _)| k.name == name)
+                .map(|(k, v)| (k.clone(), v))
+                .unwrap()
+        };
+        let (a, _) = by_name("a");
+        let (b, b_latencies) = by_name("b");
+        let (_, child_latencies) = by_name("child");
+        assert_eq!(b_latencies.parents, BTreeSet::from([None]));
+        assert_eq!(child_latencies.total_time.len(), 4);
+        assert_eq!(child_latencies.active_time.len(), 4);
+        assert_eq!(
+            child_latencies.parents,
+            BTreeSet::from([None, Some(a), Some(b)])
+        );
+
+        // A second measurement starts from scratch.
+        let latencies = Latencies::new();
+        measure_latencies_local(&latencies, child);
+        let report = latencies.report();
+        let count = |name: &str| {
+            report
+                .spans
+                .iter()
+                .filter(|(k, _)| k.name == name)
+                .map(|(_, v)| v.total_time.len())
+                .sum::<u64>()
+        };
+        assert_eq!(count("child"), 1);
+        assert_eq!(count("a"), 0);
+    }
+
+    #[test]
+    fn test_indistinguishable_callsites() {
+        // Both spans expand at the line of the macro invocation, so their callsites share a key.
+        macro_rules! twice {
+            () => {
+                trace_span!("twice").in_scope(child);
+                trace_span!("twice").in_scope(child);
+            };
+        }
+
+        let latencies = Latencies::new();
+        measure_latencies_local(&latencies, || {
+            twice!();
+        });
+        let report = latencies.report();
+        let (twice, latencies) = report
+            .spans
+            .iter()
+            .find(|(k, _)| k.name == "twice")
+            .unwrap();
+        assert_eq!(latencies.total_time.len(), 2);
+        let child = report
+            .spans
+            .iter()
+            .find(|(k, _)| k.name == "child")
+            .unwrap();
+        assert_eq!(child.1.parents, BTreeSet::from([Some(twice.clone())]));
+    }
+}
@@ -0,0 +1,9 @@
+//! Latency measurement of `tracing` spans, as explored in the `latency_trace*` binaries.
+
+mod layer;
+mod report;
+mod span_key;
+
+pub use layer::*;
+pub use report::*;
+pub use span_key::*;
@@ -0,0 +1,311 @@
+//! Process-independent latency report that can be exported, reloaded, and merged with reports from other runs.
+
+use super::SpanKey;
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use thiserror::Error;
+
+/// Latencies collected for a single span callsite.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpanLatencies {
+    /// Callsites of the parent spans that this callsite was observed under, with [`None`] standing for its
+    /// occurrences as a root span. A callsite reached from different places has several parents.
+    pub parents: BTreeSet<Option<SpanKey>>,
+    /// Span latencies including suspend time, from span creation to span close.
+    #[serde(with = "histogram_serde")]
+    pub total_time: Histogram<u64>,
+    /// Span latencies excluding suspend time, accumulated from span entries to exits.
+    #[serde(with = "histogram_serde")]
+    pub active_time: Histogram<u64>,
+}
+
+/// Latencies by span callsite.
+///
+/// Serializes as a list of entries because JSON only supports string map keys.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyReport {
+    #[serde(with = "span_entries")]
+    pub spans: BTreeMap<SpanKey, SpanLatencies>,
+}
+
+/// Reasons why two [`LatencyReport`]s cannot be merged. Span keys are boxed to keep the error small.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MergeError {
+    #[error(
+        "span {key}: histograms have different precisions ({left} and {right} significant digits)"
+    )]
+    Precision {
+        key: Box<SpanKey>,
+        left: u8,
+        right: u8,
+    },
+    #[error("span {key}: histogram bounds {left:?} cannot accommodate bounds {right:?}")]
+    Bounds {
+        key: Box<SpanKey>,
+        left: (u64, u64),
+        right: (u64, u64),
+    },
+}
+
+impl SpanLatencies {
+    fn check_mergeable(&self, key: &SpanKey, other: &SpanLatencies) -> Result<(), MergeError> {
+        check_histograms(key, &self.total_time, &other.total_time)?;
+        check_histograms(key, &self.active_time, &other.active_time)
+    }
+}
+
+impl LatencyReport {
+    pub fn new() -> LatencyReport {
+        Self::default()
+    }
+
+    /// Parents of each span callsite.
+    pub fn parents(&self) -> BTreeMap<&SpanKey, &BTreeSet<Option<SpanKey>>> {
+        self.spans.iter().map(|(k, v)| (k, &v.parents)).collect()
+    }
+
+    /// Adds the latencies in `other` to those of `self`, span by span, and takes the union of their parents. Spans
+    /// only present in `other` are added to `self`.
+    ///
+    /// Fails, without modifying `self`, if a span present in both reports has histograms that differ in precision
+    /// or in bounds that `self` cannot accommodate.
+    pub fn merge(&mut self, other: &LatencyReport) -> Result<(), MergeError> {
+        for (key, other_span) in &other.spans {
+            if let Some(span) = self.spans.get(key) {
+                span.check_mergeable(key, other_span)?;
+            }
+        }
+
+        for (key, other_span) in &other.spans {
+            match self.spans.get_mut(key) {
+                Some(span) => {
+                    // Compatibility was checked above.
+                    span.total_time.add(&other_span.total_time).unwrap();
+                    span.active_time.add(&other_span.active_time).unwrap();
+                    span.parents.extend(other_span.parents.iter().cloned());
+                }
+                None => {
+                    self.spans.insert(key.clone(), other_span.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Merges all of `reports` into a single report. See [`Self::merge`].
+    pub fn merge_all<'a>(
+        reports: impl IntoIterator<Item = &'a LatencyReport>,
+    ) -> Result<LatencyReport, MergeError> {
+        let mut res = LatencyReport::new();
+        for report in reports {
+            res.merge(report)?;
+        }
+        Ok(res)
+    }
+}
+
+fn check_histograms(
+    key: &SpanKey,
+    left: &Histogram<u64>,
+    right: &Histogram<u64>,
+) -> Result<(), MergeError> {
+    if left.sigfig() != right.sigfig() {
+        return Err(MergeError::Precision {
+            key: key.clone().into(),
+            left: left.sigfig(),
+            right: right.sigfig(),
+        });
+    }
+    let fits = left.low() == right.low() && (left.is_auto_resize() || left.high() >= right.high());
+    if !fits {
+        return Err(MergeError::Bounds {
+            key: key.clone().into(),
+            left: (left.low(), left.high()),
+            right: (right.low(), right.high()),
+        });
+    }
+    Ok(())
+}
+
+/// Serializes a [`Histogram`] as its configuration plus the recorded values and their counts.
+mod histogram_serde {
+    use hdrhistogram::Histogram;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+    #[derive(Serialize, Deserialize)]
+    struct HistogramRepr {
+        low: u64,
+        high: u64,
+        sigfig: u8,
+        auto_resize: bool,
+        counts: Vec<(u64, u64)>,
+    }
+
+    pub fn serialize<S: Serializer>(
+        hist: &Histogram<u64>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        HistogramRepr {
+            low: hist.low(),
+            high: hist.high(),
+            sigfig: hist.sigfig(),
+            auto_resize: hist.is_auto_resize(),
+            counts: hist
+                .iter_recorded()
+                .map(|v| (v.value_iterated_to(), v.count_at_value()))
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Histogram<u64>, D::Error> {
+        let repr = HistogramRepr::deserialize(deserializer)?;
+        let mut hist = Histogram::new_with_bounds(repr.low, repr.high, repr.sigfig)
+            .map_err(D::Error::custom)?;
+        hist.auto(repr.auto_resize);
+        for (value, count) in repr.counts {
+            hist.record_n(value, count).map_err(D::Error::custom)?;
+        }
+        Ok(hist)
+    }
+}
+
+/// Serializes a map keyed by [`SpanKey`] as a list of `{ key, value }` entries.
+mod span_entries {
+    use super::{SpanKey, SpanLatencies};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct EntryRef<'a> {
+        key: &'a SpanKey,
+        value: &'a SpanLatencies,
+    }
+
+    #[derive(Deserialize)]
+    struct Entry {
+        key: SpanKey,
+        value: SpanLatencies,
+    }
+
+    pub fn serialize<S: Serializer>(
+        spans: &BTreeMap<SpanKey, SpanLatencies>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(spans.iter().map(|(key, value)| EntryRef { key, value }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<SpanKey, SpanLatencies>, D::Error> {
+        let entries = Vec::<Entry>::deserialize(deserializer)?;
+        Ok(entries.into_iter().map(|e| (e.key, e.value)).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(name: &str) -> SpanKey {
+        SpanKey::new("tgt", Some("m"), Some("src/m.rs"), Some(1), name)
+    }
+
+    fn hist(sigfig: u8, values: &[u64]) -> Histogram<u64> {
+        let mut hist = Histogram::new_with_bounds(1, 60 * 1000, sigfig).unwrap();
+        hist.auto(true);
+        for v in values {
+            hist.record(*v).unwrap();
+        }
+        hist
+    }
+
+    fn report(entries: &[(&str, Option<&str>, &[u64])]) -> LatencyReport {
+        let spans = entries
+            .iter()
+            .map(|(name, parent, values)| {
+                let latencies = SpanLatencies {
+                    parents: BTreeSet::from([parent.map(key)]),
+                    total_time: hist(1, values),
+                    active_time: hist(1, values),
+                };
+                (key(name), latencies)
+            })
+            .collect();
+        LatencyReport { spans }
+    }
+
+    #[test]
+    fn test_merge() {
+        let r1 = report(&[("root", None, &[10, 20]), ("child", Some("root"), &[5])]);
+        let r2 = report(&[("root", None, &[30]), ("other", Some("root"), &[7, 8])]);
+
+        let merged = LatencyReport::merge_all([&r1, &r2]).unwrap();
+
+        assert_eq!(merged.spans.len(), 3);
+        assert_eq!(merged.spans[&key("root")].total_time.len(), 3);
+        assert_eq!(merged.spans[&key("child")].total_time.len(), 1);
+        assert_eq!(merged.spans[&key("other")].active_time.len(), 2);
+        assert_eq!(
+            merged.parents()[&key("other")],
+            &BTreeSet::from([Some(key("root"))])
+        );
+    }
+
+    #[test]
+    fn test_merge_parents_union() {
+        // `child` is reached from `root` in one run, and from `other` or as a root span in another.
+        let r1 = report(&[("root", None, &[10]), ("child", Some("root"), &[5])]);
+        let r2 = report(&[("other", None, &[10]), ("child", Some("other"), &[6])]);
+        let r3 = report(&[("child", None, &[7])]);
+
+        let merged = LatencyReport::merge_all([&r1, &r2, &r3]).unwrap();
+        let child = &merged.spans[&key("child")];
+        assert_eq!(
+            child.parents,
+            BTreeSet::from([None, Some(key("root")), Some(key("other"))])
+        );
+        assert_eq!(child.total_time.len(), 3);
+
+        // The result does not depend on the order in which the parents were seen.
+        assert_eq!(LatencyReport::merge_all([&r3, &r2, &r1]).unwrap(), merged);
+    }
+
+    #[test]
+    fn test_merge_errors() {
+        let r1 = report(&[("root", None, &[10]), ("child", Some("root"), &[5])]);
+
+        {
+            let mut r2 = r1.clone();
+            r2.spans.get_mut(&key("root")).unwrap().total_time = hist(2, &[10]);
+            let mut merged = r1.clone();
+            let res = merged.merge(&r2);
+            assert!(matches!(res, Err(MergeError::Precision { .. })), "{res:?}");
+            assert_eq!(merged, r1, "failed merge must not modify the report");
+        }
+
+        {
+            let mut r2 = r1.clone();
+            let mut narrow = Histogram::new_with_bounds(1, 1000, 1).unwrap();
+            narrow.auto(false);
+            r2.spans.get_mut(&key("root")).unwrap().active_time = narrow;
+            let res = r2.merge(&r1);
+            assert!(matches!(res, Err(MergeError::Bounds { .. })), "{res:?}");
+        }
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let r = report(&[
+            ("root", None, &[10, 20, 20, 59_000]),
+            ("child", Some("root"), &[5]),
+        ]);
+        let json = serde_json::to_string(&r).unwrap();
+        let r1: LatencyReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(r, r1);
+    }
+}
@@ -0,0 +1,109 @@
+//! Stable identification of span callsites.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use tracing::Metadata;
+
+/// Placeholder used when a piece of callsite metadata is not available.
+const UNKNOWN: &str = "?";
+
+/// Identifies a span callsite independently of the process in which it was observed.
+///
+/// Unlike [`tracing::callsite::Identifier`], which wraps a pointer, this key is made only of values derived from the
+/// source code, so it can be serialized and used to merge or compare latency results from different runs and
+/// binaries.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SpanKey {
+    pub target: String,
+    pub module_path: String,
+    pub file: String,
+    pub line: Option<u32>,
+    pub name: String,
+}
+
+impl SpanKey {
+    /// Creates a key from the individual callsite fields, with fallbacks for missing ones:
+    /// - a missing `module_path` falls back to `target`, which is what `tracing` uses as the default target;
+    /// - a missing `file` is recorded as `"?"`;
+    /// - a missing `line` is kept as [`None`].
+    pub fn new(
+        target: &str,
+        module_path: Option<&str>,
+        file: Option<&str>,
+        line: Option<u32>,
+        name: &str,
+    ) -> SpanKey {
+        SpanKey {
+            target: target.to_owned(),
+            module_path: module_path.unwrap_or(target).to_owned(),
+            file: file.unwrap_or(UNKNOWN).to_owned(),
+            line,
+            name: name.to_owned(),
+        }
+    }
+
+    /// Creates the key for the callsite described by `meta`.
+    pub fn from_metadata(meta: &Metadata<'_>) -> SpanKey {
+        Self::new(
+            meta.target(),
+            meta.module_path(),
+            meta.file(),
+            meta.line(),
+            meta.name(),
+        )
+    }
+}
+
+impl Display for SpanKey {
+    /// Formats as `module_path::name@file:line`, with `?` for a missing line.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}::{}@{}:", self.module_path, self.name, self.file)?;
+        match self.line {
+            Some(line) => write!(f, "{line}"),
+            None => write!(f, "{UNKNOWN}"),
+        }
+    }
+}
+
+impl From<&Metadata<'_>> for SpanKey {
+    fn from(meta: &Metadata<'_>) -> Self {
+        Self::from_metadata(meta)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fallbacks() {
+        let full = SpanKey::new("tgt", Some("a::b"), Some("src/b.rs"), Some(42), "my_span");
+        assert_eq!(full.to_string(), "a::b::my_span@src/b.rs:42");
+
+        let partial = SpanKey::new("tgt", None, None, None, "my_span");
+        assert_eq!(partial.module_path, "tgt");
+        assert_eq!(partial.file, "?");
+        assert_eq!(partial.line, None);
+        assert_eq!(partial.to_string(), "tgt::my_span@?:?");
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let key = SpanKey::new("tgt", Some("a::b"), Some("src/b.rs"), Some(42), "my_span");
+        let json = serde_json::to_string(&key).unwrap();
+        let key1: SpanKey = serde_json::from_str(&json).unwrap();
+        assert_eq!(key, key1);
+    }
+
+    #[test]
+    fn test_from_metadata() {
+        let span = tracing::info_span!("my_span");
+        // Without a subscriber the span is disabled, but its metadata is still available.
+        let meta = span.metadata().unwrap();
+        let key = SpanKey::from_metadata(meta);
+        assert_eq!(key.name, "my_span");
+        assert_eq!(key.module_path, module_path!());
+        assert_eq!(key.file, file!());
+        assert!(key.line.is_some());
+    }
+}
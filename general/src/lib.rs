@@ -1,2 +1,8 @@
+// Lets `approx_eq_derive` refer to this crate as `::general` from within it.
+extern crate self as general;
+
+pub mod bench;
 pub mod fwk;
+pub mod latency;
 pub mod polymorphic_struct_extension;
+pub mod stats;
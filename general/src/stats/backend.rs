@@ -0,0 +1,72 @@
+//! Interchangeable implementations of the distribution functions used in [`super`].
+
+use statrs::distribution::{ContinuousCDF, Normal, StudentsT};
+
+/// Source of the distribution functions needed by the hypothesis tests.
+pub trait DistBackend {
+    /// CDF of the standard Student's t distribution with `df` degrees of freedom.
+    fn students_t_cdf(x: f64, df: f64) -> f64;
+
+    /// Inverse of [`Self::students_t_cdf`].
+    fn students_t_inverse_cdf(p: f64, df: f64) -> f64;
+
+    /// CDF of the standard normal distribution.
+    fn normal_cdf(x: f64) -> f64;
+}
+
+/// [`DistBackend`] based on the [statrs] crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Statrs;
+
+/// [`DistBackend`] based on the [distrs] crate.
+///
+/// Its Student's t functions require `df >= 1` and return `NaN` otherwise. Moreover, `distrs` 0.2 computes the CDF
+/// in the tails for integer `df` up to 200 with a series whose term counter is a `u8`, which overflows, e.g., for `df`
+/// from 15 to 19 and `|x| >= 2`: it panics in debug builds and returns wrong values in release builds. The CDF falls
+/// back to [`Statrs`] wherever `distrs` would use that series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Distrs;
+
+impl DistBackend for Statrs {
+    fn students_t_cdf(x: f64, df: f64) -> f64 {
+        StudentsT::new(0.0, 1.0, df)
+            .map(|stud| stud.cdf(x))
+            .unwrap_or(f64::NAN)
+    }
+
+    fn students_t_inverse_cdf(p: f64, df: f64) -> f64 {
+        StudentsT::new(0.0, 1.0, df)
+            .map(|stud| stud.inverse_cdf(p))
+            .unwrap_or(f64::NAN)
+    }
+
+    fn normal_cdf(x: f64) -> f64 {
+        Normal::standard().cdf(x)
+    }
+}
+
+/// Whether `distrs::StudentsT::cdf(x, df)` uses its tail series, whose `u8` term counter can overflow.
+fn distrs_tail_series(x: f64, df: f64) -> bool {
+    let t = x * x;
+    df.fract() == 0.0
+        && (1.0..=200.0).contains(&df)
+        && ((df < 20.0 && t >= 4.0) || (df >= 20.0 && t >= df))
+}
+
+impl DistBackend for Distrs {
+    fn students_t_cdf(x: f64, df: f64) -> f64 {
+        if x.is_finite() && distrs_tail_series(x, df) {
+            Statrs::students_t_cdf(x, df)
+        } else {
+            distrs::StudentsT::cdf(x, df)
+        }
+    }
+
+    fn students_t_inverse_cdf(p: f64, df: f64) -> f64 {
+        distrs::StudentsT::ppf(p, df)
+    }
+
+    fn normal_cdf(x: f64) -> f64 {
+        distrs::Normal::cdf(x, 0.0, 1.0)
+    }
+}
@@ -0,0 +1,114 @@
+//! Critical values of common sampling distributions.
+
+use super::StatsError;
+use serde::Serialize;
+use statrs::distribution::{ChiSquared, ContinuousCDF, FisherSnedecor, Normal, StudentsT};
+use std::fmt::Display;
+
+/// Sampling distribution, with its degrees of freedom.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "distribution", rename_all = "snake_case")]
+pub enum SamplingDist {
+    StudentsT { df: f64 },
+    ChiSquared { df: f64 },
+    F { df1: f64, df2: f64 },
+    Normal,
+}
+
+/// Whether the significance level applies to one tail or is split between both tails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tails {
+    One,
+    Two,
+}
+
+fn invalid(e: impl Display) -> StatsError {
+    StatsError::InvalidParameters(e.to_string())
+}
+
+impl SamplingDist {
+    fn inverse_cdf(&self, p: f64) -> Result<f64, StatsError> {
+        let value = match *self {
+            SamplingDist::StudentsT { df } => StudentsT::new(0.0, 1.0, df)
+                .map_err(invalid)?
+                .inverse_cdf(p),
+            SamplingDist::ChiSquared { df } => ChiSquared::new(df).map_err(invalid)?.inverse_cdf(p),
+            SamplingDist::F { df1, df2 } => FisherSnedecor::new(df1, df2)
+                .map_err(invalid)?
+                .inverse_cdf(p),
+            SamplingDist::Normal => Normal::standard().inverse_cdf(p),
+        };
+        Ok(value)
+    }
+
+    /// Returns the critical value for significance level `alpha`, using the positive (upper-tail) sign convention:
+    /// the value `c` such that `P(X > c) = alpha` for one tail, or `P(X > c) = alpha / 2` for two tails.
+    ///
+    /// For the symmetric Student's t and normal distributions, the two-tailed lower critical value is `-c`.
+    /// For the chi-squared and F distributions, only the upper critical value is returned.
+    pub fn critical_value(&self, alpha: f64, tails: Tails) -> Result<f64, StatsError> {
+        if !(alpha > 0. && alpha < 1.) {
+            return Err(StatsError::InvalidAlpha(alpha));
+        }
+        let upper_tail = match tails {
+            Tails::One => alpha,
+            Tails::Two => alpha / 2.,
+        };
+        self.inverse_cdf(1. - upper_tail)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fwk::approx_eq::ApproxEq;
+
+    #[test]
+    fn test_critical_values() {
+        let cases = [
+            (
+                SamplingDist::StudentsT { df: 10. },
+                0.05,
+                Tails::One,
+                1.812461,
+            ),
+            (
+                SamplingDist::StudentsT { df: 10. },
+                0.05,
+                Tails::Two,
+                2.228139,
+            ),
+            (SamplingDist::Normal, 0.05, Tails::Two, 1.959964),
+            (SamplingDist::Normal, 0.01, Tails::One, 2.326348),
+            (
+                SamplingDist::ChiSquared { df: 5. },
+                0.05,
+                Tails::One,
+                11.070498,
+            ),
+            (
+                SamplingDist::F { df1: 3., df2: 10. },
+                0.05,
+                Tails::One,
+                3.708265,
+            ),
+        ];
+        for (dist, alpha, tails, expected) in cases {
+            let c = dist.critical_value(alpha, tails).unwrap();
+            assert!(
+                c.approx_eq(&expected, 1e-5),
+                "{dist:?}, {alpha}, {tails:?}: {c}"
+            );
+        }
+
+        assert_eq!(
+            SamplingDist::Normal.critical_value(1., Tails::One),
+            Err(StatsError::InvalidAlpha(1.))
+        );
+        assert!(matches!(
+            SamplingDist::ChiSquared { df: 0. }.critical_value(0.05, Tails::One),
+            Err(StatsError::InvalidParameters(_))
+        ));
+    }
+}
@@ -0,0 +1,22 @@
+use thiserror::Error;
+
+/// Errors returned by the functions in [`super`].
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum StatsError {
+    #[error("sample has {n} values but at least {required} are required")]
+    InsufficientData { n: u64, required: u64 },
+    #[error("confidence level {0} is not in the open interval (0, 1)")]
+    InvalidLevel(f64),
+    #[error("significance level {0} is not in the open interval (0, 1)")]
+    InvalidAlpha(f64),
+    #[error("quantile {0} is not in the closed interval [0, 1]")]
+    InvalidQuantile(f64),
+    #[error("sample contains a NaN value")]
+    NanValue,
+    #[error("paired samples have different lengths {left} and {right}")]
+    LengthMismatch { left: usize, right: usize },
+    #[error("sample variance is zero")]
+    ZeroVariance,
+    #[error("invalid distribution parameters: {0}")]
+    InvalidParameters(String),
+}
@@ -0,0 +1,426 @@
+//! Hypothesis tests for comparing latency samples: Student's t-tests and the Mann–Whitney U test.
+
+use super::{DistBackend, Moments, Statrs, StatsError};
+use crate::fwk::approx_eq::ApproxEq;
+use std::marker::PhantomData;
+
+/// Alternative hypothesis of a test. `Less` and `Greater` refer to the first sample (or to the sample mean in a
+/// one-sample test) relative to the second one (or to the hypothesized mean).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alternative {
+    TwoSided,
+    Less,
+    Greater,
+}
+
+/// Result of a hypothesis test.
+#[derive(Debug, Clone, Copy, PartialEq, ApproxEq)]
+pub struct TestOutcome {
+    /// Test statistic: `t` for the t-tests and `U` of the first sample for the Mann–Whitney U test.
+    pub statistic: f64,
+    /// Degrees of freedom. Infinite for the Mann–Whitney U test, whose p-value is based on the normal
+    /// approximation, i.e., the limit of Student's t distribution.
+    pub df: f64,
+    pub p_value: f64,
+    /// Significance level used for the decision.
+    #[approx_eq(exact)]
+    pub alpha: f64,
+    /// Whether the null hypothesis is rejected at significance level `alpha`.
+    #[approx_eq(exact)]
+    pub reject_null: bool,
+}
+
+/// Hypothesis tests with a given alternative hypothesis and significance level, using distribution functions
+/// from backend `B`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HypothesisTest<B: DistBackend = Statrs> {
+    alternative: Alternative,
+    alpha: f64,
+    _backend: PhantomData<B>,
+}
+
+impl HypothesisTest {
+    /// Creates tests using the default [`Statrs`] backend.
+    pub fn new(alternative: Alternative, alpha: f64) -> Result<Self, StatsError> {
+        Self::with_backend(alternative, alpha)
+    }
+}
+
+impl<B: DistBackend> HypothesisTest<B> {
+    /// Creates tests using backend `B`.
+    pub fn with_backend(alternative: Alternative, alpha: f64) -> Result<Self, StatsError> {
+        if !(alpha > 0. && alpha < 1.) {
+            return Err(StatsError::InvalidAlpha(alpha));
+        }
+        Ok(Self {
+            alternative,
+            alpha,
+            _backend: PhantomData,
+        })
+    }
+
+    fn outcome(&self, statistic: f64, df: f64, p_value: f64) -> TestOutcome {
+        TestOutcome {
+            statistic,
+            df,
+            p_value,
+            alpha: self.alpha,
+            reject_null: p_value < self.alpha,
+        }
+    }
+
+    fn t_outcome(&self, t: f64, df: f64) -> Result<TestOutcome, StatsError> {
+        if !t.is_finite() {
+            return Err(StatsError::ZeroVariance);
+        }
+        // The distribution is symmetric, so `cdf(-t)` is used instead of `1 - cdf(t)` to preserve precision.
+        let p_value = match self.alternative {
+            Alternative::TwoSided => 2. * B::students_t_cdf(-t.abs(), df),
+            Alternative::Less => B::students_t_cdf(t, df),
+            Alternative::Greater => B::students_t_cdf(-t, df),
+        };
+        Ok(self.outcome(t, df, p_value))
+    }
+
+    /// One-sample t-test of the null hypothesis that the mean of `sample` is `mu`.
+    pub fn one_sample_t(&self, sample: &[f64], mu: f64) -> Result<TestOutcome, StatsError> {
+        let m = Moments::from_sample(sample)?;
+        self.t_outcome((m.mean - mu) / m.stderr, m.df())
+    }
+
+    /// Two-sample t-test, assuming equal variances, of the null hypothesis that `a` and `b` have the same mean.
+    pub fn pooled_t(&self, a: &[f64], b: &[f64]) -> Result<TestOutcome, StatsError> {
+        let (ma, mb) = (Moments::from_sample(a)?, Moments::from_sample(b)?);
+        let (na, nb) = (ma.n as f64, mb.n as f64);
+        let df = na + nb - 2.;
+        let pooled_var = (ma.df() * ma.stdev.powi(2) + mb.df() * mb.stdev.powi(2)) / df;
+        let stderr = (pooled_var * (1. / na + 1. / nb)).sqrt();
+        self.t_outcome((ma.mean - mb.mean) / stderr, df)
+    }
+
+    /// Welch's two-sample t-test, which does not assume equal variances, of the null hypothesis that `a` and `b`
+    /// have the same mean.
+    pub fn welch_t(&self, a: &[f64], b: &[f64]) -> Result<TestOutcome, StatsError> {
+        let (ma, mb) = (Moments::from_sample(a)?, Moments::from_sample(b)?);
+        let (va, vb) = (ma.stderr.powi(2), mb.stderr.powi(2));
+        let df = (va + vb).powi(2) / (va.powi(2) / ma.df() + vb.powi(2) / mb.df());
+        self.t_outcome((ma.mean - mb.mean) / (va + vb).sqrt(), df)
+    }
+
+    /// Paired t-test of the null hypothesis that the mean of the differences `a[i] - b[i]` is zero.
+    pub fn paired_t(&self, a: &[f64], b: &[f64]) -> Result<TestOutcome, StatsError> {
+        if a.len() != b.len() {
+            return Err(StatsError::LengthMismatch {
+                left: a.len(),
+                right: b.len(),
+            });
+        }
+        let diffs = a.iter().zip(b).map(|(x, y)| x - y).collect::<Vec<_>>();
+        self.one_sample_t(&diffs, 0.)
+    }
+
+    /// Mann–Whitney U test of the null hypothesis that values from `a` and `b` are equally likely to exceed each
+    /// other. Suitable for non-normal data such as latencies. The p-value uses the normal approximation with tie
+    /// and continuity corrections, so it is only accurate for samples that are not very small.
+    pub fn mann_whitney_u(&self, a: &[f64], b: &[f64]) -> Result<TestOutcome, StatsError> {
+        let (na, nb) = (a.len(), b.len());
+        if na == 0 || nb == 0 {
+            return Err(StatsError::InsufficientData {
+                n: na.min(nb) as u64,
+                required: 1,
+            });
+        }
+        if a.iter().chain(b).any(|x| x.is_nan()) {
+            return Err(StatsError::NanValue);
+        }
+
+        let (rank_sum_a, tie_term) = rank_sum_and_ties(a, b);
+        let (na, nb) = (na as f64, nb as f64);
+        let n = na + nb;
+        let u = rank_sum_a - na * (na + 1.) / 2.;
+        let mu = na * nb / 2.;
+        let sigma = (na * nb / 12. * ((n + 1.) - tie_term / (n * (n - 1.)))).sqrt();
+        if sigma == 0. {
+            return Err(StatsError::ZeroVariance);
+        }
+
+        let p_value = match self.alternative {
+            Alternative::TwoSided => {
+                let z = ((u - mu).abs() - 0.5).max(0.) / sigma;
+                (2. * B::normal_cdf(-z)).min(1.)
+            }
+            Alternative::Less => B::normal_cdf((u - mu + 0.5) / sigma),
+            Alternative::Greater => B::normal_cdf(-(u - mu - 0.5) / sigma),
+        };
+        Ok(self.outcome(u, f64::INFINITY, p_value))
+    }
+}
+
+/// Returns the sum of the ranks of the values of `a` in the combined sample, using average ranks for ties, and the
+/// tie correction term `sum(t^3 - t)` over the sizes `t` of groups of tied values.
+fn rank_sum_and_ties(a: &[f64], b: &[f64]) -> (f64, f64) {
+    let mut combined = a
+        .iter()
+        .map(|x| (*x, true))
+        .chain(b.iter().map(|x| (*x, false)))
+        .collect::<Vec<_>>();
+    combined.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let mut rank_sum_a = 0.;
+    let mut tie_term = 0.;
+    let mut i = 0;
+    while i < combined.len() {
+        let mut j = i + 1;
+        while j < combined.len() && combined[j].0 == combined[i].0 {
+            j += 1;
+        }
+        // Values at 0-based positions i..j are tied and get the average of the 1-based ranks i+1..=j.
+        let avg_rank = (i + 1 + j) as f64 / 2.;
+        let in_a = combined[i..j].iter().filter(|(_, is_a)| *is_a).count();
+        rank_sum_a += avg_rank * in_a as f64;
+        let t = (j - i) as f64;
+        tie_term += t.powi(3) - t;
+        i = j;
+    }
+
+    (rank_sum_a, tie_term)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{fwk::approx_eq::ApproxEq, stats::Distrs};
+
+    // Welch's t-test example 1 from Wikipedia.
+    const A: [f64; 15] = [
+        27.5, 21.0, 19.0, 23.6, 17.0, 17.9, 16.9, 20.1, 21.9, 22.6, 23.1, 19.6, 19.0, 21.7, 21.4,
+    ];
+    const B: [f64; 15] = [
+        27.1, 22.0, 20.8, 23.4, 23.4, 23.5, 25.8, 22.0, 24.8, 20.2, 21.9, 22.1, 22.9, 20.5, 24.4,
+    ];
+
+    fn two_sided() -> HypothesisTest {
+        HypothesisTest::new(Alternative::TwoSided, 0.05).unwrap()
+    }
+
+    #[test]
+    fn test_one_sample_and_paired() {
+        let t = two_sided();
+        let diffs = A.iter().zip(B).map(|(a, b)| a - b).collect::<Vec<_>>();
+        let one = t.one_sample_t(&diffs, 0.).unwrap();
+        let paired = t.paired_t(&A, &B).unwrap();
+        assert_eq!(one, paired);
+        assert_eq!(paired.df, 14.);
+
+        let m = Moments::from_sample(&diffs).unwrap();
+        assert!(paired.statistic.approx_eq(&(m.mean / m.stderr), 1e-12));
+
+        assert_eq!(
+            t.paired_t(&A, &B[1..]),
+            Err(StatsError::LengthMismatch {
+                left: 15,
+                right: 14
+            })
+        );
+        assert_eq!(
+            t.one_sample_t(&[1., 1., 1.], 0.),
+            Err(StatsError::ZeroVariance)
+        );
+    }
+
+    #[test]
+    fn test_pooled_and_welch() {
+        let t = two_sided();
+
+        let pooled = t.pooled_t(&A, &B).unwrap();
+        assert!(pooled.statistic.approx_eq(&-2.46, 0.01), "{pooled:?}");
+        assert_eq!(pooled.df, 28.);
+        assert!(pooled.p_value.approx_eq(&0.021, 0.001), "{pooled:?}");
+        assert!(pooled.reject_null);
+
+        let welch = t.welch_t(&A, &B).unwrap();
+        assert!(welch.statistic.approx_eq(&-2.46, 0.01), "{welch:?}");
+        assert!(welch.df.approx_eq(&25.0, 0.05), "{welch:?}");
+        assert!(welch.p_value.approx_eq(&0.021, 0.001), "{welch:?}");
+
+        let less = HypothesisTest::new(Alternative::Less, 0.05).unwrap();
+        let greater = HypothesisTest::new(Alternative::Greater, 0.05).unwrap();
+        let p_less = less.welch_t(&A, &B).unwrap().p_value;
+        let p_greater = greater.welch_t(&A, &B).unwrap().p_value;
+        assert!(p_less.approx_eq(&(welch.p_value / 2.), 1e-12));
+        assert!((p_less + p_greater).approx_eq(&1., 1e-12));
+    }
+
+    #[test]
+    fn test_mann_whitney_u() {
+        let t = two_sided();
+        let a = [1., 2., 3., 4., 5.];
+        let b = [6., 7., 8., 9., 10.];
+
+        let res = t.mann_whitney_u(&a, &b).unwrap();
+        assert_eq!(res.statistic, 0.);
+        assert!(res.p_value.approx_eq(&0.01219, 1e-5), "{res:?}");
+        assert!(res.reject_null);
+
+        let res = t.mann_whitney_u(&b, &a).unwrap();
+        assert_eq!(res.statistic, 25.);
+
+        let ties = t
+            .mann_whitney_u(&[1., 2., 2., 3.], &[2., 3., 3., 4.])
+            .unwrap();
+        assert_eq!(ties.statistic, 3.);
+        assert!(!ties.reject_null);
+    }
+
+    #[test]
+    fn test_invalid_alpha() {
+        assert_eq!(
+            HypothesisTest::new(Alternative::TwoSided, 0.).err(),
+            Some(StatsError::InvalidAlpha(0.))
+        );
+    }
+
+    /// Cross-check of the [`Statrs`] and [`Distrs`] backends.
+    mod backends {
+        use super::*;
+
+        #[test]
+        fn test_distribution_functions_agree() {
+            for df in (1..=30).chain([50, 100, 200, 500]) {
+                let df = df as f64;
+                for x in [-6., -3., -2., -1., -0.5, 0., 0.25, 1., 2.5, 4.] {
+                    let (s, d) = (Statrs::students_t_cdf(x, df), Distrs::students_t_cdf(x, df));
+                    assert!(
+                        s.approx_eq(&d, 1e-6),
+                        "cdf({x}, {df}): statrs={s}, distrs={d}"
+                    );
+                }
+                for p in [
+                    0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.5, 0.9, 0.975,
+                ] {
+                    let (s, d) = (
+                        Statrs::students_t_inverse_cdf(p, df),
+                        Distrs::students_t_inverse_cdf(p, df),
+                    );
+                    assert!(
+                        s.approx_eq(&d, 1e-4 * s.abs().max(1.)),
+                        "inverse_cdf({p}, {df}): statrs={s}, distrs={d}"
+                    );
+                }
+            }
+
+            for x in [-4., -1.96, -1., 0., 0.5, 2.5] {
+                let (s, d) = (Statrs::normal_cdf(x), Distrs::normal_cdf(x));
+                assert!(
+                    s.approx_eq(&d, 1e-9),
+                    "normal cdf({x}): statrs={s}, distrs={d}"
+                );
+            }
+        }
+
+        /// Paired t-tests in the region where the `distrs` CDF falls back to `statrs`, see [`Distrs`]. Both backends
+        /// then compute the same p-values, so this only checks that the tests do not panic there.
+        #[test]
+        fn test_paired_t_tail_small_samples_do_not_panic() {
+            for n in 15..=20 {
+                let a = (0..n).map(|i| i as f64).collect::<Vec<_>>();
+                let b = a
+                    .iter()
+                    .enumerate()
+                    .map(|(i, x)| x - 0.5 - 0.3 * (i as f64).sin())
+                    .collect::<Vec<_>>();
+                for alternative in [Alternative::TwoSided, Alternative::Greater] {
+                    let s = HypothesisTest::<Statrs>::with_backend(alternative, 0.05).unwrap();
+                    let d = HypothesisTest::<Distrs>::with_backend(alternative, 0.05).unwrap();
+                    let (rs, rd) = (s.paired_t(&a, &b).unwrap(), d.paired_t(&a, &b).unwrap());
+                    assert!(rd.statistic.abs() >= 2., "{rd:?}");
+                    assert_eq!(rs, rd);
+
+                    let (rs, rd) = (s.paired_t(&b, &a).unwrap(), d.paired_t(&b, &a).unwrap());
+                    assert_eq!(rs, rd);
+                }
+            }
+        }
+
+        /// Both backends reproduce the two-sided 5% and 1% critical values of Student's t tables for the degrees
+        /// of freedom of the small samples above, which fall in the tail region.
+        #[test]
+        fn test_tail_reference_values() {
+            let critical = [
+                (14., 2.145, 2.977),
+                (15., 2.131, 2.947),
+                (16., 2.120, 2.921),
+                (17., 2.110, 2.898),
+                (18., 2.101, 2.878),
+                (19., 2.093, 2.861),
+            ];
+            for (df, t_05, t_01) in critical {
+                for (t, p) in [(t_05, 0.975), (t_01, 0.995)] {
+                    let (s, d) = (Statrs::students_t_cdf(t, df), Distrs::students_t_cdf(t, df));
+                    assert!(s.approx_eq(&p, 1e-4), "cdf({t}, {df}): statrs={s}");
+                    assert!(d.approx_eq(&p, 1e-4), "cdf({t}, {df}): distrs={d}");
+                }
+            }
+        }
+
+        /// Paired t-tests on small samples outside the tail region, where the `distrs` CDF is used.
+        #[test]
+        fn test_paired_t_small_samples_agree() {
+            for n in 15..=20 {
+                let a = (0..n).map(|i| i as f64).collect::<Vec<_>>();
+                let b = a
+                    .iter()
+                    .enumerate()
+                    .map(|(i, x)| x - 0.05 - 0.3 * (i as f64).sin())
+                    .collect::<Vec<_>>();
+                for alternative in [Alternative::TwoSided, Alternative::Greater] {
+                    let s = HypothesisTest::<Statrs>::with_backend(alternative, 0.05).unwrap();
+                    let d = HypothesisTest::<Distrs>::with_backend(alternative, 0.05).unwrap();
+                    let (rs, rd) = (s.paired_t(&a, &b).unwrap(), d.paired_t(&a, &b).unwrap());
+                    assert!(rd.statistic.abs() < 2., "{rd:?}");
+                    assert_eq!(rs.statistic, rd.statistic);
+                    assert!(
+                        rs.p_value.approx_eq(&rd.p_value, 1e-6),
+                        "statrs={rs:?}, distrs={rd:?}"
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn test_outcomes_agree() {
+            for alternative in [
+                Alternative::TwoSided,
+                Alternative::Less,
+                Alternative::Greater,
+            ] {
+                let s = HypothesisTest::<Statrs>::with_backend(alternative, 0.05).unwrap();
+                let d = HypothesisTest::<Distrs>::with_backend(alternative, 0.05).unwrap();
+
+                let pairs = [
+                    (
+                        s.one_sample_t(&A, 20.).unwrap(),
+                        d.one_sample_t(&A, 20.).unwrap(),
+                    ),
+                    (s.pooled_t(&A, &B).unwrap(), d.pooled_t(&A, &B).unwrap()),
+                    (s.welch_t(&A, &B).unwrap(), d.welch_t(&A, &B).unwrap()),
+                    (s.paired_t(&A, &B).unwrap(), d.paired_t(&A, &B).unwrap()),
+                    (
+                        s.mann_whitney_u(&A, &B).unwrap(),
+                        d.mann_whitney_u(&A, &B).unwrap(),
+                    ),
+                ];
+
+                for (rs, rd) in pairs {
+                    assert_eq!(rs.statistic, rd.statistic);
+                    assert_eq!(rs.df, rd.df);
+                    assert!(
+                        rs.p_value.approx_eq(&rd.p_value, 1e-4),
+                        "statrs={rs:?}, distrs={rd:?}"
+                    );
+                    assert_eq!(rs.reject_null, rd.reject_null);
+                }
+            }
+        }
+    }
+}
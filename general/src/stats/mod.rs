@@ -0,0 +1,13 @@
+//! Statistics for latency data, computed from [`hdrhistogram::Histogram`]s or from sample slices.
+
+mod backend;
+mod critical;
+mod error;
+mod hypothesis;
+mod summary;
+
+pub use backend::*;
+pub use critical::*;
+pub use error::*;
+pub use hypothesis::*;
+pub use summary::*;
@@ -0,0 +1,551 @@
+//! Descriptive statistics and confidence intervals.
+
+use super::StatsError;
+use crate::fwk::approx_eq::ApproxEq;
+use hdrhistogram::Histogram;
+use rand::{Rng, prelude::Distribution};
+use rand_distr::Binomial;
+use statrs::distribution::{ContinuousCDF, StudentsT};
+
+/// Two-sided confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq, ApproxEq)]
+pub struct ConfidenceInterval {
+    pub lower: f64,
+    pub upper: f64,
+    /// Confidence level, e.g., `0.95`.
+    #[approx_eq(exact)]
+    pub level: f64,
+}
+
+/// Sample size, mean, and dispersion of a sample.
+#[derive(Debug, Clone, Copy, PartialEq, ApproxEq)]
+pub struct Moments {
+    #[approx_eq(exact)]
+    pub n: u64,
+    pub mean: f64,
+    /// Sample standard deviation, with Bessel's correction.
+    pub stdev: f64,
+    /// Standard error of the mean.
+    pub stderr: f64,
+}
+
+/// Summary statistics of a sample.
+#[derive(Debug, Clone, PartialEq, ApproxEq)]
+pub struct Summary {
+    pub moments: Moments,
+    /// Student's t confidence interval for the mean.
+    pub mean_ci: ConfidenceInterval,
+    pub median: f64,
+    /// Bootstrap percentile confidence interval for the median.
+    pub median_ci: ConfidenceInterval,
+    /// Requested quantiles as `(q, value)` pairs.
+    pub quantiles: Vec<(f64, f64)>,
+}
+
+/// Parameters for the computation of a [`Summary`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryParams {
+    /// Confidence level of the intervals, e.g., `0.95`.
+    pub level: f64,
+    /// Number of bootstrap resamples used for the median confidence interval.
+    pub resamples: usize,
+    /// Quantiles to include in the summary.
+    pub quantiles: Vec<f64>,
+}
+
+impl Default for SummaryParams {
+    fn default() -> Self {
+        Self {
+            level: 0.95,
+            resamples: 1000,
+            quantiles: vec![0.5, 0.9, 0.99],
+        }
+    }
+}
+
+//=================
+// Validation
+
+fn check_level(level: f64) -> Result<(), StatsError> {
+    if level > 0. && level < 1. {
+        Ok(())
+    } else {
+        Err(StatsError::InvalidLevel(level))
+    }
+}
+
+fn check_quantile(q: f64) -> Result<(), StatsError> {
+    if (0. ..=1.).contains(&q) {
+        Ok(())
+    } else {
+        Err(StatsError::InvalidQuantile(q))
+    }
+}
+
+fn check_n(n: u64, required: u64) -> Result<(), StatsError> {
+    if n >= required {
+        Ok(())
+    } else {
+        Err(StatsError::InsufficientData { n, required })
+    }
+}
+
+fn check_no_nan(sample: &[f64]) -> Result<(), StatsError> {
+    if sample.iter().any(|x| x.is_nan()) {
+        Err(StatsError::NanValue)
+    } else {
+        Ok(())
+    }
+}
+
+//=================
+// Moments and mean confidence interval
+
+impl Moments {
+    fn from_sums(n: u64, mean: f64, sum_sq_dev: f64) -> Moments {
+        let stdev = (sum_sq_dev / (n - 1) as f64).sqrt();
+        Moments {
+            n,
+            mean,
+            stdev,
+            stderr: stdev / (n as f64).sqrt(),
+        }
+    }
+
+    /// Computes the moments of `sample`, which must have at least 2 values.
+    pub fn from_sample(sample: &[f64]) -> Result<Moments, StatsError> {
+        let n = sample.len() as u64;
+        check_n(n, 2)?;
+        check_no_nan(sample)?;
+        let mean = sample.iter().sum::<f64>() / n as f64;
+        let sum_sq_dev = sample.iter().map(|x| (x - mean).powi(2)).sum();
+        Ok(Self::from_sums(n, mean, sum_sq_dev))
+    }
+
+    /// Computes the moments of the values recorded in `hist`, which must have at least 2 values.
+    /// Each value is represented by the median equivalent of its bucket, as in [`Histogram::mean`].
+    pub fn from_histogram(hist: &Histogram<u64>) -> Result<Moments, StatsError> {
+        let n = hist.len();
+        check_n(n, 2)?;
+        let mean = hist.mean();
+        let sum_sq_dev = hist
+            .iter_recorded()
+            .map(|v| {
+                let x = hist.median_equivalent(v.value_iterated_to()) as f64;
+                (x - mean).powi(2) * v.count_at_value() as f64
+            })
+            .sum();
+        Ok(Self::from_sums(n, mean, sum_sq_dev))
+    }
+
+    /// Degrees of freedom of the Student's t distribution of the mean.
+    pub fn df(&self) -> f64 {
+        (self.n - 1) as f64
+    }
+
+    /// Student's t confidence interval for the mean at confidence `level`.
+    pub fn mean_ci(&self, level: f64) -> Result<ConfidenceInterval, StatsError> {
+        check_level(level)?;
+        let stud = StudentsT::new(0.0, 1.0, self.df()).expect("df is positive");
+        let t = stud.inverse_cdf(1. - (1. - level) / 2.);
+        let half_width = t * self.stderr;
+        Ok(ConfidenceInterval {
+            lower: self.mean - half_width,
+            upper: self.mean + half_width,
+            level,
+        })
+    }
+}
+
+//=================
+// Quantiles
+
+/// Quantile `q` of an already sorted, non-empty sample, with linear interpolation between closest ranks.
+fn sorted_quantile(sorted: &[f64], q: f64) -> f64 {
+    let h = (sorted.len() - 1) as f64 * q;
+    let lo = h.floor() as usize;
+    let hi = h.ceil() as usize;
+    sorted[lo] + (h - lo as f64) * (sorted[hi] - sorted[lo])
+}
+
+fn sorted_copy(sample: &[f64]) -> Result<Vec<f64>, StatsError> {
+    check_n(sample.len() as u64, 1)?;
+    check_no_nan(sample)?;
+    let mut sorted = sample.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    Ok(sorted)
+}
+
+/// Returns the quantiles `qs` of `sample`, using linear interpolation between closest ranks.
+pub fn quantiles(sample: &[f64], qs: &[f64]) -> Result<Vec<f64>, StatsError> {
+    qs.iter().try_for_each(|q| check_quantile(*q))?;
+    let sorted = sorted_copy(sample)?;
+    Ok(qs.iter().map(|q| sorted_quantile(&sorted, *q)).collect())
+}
+
+/// Same as [`quantiles`] for a `sorted` sample, which must be in ascending order, without copying or sorting it.
+pub fn sorted_quantiles(sorted: &[f64], qs: &[f64]) -> Result<Vec<f64>, StatsError> {
+    qs.iter().try_for_each(|q| check_quantile(*q))?;
+    check_n(sorted.len() as u64, 1)?;
+    check_no_nan(sorted)?;
+    debug_assert!(sorted.is_sorted(), "sample must be in ascending order");
+    Ok(qs.iter().map(|q| sorted_quantile(sorted, *q)).collect())
+}
+
+/// Returns quantile `q` of `sample`. See [`quantiles`].
+pub fn quantile(sample: &[f64], q: f64) -> Result<f64, StatsError> {
+    Ok(quantiles(sample, &[q])?[0])
+}
+
+/// Returns the quantiles `qs` of the values recorded in `hist`, using linear interpolation between closest ranks as
+/// [`quantiles`] does. Each value is represented by the median equivalent of its bucket, as in
+/// [`Moments::from_histogram`].
+pub fn histogram_quantiles(hist: &Histogram<u64>, qs: &[f64]) -> Result<Vec<f64>, StatsError> {
+    qs.iter().try_for_each(|q| check_quantile(*q))?;
+    check_n(hist.len(), 1)?;
+    let (values, counts) = histogram_buckets(hist);
+    Ok(qs
+        .iter()
+        .map(|q| counts_quantile(&values, &counts, hist.len(), *q))
+        .collect())
+}
+
+/// Returns the median of the values recorded in `hist`, i.e., its quantile 0.5 as computed by
+/// [`histogram_quantiles`], which is the mean of its two middle values if their number is even.
+pub fn histogram_median(hist: &Histogram<u64>) -> Result<f64, StatsError> {
+    Ok(histogram_quantiles(hist, &[0.5])?[0])
+}
+
+/// Ascending median equivalents of the recorded buckets of `hist`, with their counts.
+fn histogram_buckets(hist: &Histogram<u64>) -> (Vec<f64>, Vec<u64>) {
+    hist.iter_recorded()
+        .map(|v| {
+            let value = hist.median_equivalent(v.value_iterated_to()) as f64;
+            (value, v.count_at_value())
+        })
+        .unzip()
+}
+
+//=================
+// Median bootstrap confidence interval
+
+fn percentile_ci(mut stats: Vec<f64>, level: f64) -> ConfidenceInterval {
+    stats.sort_by(f64::total_cmp);
+    let alpha = 1. - level;
+    ConfidenceInterval {
+        lower: sorted_quantile(&stats, alpha / 2.),
+        upper: sorted_quantile(&stats, 1. - alpha / 2.),
+        level,
+    }
+}
+
+/// Bootstrap percentile confidence interval for the median of `sample`, based on `resamples` resamples drawn
+/// with `rng`.
+pub fn median_ci(
+    sample: &[f64],
+    level: f64,
+    resamples: usize,
+    rng: &mut impl Rng,
+) -> Result<ConfidenceInterval, StatsError> {
+    let sorted = sorted_copy(sample)?;
+    sorted_median_ci(&sorted, level, resamples, rng)
+}
+
+/// Same as [`median_ci`] for an already sorted, non-empty sample.
+fn sorted_median_ci(
+    sorted: &[f64],
+    level: f64,
+    resamples: usize,
+    rng: &mut impl Rng,
+) -> Result<ConfidenceInterval, StatsError> {
+    check_level(level)?;
+    check_n(resamples as u64, 1)?;
+    let n = sorted.len();
+
+    // Resampling indices of the sorted sample and sorting them yields the sorted resample.
+    let mut idxs = vec![0; n];
+    let medians = (0..resamples)
+        .map(|_| {
+            idxs.iter_mut().for_each(|i| *i = rng.gen_range(0..n));
+            idxs.sort_unstable();
+            let (lo, hi) = ((n - 1) / 2, n / 2);
+            (sorted[idxs[lo]] + sorted[idxs[hi]]) / 2.
+        })
+        .collect();
+
+    Ok(percentile_ci(medians, level))
+}
+
+/// Bootstrap percentile confidence interval for the median of the values recorded in `hist`, as computed by
+/// [`histogram_median`], based on `resamples` resamples drawn with `rng`.
+///
+/// Each resample draws the counts of the buckets from a multinomial distribution, as a sequence of binomial draws,
+/// so its cost depends on the number of buckets rather than on the number of recorded values.
+pub fn histogram_median_ci(
+    hist: &Histogram<u64>,
+    level: f64,
+    resamples: usize,
+    rng: &mut impl Rng,
+) -> Result<ConfidenceInterval, StatsError> {
+    check_level(level)?;
+    check_n(resamples as u64, 1)?;
+    let n = hist.len();
+    check_n(n, 1)?;
+
+    let (values, weights) = histogram_buckets(hist);
+    let mut counts = vec![0_u64; values.len()];
+    let medians = (0..resamples)
+        .map(|_| {
+            resample_counts(&weights, n, &mut counts, rng);
+            counts_quantile(&values, &counts, n, 0.5)
+        })
+        .collect();
+
+    Ok(percentile_ci(medians, level))
+}
+
+/// Draws into `counts` the multinomial counts of `n` values among buckets with probabilities proportional to
+/// `weights`, whose sum must be `n`. The count of each bucket is binomial given the counts of the previous ones.
+fn resample_counts(weights: &[u64], n: u64, counts: &mut [u64], rng: &mut impl Rng) {
+    let (mut remaining_n, mut remaining_weight) = (n, n);
+    for (count, weight) in counts.iter_mut().zip(weights) {
+        *count = if remaining_n == 0 || *weight == remaining_weight {
+            remaining_n
+        } else {
+            let p = *weight as f64 / remaining_weight as f64;
+            Binomial::new(remaining_n, p)
+                .expect("probability is in [0, 1]")
+                .sample(rng)
+        };
+        remaining_n -= *count;
+        remaining_weight -= weight;
+    }
+}
+
+/// Quantile `q` of `n` values given ascending `values` with the corresponding `counts`, which must sum to `n`, with
+/// the same interpolation as [`sorted_quantile`].
+fn counts_quantile(values: &[f64], counts: &[u64], n: u64, q: f64) -> f64 {
+    let h = (n - 1) as f64 * q;
+    let lo = ranked_value(values, counts, h.floor() as u64);
+    let hi = ranked_value(values, counts, h.ceil() as u64);
+    lo + h.fract() * (hi - lo)
+}
+
+/// Value with 0-based `rank` given ascending `values` with the corresponding `counts`.
+fn ranked_value(values: &[f64], counts: &[u64], rank: u64) -> f64 {
+    let mut acc = 0;
+    for (value, count) in values.iter().zip(counts) {
+        acc += count;
+        if acc > rank {
+            return *value;
+        }
+    }
+    unreachable!("rank must be less than the total count")
+}
+
+//=================
+// Summary
+
+impl Summary {
+    /// Computes the summary statistics of `sample`, using `rng` for bootstrapping.
+    pub fn from_sample(
+        sample: &[f64],
+        params: &SummaryParams,
+        rng: &mut impl Rng,
+    ) -> Result<Summary, StatsError> {
+        check_n(sample.len() as u64, 2)?;
+        Self::from_sorted_sample(&sorted_copy(sample)?, params, rng)
+    }
+
+    /// Same as [`Self::from_sample`] for a `sorted` sample, which must be in ascending order, without copying or
+    /// sorting it.
+    pub fn from_sorted_sample(
+        sorted: &[f64],
+        params: &SummaryParams,
+        rng: &mut impl Rng,
+    ) -> Result<Summary, StatsError> {
+        let moments = Moments::from_sample(sorted)?;
+        let quantile_values = sorted_quantiles(sorted, &params.quantiles)?;
+        Ok(Summary {
+            moments,
+            mean_ci: moments.mean_ci(params.level)?,
+            median: sorted_quantile(sorted, 0.5),
+            median_ci: sorted_median_ci(sorted, params.level, params.resamples, rng)?,
+            quantiles: params
+                .quantiles
+                .iter()
+                .copied()
+                .zip(quantile_values)
+                .collect(),
+        })
+    }
+
+    /// Computes the summary statistics of the values recorded in `hist`, using `rng` for bootstrapping. All the
+    /// statistics represent each value by the median equivalent of its bucket.
+    pub fn from_histogram(
+        hist: &Histogram<u64>,
+        params: &SummaryParams,
+        rng: &mut impl Rng,
+    ) -> Result<Summary, StatsError> {
+        let moments = Moments::from_histogram(hist)?;
+        let quantile_values = histogram_quantiles(hist, &params.quantiles)?;
+        Ok(Summary {
+            moments,
+            mean_ci: moments.mean_ci(params.level)?,
+            median: histogram_median(hist)?,
+            median_ci: histogram_median_ci(hist, params.level, params.resamples, rng)?,
+            quantiles: params
+                .quantiles
+                .iter()
+                .copied()
+                .zip(quantile_values)
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_approx_eq;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    const SAMPLE: [f64; 8] = [2., 4., 4., 4., 5., 5., 7., 9.];
+
+    #[test]
+    fn test_moments_and_mean_ci() {
+        let m = Moments::from_sample(&SAMPLE).unwrap();
+        assert_eq!(m.n, 8);
+        assert!(m.mean.approx_eq(&5., 1e-12));
+        assert!(m.stdev.approx_eq(&(32_f64 / 7.).sqrt(), 1e-12));
+        assert!(m.stderr.approx_eq(&(m.stdev / 8_f64.sqrt()), 1e-12));
+
+        // t(0.975, 7) = 2.364624
+        let ci = m.mean_ci(0.95).unwrap();
+        let half_width = 2.364624 * m.stderr;
+        assert!(ci.lower.approx_eq(&(5. - half_width), 1e-5), "{ci:?}");
+        assert!(ci.upper.approx_eq(&(5. + half_width), 1e-5), "{ci:?}");
+
+        assert_eq!(m.mean_ci(1.), Err(StatsError::InvalidLevel(1.)));
+        assert_eq!(
+            Moments::from_sample(&[1.]),
+            Err(StatsError::InsufficientData { n: 1, required: 2 })
+        );
+        assert_eq!(
+            Moments::from_sample(&[1., f64::NAN]),
+            Err(StatsError::NanValue)
+        );
+    }
+
+    #[test]
+    fn test_histogram_moments() {
+        let mut hist = Histogram::<u64>::new_with_bounds(1, 1000, 3).unwrap();
+        for x in SAMPLE {
+            hist.record(x as u64).unwrap();
+        }
+        let m = Moments::from_histogram(&hist).unwrap();
+        let expected = Moments::from_sample(&SAMPLE).unwrap();
+        assert_approx_eq!(m, expected, 1e-12);
+        assert!(!m.approx_eq(&Moments { n: 9, ..expected }, 1.));
+    }
+
+    #[test]
+    fn test_quantiles() {
+        let qs = quantiles(&SAMPLE, &[0., 0.25, 0.5, 1.]).unwrap();
+        assert_eq!(qs, vec![2., 4., 4.5, 9.]);
+        assert_eq!(sorted_quantiles(&SAMPLE, &[0., 0.25, 0.5, 1.]), Ok(qs));
+        assert_eq!(
+            quantile(&SAMPLE, 1.5),
+            Err(StatsError::InvalidQuantile(1.5))
+        );
+        assert_eq!(
+            quantile(&[], 0.5),
+            Err(StatsError::InsufficientData { n: 0, required: 1 })
+        );
+    }
+
+    #[test]
+    fn test_median_ci() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let sample = (1..=101).map(|x| x as f64).collect::<Vec<_>>();
+        let ci = median_ci(&sample, 0.95, 500, &mut rng).unwrap();
+        assert!(ci.lower < 51. && 51. < ci.upper, "{ci:?}");
+        assert!(ci.lower > 35. && ci.upper < 67., "{ci:?}");
+
+        let mut hist = Histogram::<u64>::new_with_bounds(1, 1000, 3).unwrap();
+        for x in &sample {
+            hist.record(*x as u64).unwrap();
+        }
+        let hist_ci = histogram_median_ci(&hist, 0.95, 500, &mut rng).unwrap();
+        assert!(hist_ci.lower < 51. && 51. < hist_ci.upper, "{hist_ci:?}");
+        assert!(hist_ci.lower > 35. && hist_ci.upper < 67., "{hist_ci:?}");
+
+        // Resampling costs do not depend on the number of recorded values: with 10^5 times each value, the
+        // interval shrinks around the median.
+        let mut large = Histogram::<u64>::new_with_bounds(1, 1000, 3).unwrap();
+        for x in &sample {
+            large.record_n(*x as u64, 100_000).unwrap();
+        }
+        let large_ci = histogram_median_ci(&large, 0.95, 500, &mut rng).unwrap();
+        assert!(
+            large_ci.lower >= 50. && large_ci.upper <= 52.,
+            "{large_ci:?}"
+        );
+    }
+
+    #[test]
+    fn test_resample_counts() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let weights = [5, 0, 30, 15, 50];
+        let mut counts = [0; 5];
+        let mut totals = [0; 5];
+        for _ in 0..1000 {
+            resample_counts(&weights, 100, &mut counts, &mut rng);
+            assert_eq!(counts.iter().sum::<u64>(), 100);
+            assert_eq!(counts[1], 0);
+            totals.iter_mut().zip(counts).for_each(|(t, c)| *t += c);
+        }
+        for (total, weight) in totals.iter().zip(weights) {
+            let mean = *total as f64 / 1000.;
+            assert!((mean - weight as f64).abs() < 1., "{totals:?}");
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let params = SummaryParams::default();
+        let summary = Summary::from_sample(&SAMPLE, &params, &mut rng).unwrap();
+        assert_eq!(summary.median, 4.5);
+        assert_eq!(summary.quantiles.len(), 3);
+        assert!(summary.mean_ci.lower < 5. && 5. < summary.mean_ci.upper);
+        assert!(summary.median_ci.lower <= 4.5 && 4.5 <= summary.median_ci.upper);
+
+        // `SAMPLE` is already sorted, so skipping the sort yields the same summary.
+        let mut rng = StdRng::seed_from_u64(42);
+        let sorted_summary = Summary::from_sorted_sample(&SAMPLE, &params, &mut rng).unwrap();
+        assert_eq!(sorted_summary, summary);
+
+        // With exact buckets, the histogram summary agrees with the sample one.
+        let mut hist = Histogram::<u64>::new_with_bounds(1, 1000, 3).unwrap();
+        SAMPLE.iter().for_each(|x| hist.record(*x as u64).unwrap());
+        let hist_summary = Summary::from_histogram(&hist, &params, &mut rng).unwrap();
+        assert_eq!(hist_summary.median, 4.5);
+        // The histogram quantiles interpolate between ranks as the sample ones do, so p50 is the median.
+        let qs = [0., 0.25, 0.5, 0.9, 1.];
+        assert_eq!(histogram_quantiles(&hist, &qs), quantiles(&SAMPLE, &qs));
+        assert_eq!(
+            histogram_quantiles(&hist, &[0.5]),
+            Ok(vec![hist_summary.median])
+        );
+        assert_approx_eq!(hist_summary.moments, summary.moments, 1e-12);
+
+        // With coarse buckets, the median and the moments use the same representative value.
+        let mut coarse = Histogram::<u64>::new_with_bounds(1, 100_000, 1).unwrap();
+        coarse.record_n(1001, 3).unwrap();
+        let coarse_summary = Summary::from_histogram(&coarse, &params, &mut rng).unwrap();
+        assert_eq!(coarse_summary.median, coarse.mean());
+        assert_eq!(coarse_summary.median, coarse_summary.moments.mean);
+        assert_eq!(coarse_summary.quantiles[0], (0.5, coarse.mean()));
+    }
+}
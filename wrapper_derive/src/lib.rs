@@ -0,0 +1,548 @@
+//! Provides `#[derive(Wrapper)]`, which implements delegations to the single field of a newtype, like those of
+//! `general::fwk::wrapper::Wrapper`, for any struct with one field.
+//!
+//! The delegations are picked with `#[wrapper(...)]` on the struct, from:
+//! - `new`: inherent `new(value)`, `value(&self)` and `into_inner(self)` methods;
+//! - `deref`, `deref_mut`, `as_ref`, `as_mut`, `borrow`, `borrow_mut`, `from`, `debug`, `display`, `from_str`;
+//! - `into_iterator`: [`IntoIterator`] for the newtype and for shared and mutable references to it;
+//! - `add`, `sub`, `mul`, `div`, `rem`, `neg`, and `add_assign`, `sub_assign`, `mul_assign`, `div_assign`,
+//!   `rem_assign`, which operate on the fields of newtypes of the same type;
+//! - `index`, `index_mut`: indexing with any index type supported by the field;
+//! - the groups `default` (`new`, `deref`, `deref_mut`, `as_ref`, `borrow`, `from` and `debug`, as implemented by
+//!   `Wrapper`) and `ops` (all arithmetic operators).
+//!
+//! Without the attribute, the `default` group is implemented. Unlike `Wrapper`, it leaves out `into_iterator`,
+//! which most fields do not support. `deref_mut`, `borrow_mut` and `index_mut` also implement `deref`, `borrow` and
+//! `index`, respectively, which their traits require. Each delegation is bounded by the field type implementing the
+//! delegated trait, so picking one that the field does not support fails to compile.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    Data, DeriveInput, Error, Fields, GenericParam, Generics, Ident, Member, Result, Type,
+    WherePredicate, parse_macro_input, parse_quote,
+};
+
+#[proc_macro_derive(Wrapper, attributes(wrapper))]
+pub fn derive_wrapper(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+const DEFAULT: &[&str] = &[
+    "new",
+    "deref",
+    "deref_mut",
+    "as_ref",
+    "borrow",
+    "from",
+    "debug",
+];
+
+const OPS: &[&str] = &[
+    "add",
+    "sub",
+    "mul",
+    "div",
+    "rem",
+    "neg",
+    "add_assign",
+    "sub_assign",
+    "mul_assign",
+    "div_assign",
+    "rem_assign",
+];
+
+const OTHERS: &[&str] = &[
+    "as_mut",
+    "borrow_mut",
+    "display",
+    "from_str",
+    "index",
+    "index_mut",
+    "into_iterator",
+];
+
+/// Delegations implied by other ones, whose traits are supertraits of the latter's.
+const REQUIRED: &[(&str, &str)] = &[
+    ("deref_mut", "deref"),
+    ("borrow_mut", "borrow"),
+    ("index_mut", "index"),
+];
+
+/// Names of the delegations picked by the `wrapper` attributes and those they imply, without duplicates.
+fn delegations(input: &DeriveInput) -> Result<Vec<&'static str>> {
+    let mut names = Vec::new();
+    let mut pick = |group: &[&'static str]| {
+        for name in group {
+            if !names.contains(name) {
+                names.push(*name);
+            }
+        }
+    };
+
+    let attrs = input
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("wrapper"))
+        .collect::<Vec<_>>();
+    if attrs.is_empty() {
+        pick(DEFAULT);
+    }
+    for attr in attrs {
+        attr.parse_nested_meta(|meta| {
+            let path = &meta.path;
+            let name = quote!(#path).to_string().replace(' ', "");
+            match name.as_str() {
+                "default" => pick(DEFAULT),
+                "ops" => pick(OPS),
+                _ => match [DEFAULT, OPS, OTHERS]
+                    .concat()
+                    .into_iter()
+                    .find(|n| *n == name)
+                {
+                    Some(n) => pick(&[n]),
+                    None => return Err(meta.error(format!("unknown delegation `{name}`"))),
+                },
+            }
+            Ok(())
+        })?;
+    }
+    for (name, required) in REQUIRED {
+        if names.contains(name) && !names.contains(required) {
+            names.push(required);
+        }
+    }
+    Ok(names)
+}
+
+/// The newtype being derived, with its single field.
+struct Newtype {
+    name: Ident,
+    generics: Generics,
+    member: Member,
+    inner: Type,
+}
+
+impl Newtype {
+    /// Expression constructing the newtype from `value`.
+    fn construct(&self, value: TokenStream) -> TokenStream {
+        match &self.member {
+            Member::Named(field) => quote!(Self { #field: #value }),
+            Member::Unnamed(_) => quote!(Self(#value)),
+        }
+    }
+
+    /// Impl of `trait_` with `body`, with the extra generic parameters `params` and where predicates `preds`.
+    fn impl_with(
+        &self,
+        params: TokenStream,
+        trait_: TokenStream,
+        self_ty: Option<TokenStream>,
+        preds: &[WherePredicate],
+        body: TokenStream,
+    ) -> TokenStream {
+        let mut generics = self.generics.clone();
+        if !params.is_empty() {
+            let extra: Generics = parse_quote!(<#params>);
+            for param in extra.params {
+                // Lifetimes must precede the other parameters.
+                match param {
+                    GenericParam::Lifetime(_) => generics.params.insert(0, param),
+                    _ => generics.params.push(param),
+                }
+            }
+        }
+        generics
+            .make_where_clause()
+            .predicates
+            .extend(preds.iter().cloned());
+
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let (_, ty_generics, _) = self.generics.split_for_impl();
+        let name = &self.name;
+        let self_ty = self_ty.unwrap_or_else(|| quote!(#name #ty_generics));
+        quote! {
+            impl #impl_generics #trait_ for #self_ty #where_clause {
+                #body
+            }
+        }
+    }
+
+    fn impl_trait(
+        &self,
+        trait_: TokenStream,
+        preds: &[WherePredicate],
+        body: TokenStream,
+    ) -> TokenStream {
+        self.impl_with(quote!(), trait_, None, preds, body)
+    }
+
+    fn delegation(&self, name: &str) -> TokenStream {
+        let (m, inner) = (&self.member, &self.inner);
+        let (_, ty_generics, _) = self.generics.split_for_impl();
+        let self_name = &self.name;
+        let construct = self.construct(quote!(value));
+        let none: &[WherePredicate] = &[];
+
+        match name {
+            "new" => {
+                let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+                quote! {
+                    impl #impl_generics #self_name #ty_generics #where_clause {
+                        /// Wraps `value`.
+                        pub fn new(value: #inner) -> Self {
+                            #construct
+                        }
+
+                        /// The wrapped value.
+                        pub fn value(&self) -> &#inner {
+                            &self.#m
+                        }
+
+                        /// Unwraps the wrapped value.
+                        pub fn into_inner(self) -> #inner {
+                            self.#m
+                        }
+                    }
+                }
+            }
+            "deref" => self.impl_trait(
+                quote!(::core::ops::Deref),
+                none,
+                quote! {
+                    type Target = #inner;
+                    fn deref(&self) -> &#inner {
+                        &self.#m
+                    }
+                },
+            ),
+            "deref_mut" => self.impl_trait(
+                quote!(::core::ops::DerefMut),
+                none,
+                quote! {
+                    fn deref_mut(&mut self) -> &mut #inner {
+                        &mut self.#m
+                    }
+                },
+            ),
+            "as_ref" => self.impl_trait(
+                quote!(::core::convert::AsRef<#inner>),
+                none,
+                quote! {
+                    fn as_ref(&self) -> &#inner {
+                        &self.#m
+                    }
+                },
+            ),
+            "as_mut" => self.impl_trait(
+                quote!(::core::convert::AsMut<#inner>),
+                none,
+                quote! {
+                    fn as_mut(&mut self) -> &mut #inner {
+                        &mut self.#m
+                    }
+                },
+            ),
+            "borrow" => self.impl_trait(
+                quote!(::core::borrow::Borrow<#inner>),
+                none,
+                quote! {
+                    fn borrow(&self) -> &#inner {
+                        &self.#m
+                    }
+                },
+            ),
+            "borrow_mut" => self.impl_trait(
+                quote!(::core::borrow::BorrowMut<#inner>),
+                none,
+                quote! {
+                    fn borrow_mut(&mut self) -> &mut #inner {
+                        &mut self.#m
+                    }
+                },
+            ),
+            "from" => self.impl_trait(
+                quote!(::core::convert::From<#inner>),
+                none,
+                quote! {
+                    fn from(value: #inner) -> Self {
+                        #construct
+                    }
+                },
+            ),
+            "debug" | "display" => {
+                let trait_ = match name {
+                    "debug" => quote!(::core::fmt::Debug),
+                    _ => quote!(::core::fmt::Display),
+                };
+                self.impl_trait(
+                    trait_.clone(),
+                    &[parse_quote!(#inner: #trait_)],
+                    quote! {
+                        fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                            <#inner as #trait_>::fmt(&self.#m, f)
+                        }
+                    },
+                )
+            }
+            "from_str" => self.impl_trait(
+                quote!(::core::str::FromStr),
+                &[parse_quote!(#inner: ::core::str::FromStr)],
+                quote! {
+                    type Err = <#inner as ::core::str::FromStr>::Err;
+                    fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> {
+                        <#inner as ::core::str::FromStr>::from_str(s).map(|value| #construct)
+                    }
+                },
+            ),
+            "into_iterator" => {
+                let owned = self.impl_trait(
+                    quote!(::core::iter::IntoIterator),
+                    &[parse_quote!(#inner: ::core::iter::IntoIterator)],
+                    quote! {
+                        type Item = <#inner as ::core::iter::IntoIterator>::Item;
+                        type IntoIter = <#inner as ::core::iter::IntoIterator>::IntoIter;
+                        fn into_iter(self) -> Self::IntoIter {
+                            self.#m.into_iter()
+                        }
+                    },
+                );
+                let by_ref = |mutability: TokenStream| {
+                    self.impl_with(
+                        quote!('__a),
+                        quote!(::core::iter::IntoIterator),
+                        Some(quote!(&'__a #mutability #self_name #ty_generics)),
+                        &[parse_quote!(&'__a #mutability #inner: ::core::iter::IntoIterator)],
+                        quote! {
+                            type Item = <&'__a #mutability #inner as ::core::iter::IntoIterator>::Item;
+                            type IntoIter = <&'__a #mutability #inner as ::core::iter::IntoIterator>::IntoIter;
+                            fn into_iter(self) -> Self::IntoIter {
+                                (&#mutability self.#m).into_iter()
+                            }
+                        },
+                    )
+                };
+                let (shared, mutable) = (by_ref(quote!()), by_ref(quote!(mut)));
+                quote!(#owned #shared #mutable)
+            }
+            "add" | "sub" | "mul" | "div" | "rem" => {
+                let trait_ident = format_ident!("{}", capitalize(name));
+                let method = format_ident!("{name}");
+                let construct =
+                    self.construct(quote!(::core::ops::#trait_ident::#method(self.#m, rhs.#m)));
+                self.impl_trait(
+                    quote!(::core::ops::#trait_ident),
+                    &[parse_quote!(#inner: ::core::ops::#trait_ident<Output = #inner>)],
+                    quote! {
+                        type Output = Self;
+                        fn #method(self, rhs: Self) -> Self {
+                            #construct
+                        }
+                    },
+                )
+            }
+            "neg" => {
+                let construct = self.construct(quote!(::core::ops::Neg::neg(self.#m)));
+                self.impl_trait(
+                    quote!(::core::ops::Neg),
+                    &[parse_quote!(#inner: ::core::ops::Neg<Output = #inner>)],
+                    quote! {
+                        type Output = Self;
+                        fn neg(self) -> Self {
+                            #construct
+                        }
+                    },
+                )
+            }
+            "add_assign" | "sub_assign" | "mul_assign" | "div_assign" | "rem_assign" => {
+                let op = name.trim_end_matches("_assign");
+                let trait_ident = format_ident!("{}Assign", capitalize(op));
+                let method = format_ident!("{name}");
+                self.impl_trait(
+                    quote!(::core::ops::#trait_ident),
+                    &[parse_quote!(#inner: ::core::ops::#trait_ident)],
+                    quote! {
+                        fn #method(&mut self, rhs: Self) {
+                            ::core::ops::#trait_ident::#method(&mut self.#m, rhs.#m);
+                        }
+                    },
+                )
+            }
+            "index" => self.impl_with(
+                quote!(__Idx),
+                quote!(::core::ops::Index<__Idx>),
+                None,
+                &[parse_quote!(#inner: ::core::ops::Index<__Idx>)],
+                quote! {
+                    type Output = <#inner as ::core::ops::Index<__Idx>>::Output;
+                    fn index(&self, index: __Idx) -> &Self::Output {
+                        &self.#m[index]
+                    }
+                },
+            ),
+            "index_mut" => self.impl_with(
+                quote!(__Idx),
+                quote!(::core::ops::IndexMut<__Idx>),
+                None,
+                &[parse_quote!(#inner: ::core::ops::IndexMut<__Idx>)],
+                quote! {
+                    fn index_mut(&mut self, index: __Idx) -> &mut Self::Output {
+                        &mut self.#m[index]
+                    }
+                },
+            ),
+            _ => unreachable!("delegation names are validated when parsed"),
+        }
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map_or_else(String::new, |c| {
+        c.to_ascii_uppercase().to_string() + chars.as_str()
+    })
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            Span::call_site(),
+            "`Wrapper` can only be derived for structs",
+        ));
+    };
+    let field = match &data.fields {
+        Fields::Named(fields) if fields.named.len() == 1 => &fields.named[0],
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0],
+        _ => {
+            return Err(Error::new(
+                Span::call_site(),
+                "`Wrapper` can only be derived for structs with exactly one field",
+            ));
+        }
+    };
+
+    let newtype = Newtype {
+        name: input.ident.clone(),
+        generics: input.generics.clone(),
+        member: match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(0.into()),
+        },
+        inner: field.ty.clone(),
+    };
+    let impls = delegations(&input)?
+        .iter()
+        .map(|name| newtype.delegation(name))
+        .collect::<TokenStream>();
+    Ok(impls)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn error_of<T>(res: Result<T>) -> String {
+        res.err().expect("expected an error").to_string()
+    }
+
+    #[test]
+    fn test_delegations() {
+        let input: DeriveInput = parse_quote!(
+            struct S(f64);
+        );
+        assert_eq!(delegations(&input).unwrap(), DEFAULT);
+
+        // Groups and single delegations are merged without duplicates, in the order they are picked.
+        let input: DeriveInput = parse_quote!(
+            #[wrapper(debug, default)]
+            #[wrapper(neg, ops)]
+            struct S(f64);
+        );
+        let names = delegations(&input).unwrap();
+        assert_eq!(names[..2], ["debug", "new"]);
+        assert_eq!(names.len(), DEFAULT.len() + OPS.len());
+
+        // `into_iterator` must be picked explicitly.
+        let input: DeriveInput = parse_quote!(
+            #[wrapper(default, into_iterator)]
+            struct S(Vec<u8>);
+        );
+        assert_eq!(delegations(&input).unwrap().last(), Some(&"into_iterator"));
+    }
+
+    #[test]
+    fn test_delegations_implied() {
+        let input: DeriveInput = parse_quote!(
+            #[wrapper(deref_mut, borrow_mut, index_mut)]
+            struct S(Vec<u8>);
+        );
+        assert_eq!(
+            delegations(&input).unwrap(),
+            [
+                "deref_mut",
+                "borrow_mut",
+                "index_mut",
+                "deref",
+                "borrow",
+                "index"
+            ]
+        );
+
+        let input: DeriveInput = parse_quote!(
+            #[wrapper(deref, deref_mut)]
+            struct S(Vec<u8>);
+        );
+        assert_eq!(delegations(&input).unwrap(), ["deref", "deref_mut"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let input: DeriveInput = parse_quote!(
+            #[wrapper(new, iter)]
+            struct S(Vec<u8>);
+        );
+        assert_eq!(error_of(delegations(&input)), "unknown delegation `iter`");
+
+        let input: DeriveInput = parse_quote!(
+            #[wrapper(core::ops)]
+            struct S(f64);
+        );
+        assert_eq!(
+            error_of(delegations(&input)),
+            "unknown delegation `core::ops`"
+        );
+
+        let input: DeriveInput = parse_quote!(
+            enum E {
+                A(f64),
+            }
+        );
+        assert_eq!(
+            error_of(expand(input)),
+            "`Wrapper` can only be derived for structs"
+        );
+
+        for input in [
+            parse_quote!(
+                struct S;
+            ),
+            parse_quote!(
+                struct S(f64, f64);
+            ),
+            parse_quote!(
+                struct S {
+                    a: f64,
+                    b: f64,
+                }
+            ),
+        ] {
+            assert_eq!(
+                error_of(expand(input)),
+                "`Wrapper` can only be derived for structs with exactly one field"
+            );
+        }
+    }
+}